@@ -0,0 +1,175 @@
+use crate::{Exemplar, Exposition, Labels, Metricfamily, Sample};
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+
+// How far apart two values may be and still be equal, exact by default: `a` and `b` are equal
+// if `a == b`, `|a - b| <= absolute` or `|a - b| <= relative * max(|a|, |b|)`, or if both are NaN
+// and `nan_eq` is set.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Tolerance {
+    relative: f64,
+    absolute: f64,
+    nan_eq: bool,
+}
+
+impl Tolerance {
+    pub fn relative(mut self, relative: f64) -> Self {
+        self.relative = relative;
+        self
+    }
+
+    pub fn absolute(mut self, absolute: f64) -> Self {
+        self.absolute = absolute;
+        self
+    }
+
+    pub fn nan_eq(mut self, nan_eq: bool) -> Self {
+        self.nan_eq = nan_eq;
+        self
+    }
+
+    pub fn eq(&self, a: f64, b: f64) -> bool {
+        if a.is_nan() || b.is_nan() {
+            return self.nan_eq && a.is_nan() && b.is_nan();
+        }
+        if a == b {
+            return true;
+        }
+        let difference = (a - b).abs();
+        difference <= self.absolute || difference <= self.relative * a.abs().max(b.abs())
+    }
+}
+
+// Equality of parsed nodes up to `Tolerance`, see `assert_metric_eq!`. Sample values and exemplar
+// values are compared as numbers, labels as sets of unescaped values whatever their order, and
+// timestamps as numbers without tolerance. Descriptors and the order of families and samples
+// must be the same.
+pub trait ApproxEq {
+    fn approx_eq(&self, other: &Self, tolerance: &Tolerance) -> bool;
+}
+
+impl ApproxEq for f64 {
+    fn approx_eq(&self, other: &Self, tolerance: &Tolerance) -> bool {
+        tolerance.eq(*self, *other)
+    }
+}
+
+impl<I> ApproxEq for Exposition<I>
+where
+    I: AsRef<str>,
+{
+    fn approx_eq(&self, other: &Self, tolerance: &Tolerance) -> bool {
+        let (left, right) = (&self.metricset.metricfamily, &other.metricset.metricfamily);
+        left.len() == right.len()
+            && left
+                .iter()
+                .zip(right)
+                .all(|(left, right)| left.approx_eq(right, tolerance))
+    }
+}
+
+impl<I> ApproxEq for Metricfamily<I>
+where
+    I: AsRef<str>,
+{
+    fn approx_eq(&self, other: &Self, tolerance: &Tolerance) -> bool {
+        self.metric_descriptor.len() == other.metric_descriptor.len()
+            && self
+                .metric_descriptor
+                .iter()
+                .zip(&other.metric_descriptor)
+                .all(|(left, right)| left.raw.as_ref() == right.raw.as_ref())
+            && self.samples().count() == other.samples().count()
+            && self
+                .samples()
+                .zip(other.samples())
+                .all(|(left, right)| left.approx_eq(right, tolerance))
+    }
+}
+
+impl<I> ApproxEq for Sample<I>
+where
+    I: AsRef<str>,
+{
+    fn approx_eq(&self, other: &Self, tolerance: &Tolerance) -> bool {
+        self.metricname.as_ref() == other.metricname.as_ref()
+            && label_set(self.labels.as_deref()) == label_set(other.labels.as_deref())
+            && number_eq(&self.number, &other.number, tolerance)
+            && timestamp_eq(self.timestamp.as_ref(), other.timestamp.as_ref())
+            && match (&self.exemplar, &other.exemplar) {
+                (Some(left), Some(right)) => left.approx_eq(right, tolerance),
+                (None, None) => true,
+                _ => false,
+            }
+    }
+}
+
+impl<I> ApproxEq for Exemplar<I>
+where
+    I: AsRef<str>,
+{
+    fn approx_eq(&self, other: &Self, tolerance: &Tolerance) -> bool {
+        label_set(Some(&self.labels)) == label_set(Some(&other.labels))
+            && number_eq(&self.number, &other.number, tolerance)
+            && timestamp_eq(self.timestamp.as_ref(), other.timestamp.as_ref())
+    }
+}
+
+// Panics unless `left` and `right` are equal up to a `Tolerance`, exact if omitted, e.g.
+// `assert_metric_eq!(exposition, expected, Tolerance::default().relative(1e-9).nan_eq(true))`.
+#[macro_export]
+macro_rules! assert_metric_eq {
+    ($left:expr, $right:expr $(,)?) => {
+        $crate::assert_metric_eq!($left, $right, $crate::approx::Tolerance::default())
+    };
+    ($left:expr, $right:expr, $tolerance:expr $(,)?) => {
+        match (&$left, &$right, &$tolerance) {
+            (left, right, tolerance) => {
+                if !$crate::approx::ApproxEq::approx_eq(left, right, tolerance) {
+                    panic!(
+                        "assertion `left ≈ right` failed\n     left: {left:?}\n    right: {right:?}\ntolerance: {tolerance:?}"
+                    );
+                }
+            }
+        }
+    };
+}
+
+fn label_set<I>(labels: Option<&Labels<I>>) -> BTreeMap<&str, Cow<'_, str>>
+where
+    I: AsRef<str>,
+{
+    labels
+        .into_iter()
+        .flat_map(|labels| &labels.label)
+        .map(|label| (label.label_name.as_ref(), label.escaped_string.unescape()))
+        .collect()
+}
+
+// as numbers if both are, as text otherwise
+fn number_eq<I>(left: &I, right: &I, tolerance: &Tolerance) -> bool
+where
+    I: AsRef<str>,
+{
+    match (
+        crate::parse_number(left.as_ref()),
+        crate::parse_number(right.as_ref()),
+    ) {
+        (Some(left), Some(right)) => tolerance.eq(left, right),
+        _ => left.as_ref() == right.as_ref(),
+    }
+}
+
+fn timestamp_eq<I>(left: Option<&I>, right: Option<&I>) -> bool
+where
+    I: AsRef<str>,
+{
+    match (left, right) {
+        (Some(left), Some(right)) => number_eq(left, right, &Tolerance::default()),
+        (None, None) => true,
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests;
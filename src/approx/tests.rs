@@ -0,0 +1,69 @@
+use super::{ApproxEq, Tolerance};
+use nom::error::Error;
+use nom::{Finish, Parser};
+
+fn parse(input: &str) -> crate::Exposition<&str> {
+    let (_, exposition) = crate::exposition::<_, Error<_>>
+        .parse(input)
+        .finish()
+        .unwrap();
+    exposition
+}
+
+#[rstest::rstest]
+#[case(Tolerance::default(), 1., 1., true)]
+#[case(Tolerance::default(), 0.1 + 0.2, 0.3, false)]
+#[case(Tolerance::default().relative(1e-9), 0.1 + 0.2, 0.3, true)]
+#[case(Tolerance::default().relative(0.01), 100., 101., true)]
+#[case(Tolerance::default().relative(0.01), 100., 102.1, false)]
+#[case(Tolerance::default().absolute(0.5), 1., 1.5, true)]
+#[case(Tolerance::default().absolute(0.5), 1., 1.6, false)]
+#[case(Tolerance::default().absolute(1.), f64::INFINITY, f64::INFINITY, true)]
+#[case(Tolerance::default().absolute(1.), f64::INFINITY, f64::NEG_INFINITY, false)]
+#[case(Tolerance::default(), f64::NAN, f64::NAN, false)]
+#[case(Tolerance::default().nan_eq(true), f64::NAN, f64::NAN, true)]
+#[case(Tolerance::default().nan_eq(true).absolute(f64::INFINITY), f64::NAN, 1., false)]
+fn test_tolerance(
+    #[case] tolerance: Tolerance,
+    #[case] a: f64,
+    #[case] b: f64,
+    #[case] expected: bool,
+) {
+    assert_eq!(tolerance.eq(a, b), expected);
+    assert_eq!(b.approx_eq(&a, &tolerance), expected);
+}
+
+#[rstest::rstest]
+#[case(
+    "a{x=\"1\",y=\"2\"} 0.3 1\n",
+    "a{y=\"2\",x=\"1\"} 0.30000000000000004 1.0\n",
+    true
+)]
+#[case("a{x=\"\\\\\"} NaN\n", "a{x=\"\\\\\"} NaN\n", true)]
+#[case("a 1 # {t=\"u\"} 2.0\n", "a 1 # {t=\"u\"} 2\n", true)]
+#[case("a{x=\"1\"} 1\n", "a{x=\"2\"} 1\n", false)]
+#[case("a 1\n", "a 1 1\n", false)]
+#[case("a 1\n", "a 1.1\n", false)]
+#[case("a 1\n", "a 1\nb 2\n", false)]
+#[case("# HELP a A.\na 1\n", "# HELP a B.\na 1\n", false)]
+fn test_approx_eq(#[case] left: &str, #[case] right: &str, #[case] expected: bool) {
+    let tolerance = Tolerance::default().relative(1e-9).nan_eq(true);
+    let (left, right) = (format!("{left}# EOF\n"), format!("{right}# EOF\n"));
+    assert_eq!(parse(&left).approx_eq(&parse(&right), &tolerance), expected);
+}
+
+#[test]
+fn test_assert_metric_eq() {
+    crate::assert_metric_eq!(parse("a 1\n# EOF\n"), parse("a 1.0\n# EOF\n"));
+    crate::assert_metric_eq!(
+        parse("a 99\n# EOF\n"),
+        parse("a 100\n# EOF\n"),
+        Tolerance::default().absolute(1.),
+    );
+}
+
+#[test]
+#[should_panic(expected = "assertion `left ≈ right` failed")]
+fn test_assert_metric_eq_panic() {
+    crate::assert_metric_eq!(parse("a NaN\n# EOF\n"), parse("a NaN\n# EOF\n"));
+}
@@ -0,0 +1,76 @@
+use crate::{Exposition, Timestamp};
+use std::collections::BTreeMap;
+use std::io::{self, Write};
+use std::time::Duration;
+
+// https://prometheus.io/docs/prometheus/latest/storage/#backfilling-from-openmetrics-format
+//
+// Splits `exposition` into one OpenMetrics document per block of `block_duration`.
+// `create` is called once per non-empty block, in ascending order, with the block start
+// in milliseconds since the Unix epoch. Each document keeps the families in their original
+// order, repeats their descriptors and is terminated by `# EOF`. Timestamps are truncated to
+// milliseconds without rounding, and fail when the milliseconds do not fit in an `i64`.
+pub fn backfill<I, W, F>(
+    exposition: &Exposition<I>,
+    block_duration: Duration,
+    mut create: F,
+) -> io::Result<()>
+where
+    I: AsRef<str>,
+    W: Write,
+    F: FnMut(i64) -> io::Result<W>,
+{
+    let block_duration = i64::try_from(block_duration.as_millis())
+        .ok()
+        .filter(|block_duration| *block_duration > 0)
+        .ok_or_else(|| invalid_input("block duration must be between 1ms and i64::MAX ms"))?;
+
+    let mut blocks = BTreeMap::<_, Vec<_>>::new();
+    for (index, metricfamily) in exposition.metricset.metricfamily.iter().enumerate() {
+        for metric in &metricfamily.metric {
+            for sample in &metric.sample {
+                let timestamp = sample
+                    .timestamp
+                    .as_ref()
+                    .ok_or_else(|| invalid_input("sample without timestamp"))?;
+                let timestamp = Timestamp::parse(timestamp.as_ref())
+                    .and_then(|parsed| {
+                        parsed
+                            .seconds
+                            .checked_mul(1000)?
+                            .checked_add(i64::from(parsed.nanos / 1_000_000))
+                    })
+                    .ok_or_else(|| invalid_input("timestamp out of range"))?;
+                blocks
+                    .entry(timestamp.div_euclid(block_duration) * block_duration)
+                    .or_default()
+                    .push((index, &sample.raw));
+            }
+        }
+    }
+
+    for (start, samples) in blocks {
+        let mut writer = create(start)?;
+        let mut current = None;
+        for (index, raw) in samples {
+            if current != Some(index) {
+                current = Some(index);
+                let metricfamily = &exposition.metricset.metricfamily[index];
+                for metric_descriptor in &metricfamily.metric_descriptor {
+                    writer.write_all(metric_descriptor.raw.as_ref().as_bytes())?;
+                }
+            }
+            writer.write_all(raw.as_ref().as_bytes())?;
+        }
+        writer.write_all(b"# EOF\n")?;
+        writer.flush()?;
+    }
+    Ok(())
+}
+
+fn invalid_input(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, message)
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,86 @@
+use nom::error::Error;
+use nom::{Finish, Parser};
+use std::cell::RefCell;
+use std::io::{self, Write};
+use std::time::Duration;
+
+struct Block<'a>(&'a RefCell<Vec<(i64, Vec<u8>)>>);
+
+impl Write for Block<'_> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.borrow_mut().last_mut().unwrap().1.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn test_backfill() {
+    let input = r#"# TYPE a counter
+a_total 1 0
+a_total 2 3600.5
+a_total 3 7200.001
+# TYPE b gauge
+# UNIT b seconds
+b 4 7199.999
+# EOF
+"#;
+    let (_, exposition) = crate::exposition::<_, Error<_>>
+        .parse(input)
+        .finish()
+        .unwrap();
+
+    let blocks = RefCell::new(Vec::new());
+    crate::backfill::backfill(&exposition, Duration::from_secs(2 * 60 * 60), |start| {
+        blocks.borrow_mut().push((start, Vec::new()));
+        Ok(Block(&blocks))
+    })
+    .unwrap();
+
+    let blocks = blocks
+        .into_inner()
+        .into_iter()
+        .map(|(start, block)| (start, String::from_utf8(block).unwrap()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        blocks,
+        [
+            (
+                0,
+                r#"# TYPE a counter
+a_total 1 0
+a_total 2 3600.5
+# TYPE b gauge
+# UNIT b seconds
+b 4 7199.999
+# EOF
+"#
+                .to_owned(),
+            ),
+            (
+                7_200_000,
+                r#"# TYPE a counter
+a_total 3 7200.001
+# EOF
+"#
+                .to_owned(),
+            ),
+        ],
+    );
+}
+
+#[rstest::rstest]
+#[case("a 1\n# EOF\n")]
+#[case("a 1 1e16\n# EOF\n")]
+#[case("a 1 -9223372036854775808\n# EOF\n")]
+fn test_backfill_invalid_timestamp(#[case] input: &str) {
+    let (_, exposition) = crate::exposition::<_, Error<_>>
+        .parse(input)
+        .finish()
+        .unwrap();
+    let err = crate::backfill::backfill(&exposition, Duration::from_secs(1), |_| Ok(Vec::new()))
+        .unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+}
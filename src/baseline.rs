@@ -0,0 +1,103 @@
+use crate::diagnostic::Diagnostic;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// Known violations that should not be reported again, e.g. while an exporter adopts the
+// linter incrementally. Spans are not recorded so that entries survive unrelated edits.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Baseline {
+    pub entries: Vec<Entry>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Entry {
+    pub rule: String,
+    pub message: String,
+    pub family: Option<String>,
+    pub series: Option<String>,
+    // seconds since the Unix epoch from which the entry no longer suppresses anything
+    pub expires: Option<u64>,
+}
+
+impl Entry {
+    fn matches(&self, diagnostic: &Diagnostic) -> bool {
+        self.rule == diagnostic.rule
+            && self.message == diagnostic.message
+            && self.family == diagnostic.family
+            && self.series == diagnostic.series
+    }
+
+    pub fn is_expired(&self, now: SystemTime) -> bool {
+        self.expires
+            .is_some_and(|expires| unix_time(now) >= expires)
+    }
+}
+
+impl Baseline {
+    pub fn record(diagnostics: &[Diagnostic], expires: Option<SystemTime>) -> Self {
+        let expires = expires.map(unix_time);
+        Self {
+            entries: diagnostics
+                .iter()
+                .map(|diagnostic| Entry {
+                    rule: diagnostic.rule.to_owned(),
+                    message: diagnostic.message.clone(),
+                    family: diagnostic.family.clone(),
+                    series: diagnostic.series.clone(),
+                    expires,
+                })
+                .collect(),
+        }
+    }
+
+    // Removes the diagnostics covered by unexpired entries. Each entry suppresses at most one
+    // diagnostic, so new occurrences of a recorded violation are still reported.
+    pub fn suppress(&self, diagnostics: Vec<Diagnostic>, now: SystemTime) -> Vec<Diagnostic> {
+        let mut used = vec![false; self.entries.len()];
+        diagnostics
+            .into_iter()
+            .filter(|diagnostic| {
+                match self.entries.iter().enumerate().position(|(i, entry)| {
+                    !used[i] && !entry.is_expired(now) && entry.matches(diagnostic)
+                }) {
+                    Some(i) => {
+                        used[i] = true;
+                        false
+                    }
+                    None => true,
+                }
+            })
+            .collect()
+    }
+
+    pub fn expired(&self, now: SystemTime) -> impl Iterator<Item = &Entry> {
+        self.entries
+            .iter()
+            .filter(move |entry| entry.is_expired(now))
+    }
+
+    #[cfg(feature = "serde")]
+    pub fn read<R>(reader: R) -> serde_json::Result<Self>
+    where
+        R: std::io::Read,
+    {
+        serde_json::from_reader(reader)
+    }
+
+    #[cfg(feature = "serde")]
+    pub fn write<W>(&self, writer: W) -> serde_json::Result<()>
+    where
+        W: std::io::Write,
+    {
+        serde_json::to_writer_pretty(writer, self)
+    }
+}
+
+fn unix_time(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs())
+}
+
+#[cfg(test)]
+mod tests;
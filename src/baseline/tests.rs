@@ -0,0 +1,56 @@
+use super::Baseline;
+use crate::diagnostic::{Diagnostic, Severity};
+use std::time::{Duration, UNIX_EPOCH};
+
+fn diagnostic(rule: &'static str, series: &str) -> Diagnostic {
+    Diagnostic {
+        rule,
+        severity: Severity::Warning,
+        message: format!("{rule} violated"),
+        span: Some(0..1),
+        family: Some("a".to_owned()),
+        series: Some(series.to_owned()),
+    }
+}
+
+#[test]
+fn test_suppress() {
+    let now = UNIX_EPOCH + Duration::from_secs(1000);
+    let baseline = Baseline::record(&[diagnostic("x", "a"), diagnostic("y", "a")], None);
+
+    let diagnostics = vec![
+        // recorded
+        Diagnostic {
+            span: Some(10..11),
+            ..diagnostic("x", "a")
+        },
+        // another occurrence of a recorded violation
+        diagnostic("x", "a"),
+        // new series
+        diagnostic("y", "a{b=\"c\"}"),
+    ];
+    assert_eq!(
+        baseline.suppress(diagnostics, now),
+        [diagnostic("x", "a"), diagnostic("y", "a{b=\"c\"}")],
+    );
+}
+
+#[test]
+fn test_expires() {
+    let expires = UNIX_EPOCH + Duration::from_secs(1000);
+    let baseline = Baseline::record(&[diagnostic("x", "a")], Some(expires));
+
+    let before = expires - Duration::from_secs(1);
+    assert!(
+        baseline
+            .suppress(vec![diagnostic("x", "a")], before)
+            .is_empty()
+    );
+    assert_eq!(baseline.expired(before).count(), 0);
+
+    assert_eq!(
+        baseline.suppress(vec![diagnostic("x", "a")], expires),
+        [diagnostic("x", "a")],
+    );
+    assert_eq!(baseline.expired(expires).count(), 1);
+}
@@ -0,0 +1,94 @@
+use crate::clock::{Clock, SystemClock};
+use std::time::{Duration, Instant};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Window {
+    // flush once the batch holds this many items
+    pub max_len: Option<usize>,
+    // flush once the first item of the batch is this old
+    pub max_age: Option<Duration>,
+}
+
+// Groups items (e.g. samples emitted while parsing) into batches and hands every full batch to
+// `flush`. Call `finish` to flush the last, partial batch.
+#[derive(Debug)]
+pub struct Batcher<T, F, C = SystemClock> {
+    window: Window,
+    flush: F,
+    clock: C,
+    batch: Vec<T>,
+    started: Option<Instant>,
+}
+
+impl<T, F, E> Batcher<T, F>
+where
+    F: FnMut(Vec<T>) -> Result<(), E>,
+{
+    pub fn new(window: Window, flush: F) -> Self {
+        Self::with_clock(window, flush, SystemClock)
+    }
+}
+
+impl<T, F, E, C> Batcher<T, F, C>
+where
+    F: FnMut(Vec<T>) -> Result<(), E>,
+    C: Clock,
+{
+    // `push` and `tick` read the time from `clock`.
+    pub fn with_clock(window: Window, flush: F, clock: C) -> Self {
+        Self {
+            window,
+            flush,
+            clock,
+            batch: Vec::new(),
+            started: None,
+        }
+    }
+
+    pub fn push(&mut self, item: T) -> Result<(), E> {
+        self.push_at(item, self.clock.instant())
+    }
+
+    pub fn push_at(&mut self, item: T, now: Instant) -> Result<(), E> {
+        self.tick_at(now)?;
+        self.started.get_or_insert(now);
+        self.batch.push(item);
+        if self
+            .window
+            .max_len
+            .is_some_and(|max_len| self.batch.len() >= max_len)
+        {
+            self.flush()?;
+        }
+        Ok(())
+    }
+
+    // Flushes the batch if its window has expired, for callers that stop pushing for a while.
+    pub fn tick(&mut self) -> Result<(), E> {
+        self.tick_at(self.clock.instant())
+    }
+
+    pub fn tick_at(&mut self, now: Instant) -> Result<(), E> {
+        let expired = self
+            .started
+            .zip(self.window.max_age)
+            .is_some_and(|(started, max_age)| now.saturating_duration_since(started) >= max_age);
+        if expired { self.flush() } else { Ok(()) }
+    }
+
+    pub fn flush(&mut self) -> Result<(), E> {
+        self.started = None;
+        if self.batch.is_empty() {
+            Ok(())
+        } else {
+            (self.flush)(std::mem::take(&mut self.batch))
+        }
+    }
+
+    pub fn finish(mut self) -> Result<(), E> {
+        self.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,73 @@
+use super::{Batcher, Window};
+use crate::clock::MockClock;
+use std::convert::Infallible;
+use std::time::{Duration, Instant, UNIX_EPOCH};
+
+#[test]
+fn test_max_len() {
+    let mut batches = Vec::new();
+    let mut batcher = Batcher::new(
+        Window {
+            max_len: Some(2),
+            max_age: None,
+        },
+        |batch| {
+            batches.push(batch);
+            Ok::<_, Infallible>(())
+        },
+    );
+    for i in 0..5 {
+        batcher.push(i).unwrap();
+    }
+    batcher.finish().unwrap();
+    assert_eq!(batches, [vec![0, 1], vec![2, 3], vec![4]]);
+}
+
+#[test]
+fn test_max_age() {
+    let mut batches = Vec::new();
+    let mut batcher = Batcher::new(
+        Window {
+            max_len: None,
+            max_age: Some(Duration::from_secs(10)),
+        },
+        |batch| {
+            batches.push(batch);
+            Ok::<_, Infallible>(())
+        },
+    );
+    let now = Instant::now();
+    batcher.push_at(0, now).unwrap();
+    batcher.push_at(1, now + Duration::from_secs(5)).unwrap();
+    batcher.push_at(2, now + Duration::from_secs(10)).unwrap();
+    batcher.tick_at(now + Duration::from_secs(15)).unwrap();
+    batcher.tick_at(now + Duration::from_secs(20)).unwrap();
+    batcher.push_at(3, now + Duration::from_secs(25)).unwrap();
+    batcher.finish().unwrap();
+    assert_eq!(batches, [vec![0, 1], vec![2], vec![3]]);
+}
+
+#[test]
+fn test_with_clock() {
+    let clock = MockClock::new(UNIX_EPOCH);
+    let mut batches = Vec::new();
+    let mut batcher = Batcher::with_clock(
+        Window {
+            max_len: None,
+            max_age: Some(Duration::from_secs(1)),
+        },
+        |batch| {
+            batches.push(batch);
+            Ok::<_, Infallible>(())
+        },
+        &clock,
+    );
+    batcher.push(0).unwrap();
+    clock.advance(Duration::from_millis(999));
+    batcher.push(1).unwrap();
+    clock.advance(Duration::from_millis(1));
+    batcher.tick().unwrap();
+    batcher.push(2).unwrap();
+    batcher.finish().unwrap();
+    assert_eq!(batches, [vec![0, 1], vec![2]]);
+}
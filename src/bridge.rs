@@ -0,0 +1,110 @@
+use crate::cancel::{CancellationToken, Cancelled};
+use crate::diagnostic::Diagnostic;
+use crate::{Metricfamily, Shift};
+use nom::bytes::complete::tag;
+use nom::character::complete::char;
+use nom::combinator::{eof, opt};
+use nom::{Finish, Offset, Parser};
+use std::fmt;
+use std::sync::mpsc::{self, Receiver};
+use std::thread::{self, JoinHandle};
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Error {
+    // the span refers to the input
+    Invalid(Diagnostic),
+    // see `Bridge::spawn_with`
+    Cancelled,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Invalid(diagnostic) => write!(f, "{}", diagnostic.message),
+            Self::Cancelled => write!(f, "{Cancelled}"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+// Parses an exposition on a dedicated thread and yields its families, converted to owned
+// strings with spans in `input`, through a channel holding at most `bound` families. The parser
+// waits while the channel is full. A syntax error is yielded as the last item; dropping the
+// bridge stops the parser.
+#[derive(Debug)]
+pub struct Bridge {
+    receiver: Option<Receiver<Result<Metricfamily<String>, Error>>>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl Bridge {
+    pub fn spawn(input: String, bound: usize) -> Self {
+        Self::spawn_with(input, bound, CancellationToken::new())
+    }
+
+    // `spawn`, where the parser yields `Error::Cancelled` as the last item instead of its next
+    // family once `token` is cancelled.
+    pub fn spawn_with(input: String, bound: usize, token: CancellationToken) -> Self {
+        let (sender, receiver) = mpsc::sync_channel(bound);
+        let handle = thread::spawn(move || {
+            let mut rest = input.as_str();
+            loop {
+                if token.is_cancelled() {
+                    let _ = sender.send(Err(Error::Cancelled));
+                    return;
+                }
+                match crate::metricfamily::<_, nom::error::Error<_>>
+                    .parse(rest)
+                    .finish()
+                {
+                    Ok((next, metricfamily)) => {
+                        let mut metricfamily = metricfamily.into_owned();
+                        metricfamily.shift(input.as_str().offset(rest));
+                        rest = next;
+                        if sender.send(Ok(metricfamily)).is_err() {
+                            // the bridge was dropped
+                            return;
+                        }
+                    }
+                    Err(e) => {
+                        let terminated = (tag("# EOF"), opt(char('\n')), eof)
+                            .parse(rest)
+                            .finish()
+                            .map_err(|_: nom::error::Error<_>| ());
+                        if terminated.is_err() {
+                            let diagnostic = Diagnostic::from_error(&input.as_str(), &e);
+                            let _ = sender.send(Err(Error::Invalid(diagnostic)));
+                        }
+                        return;
+                    }
+                }
+            }
+        });
+        Self {
+            receiver: Some(receiver),
+            handle: Some(handle),
+        }
+    }
+}
+
+impl Iterator for Bridge {
+    type Item = Result<Metricfamily<String>, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.receiver.as_ref()?.recv().ok()
+    }
+}
+
+impl Drop for Bridge {
+    fn drop(&mut self) {
+        // a pending `send` fails once the receiver is gone
+        self.receiver.take();
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,62 @@
+use super::{Bridge, Error};
+
+#[test]
+fn test_bridge() {
+    let input = r#"# TYPE a counter
+a_total 1
+# TYPE b gauge
+b 2
+# EOF
+"#;
+    let metricfamily = Bridge::spawn(input.to_owned(), 1)
+        .map(|metricfamily| {
+            let metricfamily = metricfamily.unwrap();
+            metricfamily.metric_descriptor[0].metricname().clone()
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(metricfamily, ["a", "b"]);
+
+    let metricfamily = Bridge::spawn(input.to_owned(), 1).nth(1).unwrap().unwrap();
+    assert_eq!(metricfamily.metric_descriptor[0].range, 27..42);
+    assert_eq!(metricfamily.metric[0].sample[0].range, 42..46);
+}
+
+#[test]
+fn test_bridge_error() {
+    let input = r#"# TYPE a counter
+a_total 1
+b
+# EOF
+"#;
+    let mut bridge = Bridge::spawn(input.to_owned(), 1);
+    assert!(bridge.next().unwrap().is_ok());
+    let Some(Err(Error::Invalid(diagnostic))) = bridge.next() else {
+        panic!();
+    };
+    assert_eq!(diagnostic.span, Some(28..29));
+    assert!(bridge.next().is_none());
+}
+
+#[test]
+fn test_bridge_drop() {
+    let input = "a 1\n".repeat(1000) + "# EOF\n";
+    let mut bridge = Bridge::spawn(input, 1);
+    assert!(bridge.next().unwrap().is_ok());
+    drop(bridge);
+}
+
+#[test]
+fn test_bridge_cancel() {
+    let input = (0..1000)
+        .map(|i| format!("# TYPE a{i} gauge\na{i} 1\n"))
+        .collect::<String>()
+        + "# EOF\n";
+    let token = crate::cancel::CancellationToken::new();
+    let mut bridge = Bridge::spawn_with(input, 1, token.clone());
+    assert!(bridge.next().unwrap().is_ok());
+    token.cancel();
+    let rest = bridge.collect::<Vec<_>>();
+    // the family in the channel and the one being sent, if any, then the error
+    assert!(rest.len() <= 3, "{}", rest.len());
+    assert_eq!(rest.last(), Some(&Err(Error::Cancelled)));
+}
@@ -0,0 +1,46 @@
+use std::fmt;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+// Aborts a long parse from another thread, e.g. once a scrape deadline passed. Clones share the
+// same flag, which the parsers taking a token check as they go and then fail with `Cancelled`:
+// `reader::Families::cancel_on` and `sink::fan_out_with` before every line,
+// `bridge::Bridge::spawn_with` before every family and `streaming::Streaming::cancel_on` before
+// every chunk.
+#[derive(Clone, Debug, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+// e.g. a flag already shared with other tasks
+impl From<Arc<AtomicBool>> for CancellationToken {
+    fn from(flag: Arc<AtomicBool>) -> Self {
+        Self(flag)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Cancelled;
+
+impl fmt::Display for Cancelled {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "the parse was cancelled")
+    }
+}
+
+impl std::error::Error for Cancelled {}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,18 @@
+use super::{CancellationToken, Cancelled};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+#[test]
+fn test_cancel() {
+    let token = CancellationToken::new();
+    let clone = token.clone();
+    assert!(!clone.is_cancelled());
+    token.cancel();
+    assert!(clone.is_cancelled());
+
+    let flag = Arc::new(AtomicBool::new(false));
+    let token = CancellationToken::from(flag.clone());
+    flag.store(true, Ordering::Relaxed);
+    assert!(token.is_cancelled());
+    assert_eq!(Cancelled.to_string(), "the parse was cancelled");
+}
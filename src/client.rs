@@ -0,0 +1,283 @@
+use crate::diagnostic::Diagnostic;
+use crate::model::{self, MetricFamily};
+use crate::{Exposition, Label, MetricType, Metricfamily, Spanned, validate};
+use ::prometheus_client::collector::Collector;
+use ::prometheus_client::encoding::{DescriptorEncoder, NoLabelSet};
+use ::prometheus_client::metrics::MetricType as ClientType;
+use ::prometheus_client::registry::{Registry, Unit};
+use nom::error::Error as NomError;
+use nom::{Finish, Parser};
+use std::fmt;
+
+// https://docs.rs/prometheus-client/0.23
+//
+// Interop with the official client: `validate` checks what a registry encodes, a `Snapshot`
+// registers the families of a parsed exposition into a registry. The client encodes neither
+// summaries nor gaugehistograms nor timestamps, so an exposition with them is `Unsupported`. It
+// writes help texts and label values as they are, so a `Snapshot` holds them escaped. The
+// optional `_sum` of a histogram is 0 when missing, and its count the one of its last bucket.
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Encoded {
+    pub text: String,
+    // spans refer to `text`
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+// Encodes `registry` in the text format and validates the result.
+pub fn validate(registry: &Registry) -> Result<Encoded, fmt::Error> {
+    let mut text = String::new();
+    ::prometheus_client::encoding::text::encode(&mut text, registry)?;
+    let diagnostics = match crate::exposition::<_, NomError<_>>
+        .parse(text.as_str())
+        .finish()
+    {
+        Ok((_, exposition)) => validate::validate(&exposition),
+        Err(e) => vec![Diagnostic::from_error(&text.as_str(), &e)],
+    };
+    Ok(Encoded { text, diagnostics })
+}
+
+#[derive(Debug)]
+pub enum Error {
+    Model(model::Error),
+    Unsupported(String),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Model(e) => write!(f, "{e}"),
+            Self::Unsupported(message) => write!(f, "{message}"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+// The families of a parsed exposition as a `Collector`. The values are taken once by `new`.
+#[derive(Clone, Debug)]
+pub struct Snapshot {
+    families: Vec<Family>,
+}
+
+#[derive(Clone, Debug)]
+struct Family {
+    // without the unit and type suffixes the encoder appends
+    name: String,
+    help: String,
+    unit: Option<Unit>,
+    r#type: ClientType,
+    points: Vec<(Vec<(String, String)>, Point)>,
+}
+
+#[derive(Clone, Debug)]
+enum Point {
+    Counter(f64),
+    Gauge(f64),
+    // buckets with the count of each, not cumulative, the last one bounded by `f64::MAX`
+    Histogram {
+        sum: f64,
+        count: u64,
+        buckets: Vec<(f64, u64)>,
+    },
+    Info,
+}
+
+impl Snapshot {
+    pub fn new<I>(exposition: &Exposition<I>) -> Result<Self, Error>
+    where
+        I: AsRef<str> + Clone,
+    {
+        let families = exposition
+            .metricset
+            .metricfamily
+            .iter()
+            .map(convert)
+            .collect::<Result<_, _>>()?;
+        Ok(Self { families })
+    }
+}
+
+impl Collector for Snapshot {
+    fn encode(&self, mut encoder: DescriptorEncoder) -> Result<(), fmt::Error> {
+        for family in &self.families {
+            let mut metric_encoder = encoder.encode_descriptor(
+                &family.name,
+                &family.help,
+                family.unit.as_ref(),
+                family.r#type,
+            )?;
+            for (labels, point) in &family.points {
+                match point {
+                    Point::Counter(total) => metric_encoder
+                        .encode_family(labels)?
+                        .encode_counter::<NoLabelSet, _, f64>(total, None)?,
+                    Point::Gauge(value) => {
+                        metric_encoder.encode_family(labels)?.encode_gauge(value)?
+                    }
+                    Point::Histogram {
+                        sum,
+                        count,
+                        buckets,
+                    } => metric_encoder
+                        .encode_family(labels)?
+                        .encode_histogram::<NoLabelSet>(*sum, *count, buckets, None)?,
+                    Point::Info => metric_encoder.encode_info(labels)?,
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+// Registers the families of `exposition` into `registry` as one `Snapshot`.
+pub fn register<I>(registry: &mut Registry, exposition: &Exposition<I>) -> Result<(), Error>
+where
+    I: AsRef<str> + Clone,
+{
+    registry.register_collector(Box::new(Snapshot::new(exposition)?));
+    Ok(())
+}
+
+// A stateset becomes a gauge with a sample per state, as Prometheus ingests it.
+fn convert<I>(metricfamily: &Spanned<I, Metricfamily<I>>) -> Result<Family, Error>
+where
+    I: AsRef<str> + Clone,
+{
+    let family = MetricFamily::try_from(metricfamily.value.clone()).map_err(Error::Model)?;
+    let name = family.name.as_ref();
+    let unsupported = |what: &str| Error::Unsupported(format!("`{name}` has {what}"));
+    let metricfamily = &metricfamily.value;
+    if metricfamily
+        .samples()
+        .any(|sample| sample.value.timestamp.is_some())
+    {
+        return Err(unsupported("a timestamp, which the client cannot encode"));
+    }
+
+    let mut points = Vec::new();
+    let r#type = match family.r#type {
+        MetricType::Counter => {
+            for counter in model::counters(metricfamily).map_err(Error::Model)? {
+                points.push((labels(&counter.labels), Point::Counter(counter.total)));
+            }
+            ClientType::Counter
+        }
+        MetricType::Gauge | MetricType::Unknown => {
+            for sample in metricfamily.samples().map(|sample| &sample.value) {
+                let number = sample.number.as_ref();
+                let value = crate::parse_number(number).ok_or_else(|| {
+                    Error::Model(model::Error::InvalidNumber {
+                        metricname: sample.metricname.as_ref().to_owned(),
+                        number: number.to_owned(),
+                    })
+                })?;
+                let labels = sample
+                    .labels
+                    .iter()
+                    .flat_map(|labels| &labels.label)
+                    .map(|label| &label.value)
+                    .collect::<Vec<_>>();
+                points.push((self::labels(&labels), Point::Gauge(value)));
+            }
+            match family.r#type {
+                MetricType::Gauge => ClientType::Gauge,
+                _ => ClientType::Unknown,
+            }
+        }
+        MetricType::Histogram => {
+            for histogram in model::histograms(metricfamily).map_err(Error::Model)? {
+                let mut buckets = Vec::new();
+                let mut previous = 0;
+                for (le, count) in &histogram.buckets {
+                    let count = count_of(*count)
+                        .filter(|count| *count >= previous)
+                        .ok_or_else(|| unsupported(&format!("a bucket count of {count}")))?;
+                    let upper_bound = if *le == f64::INFINITY { f64::MAX } else { *le };
+                    buckets.push((upper_bound, count - previous));
+                    previous = count;
+                }
+                let count = match histogram.count {
+                    Some(count) => count_of(count)
+                        .ok_or_else(|| unsupported(&format!("a count of {count}")))?,
+                    None => previous,
+                };
+                points.push((
+                    labels(&histogram.labels),
+                    Point::Histogram {
+                        sum: histogram.sum.unwrap_or_default(),
+                        count,
+                        buckets,
+                    },
+                ));
+            }
+            ClientType::Histogram
+        }
+        MetricType::Stateset => {
+            for stateset in model::statesets(metricfamily).map_err(Error::Model)? {
+                for (state, enabled) in &stateset.states {
+                    let mut labels = labels(&stateset.labels);
+                    labels.push((name.to_owned(), crate::escape(state)));
+                    points.push((labels, Point::Gauge(f64::from(u8::from(*enabled)))));
+                }
+            }
+            ClientType::Gauge
+        }
+        MetricType::Info => {
+            for info in model::infos(metricfamily).map_err(Error::Model)? {
+                points.push((labels(&info.labels), Point::Info));
+            }
+            ClientType::Info
+        }
+        MetricType::Summary | MetricType::Gaugehistogram => {
+            return Err(unsupported(&format!(
+                "the type {}, which the client cannot encode",
+                family.r#type
+            )));
+        }
+    };
+    let unit = family
+        .unit
+        .as_ref()
+        .map(|unit| Unit::Other(unit.as_ref().to_owned()));
+    let base = match &unit {
+        Some(Unit::Other(unit)) => name
+            .strip_suffix(unit.as_str())
+            .and_then(|name| name.strip_suffix('_')),
+        _ => None,
+    };
+    Ok(Family {
+        name: base.unwrap_or(name).to_owned(),
+        help: family.help.as_ref().map_or_else(
+            || name.to_owned(),
+            |help| crate::escape_help(&help.unescape()),
+        ),
+        unit,
+        r#type,
+        points,
+    })
+}
+
+fn labels<I>(labels: &[&Label<I>]) -> Vec<(String, String)>
+where
+    I: AsRef<str>,
+{
+    labels
+        .iter()
+        .map(|label| {
+            (
+                label.label_name.as_ref().to_owned(),
+                label.escaped_string.raw.as_ref().to_owned(),
+            )
+        })
+        .collect()
+}
+
+// The client counts observations as integers.
+fn count_of(count: f64) -> Option<u64> {
+    (count >= 0. && count.fract() == 0. && count <= u64::MAX as f64).then_some(count as u64)
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,94 @@
+use super::{Error, Snapshot};
+use ::prometheus_client::registry::Registry;
+use nom::error::Error as NomError;
+use nom::{Finish, Parser};
+
+fn registry(input: &str) -> Result<Registry, Error> {
+    let (_, exposition) = crate::exposition::<_, NomError<_>>
+        .parse(input)
+        .finish()
+        .unwrap();
+    let mut registry = Registry::default();
+    super::register(&mut registry, &exposition)?;
+    Ok(registry)
+}
+
+#[test]
+fn test_round_trip() {
+    let input = r#"# TYPE a_seconds counter
+# UNIT a_seconds seconds
+# HELP a_seconds Help.
+a_seconds_total{job="x\"y"} 1.5
+# TYPE b histogram
+b_bucket{le="1"} 2
+b_bucket{le="+Inf"} 3
+b_count 3
+b_sum 4.5
+# TYPE c stateset
+c{c="on"} 1
+c{c="off"} 0
+c{c="a\"b"} 0
+# TYPE d info
+d_info{version="1"} 1
+# TYPE e gauge
+e{a="1"} 7
+e{a="2"} -1
+# EOF
+"#;
+    let encoded = super::validate(&registry(input).unwrap()).unwrap();
+    assert_eq!(encoded.diagnostics, []);
+
+    let (_, exposition) = crate::exposition::<_, NomError<_>>
+        .parse(encoded.text.as_str())
+        .finish()
+        .unwrap();
+    let families = crate::model::metric_families(exposition.metricset.value).unwrap();
+    assert_eq!(
+        families
+            .iter()
+            .map(|family| (family.name, family.r#type))
+            .collect::<Vec<_>>(),
+        [
+            ("a_seconds", crate::MetricType::Counter),
+            ("b", crate::MetricType::Histogram),
+            ("c", crate::MetricType::Gauge),
+            ("d", crate::MetricType::Info),
+            ("e", crate::MetricType::Gauge),
+        ]
+    );
+    assert!(
+        encoded
+            .text
+            .contains("a_seconds_total{job=\"x\\\"y\"} 1.5\n")
+    );
+    assert!(encoded.text.contains("b_bucket{le=\"1.0\"} 2\n"));
+    assert!(encoded.text.contains("c{c=\"a\\\"b\"} 0.0\n"));
+    assert!(encoded.text.contains("b_bucket{le=\"+Inf\"} 3\n"));
+}
+
+#[test]
+fn test_histogram_without_sum() {
+    let input = "# TYPE a histogram\na_bucket{le=\"+Inf\"} 2\n# EOF\n";
+    let encoded = super::validate(&registry(input).unwrap()).unwrap();
+    assert_eq!(encoded.diagnostics, []);
+    assert!(encoded.text.contains("a_sum{} 0.0\n"));
+    assert!(encoded.text.contains("a_count{} 2\n"));
+}
+
+#[rstest::rstest]
+#[case("# TYPE a summary\na_count 1\na_sum 1\n# EOF\n")]
+#[case("# TYPE a gaugehistogram\na_bucket{le=\"+Inf\"} 1\na_gcount 1\na_gsum 1\n# EOF\n")]
+#[case("# TYPE a gauge\na 1 123\n# EOF\n")]
+#[case("# TYPE a histogram\na_bucket{le=\"1\"} 2\na_bucket{le=\"+Inf\"} 1\na_sum 1\n# EOF\n")]
+fn test_unsupported(#[case] input: &str) {
+    assert!(matches!(
+        Snapshot::new(
+            &crate::exposition::<_, NomError<_>>
+                .parse(input)
+                .finish()
+                .unwrap()
+                .1
+        ),
+        Err(Error::Unsupported(_))
+    ));
+}
@@ -0,0 +1,85 @@
+use crate::Timestamp;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant, SystemTime};
+
+// The time of time-dependent features, e.g. `batch::Batcher`, replaceable by `MockClock` to make
+// them deterministic.
+pub trait Clock {
+    // wall-clock time, e.g. for timestamps and expiry dates
+    fn now(&self) -> SystemTime;
+    // monotonic time, e.g. for ages and timeouts
+    fn instant(&self) -> Instant;
+
+    fn timestamp(&self) -> Timestamp {
+        Timestamp::from_system_time(self.now())
+    }
+}
+
+impl<C> Clock for &C
+where
+    C: Clock + ?Sized,
+{
+    fn now(&self) -> SystemTime {
+        (**self).now()
+    }
+
+    fn instant(&self) -> Instant {
+        (**self).instant()
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> SystemTime {
+        SystemTime::now()
+    }
+
+    fn instant(&self) -> Instant {
+        Instant::now()
+    }
+}
+
+// A clock that only moves when advanced. Clones share the time, so that a test keeps one to
+// advance the clock it gave away.
+#[derive(Clone, Debug)]
+pub struct MockClock {
+    start: SystemTime,
+    instant: Instant,
+    // since `start`
+    elapsed_nanos: Arc<AtomicU64>,
+}
+
+impl MockClock {
+    pub fn new(start: SystemTime) -> Self {
+        Self {
+            start,
+            instant: Instant::now(),
+            elapsed_nanos: Arc::default(),
+        }
+    }
+
+    pub fn advance(&self, duration: Duration) {
+        let nanos = u64::try_from(duration.as_nanos()).unwrap_or(u64::MAX);
+        self.elapsed_nanos.fetch_add(nanos, Ordering::Relaxed);
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        Duration::from_nanos(self.elapsed_nanos.load(Ordering::Relaxed))
+    }
+}
+
+impl Clock for MockClock {
+    fn now(&self) -> SystemTime {
+        self.start + self.elapsed()
+    }
+
+    fn instant(&self) -> Instant {
+        self.instant + self.elapsed()
+    }
+}
+
+#[cfg(test)]
+mod tests;
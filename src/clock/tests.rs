@@ -0,0 +1,20 @@
+use super::{Clock, MockClock};
+use crate::Timestamp;
+use std::time::{Duration, UNIX_EPOCH};
+
+#[test]
+fn test_mock_clock() {
+    let clock = MockClock::new(UNIX_EPOCH + Duration::from_secs(10));
+    let instant = clock.instant();
+    clock.clone().advance(Duration::from_millis(1500));
+    assert_eq!(clock.now(), UNIX_EPOCH + Duration::from_millis(11500));
+    assert_eq!(clock.instant() - instant, Duration::from_millis(1500));
+    assert_eq!(
+        clock.timestamp(),
+        Timestamp {
+            seconds: 11,
+            nanos: 500_000_000,
+        },
+    );
+    assert_eq!(clock.timestamp().to_string(), "11.5");
+}
@@ -0,0 +1,30 @@
+// Building blocks for embedding OpenMetrics in larger grammars, e.g. samples within log lines.
+//
+// Every parser here is a plain `fn(I) -> IResult<I, O, E>`, hence a `nom::Parser`, generic over
+// the input and the error type. They only use complete (non-streaming) primitives, never
+// return `Err::Failure` and leave the input untouched on error, so they compose with `alt`,
+// `opt` and `many0`. What each one consumes on success:
+//
+// - `exposition`: the whole document, through `# EOF` and its optional trailing LF
+// - `metricset`: families up to (excluding) `# EOF`
+// - `metricfamily`: descriptor and sample lines, each including its LF
+// - `metric_descriptor`: a `# TYPE`, `# HELP` or `# UNIT` line, including its LF
+// - `metric`: consecutive sample lines, each including its LF
+// - `sample`: one sample line, including its LF
+// - `exemplar`: ` # {...} value [timestamp]`, excluding the LF
+// - `labels`: `{...}`, including the braces
+// - `label`: `name="value"`
+// - `escaped_string`: the escaped content between the quotes, excluding them
+// - `help_escaped_string`: the escaped HELP text up to (excluding) the LF
+// - `metric_type`, `metricname`, `label_name`, `number`, `realnumber`, `timestamp`: the token
+//   only
+// - `number_value`, `timestamp_value`: the same token as `number` and `timestamp`, yielding its
+//   value
+pub use crate::{
+    escaped_string, exemplar, exposition, help_escaped_string, label, label_name, labels, metric,
+    metric_descriptor, metric_type, metricfamily, metricname, metricset, number, number_value,
+    realnumber, sample, timestamp, timestamp_value,
+};
+
+#[cfg(test)]
+mod tests;
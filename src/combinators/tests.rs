@@ -0,0 +1,54 @@
+use super::{labels, metricname, sample};
+use nom::bytes::complete::{tag, take_until};
+use nom::character::complete::char;
+use nom::combinator::opt;
+use nom::error::Error;
+use nom::multi::many0;
+use nom::sequence::{delimited, preceded};
+use nom::{Finish, Parser};
+
+#[test]
+fn test_sample_in_log_line() {
+    let input =
+        "2024-01-01T00:00:00Z INFO scrape done: a_total{job=\"x\"} 1 # {trace_id=\"t\"} 1\n";
+    let (rest, sample) = preceded((take_until(": "), tag(": ")), sample::<_, Error<_>>)
+        .parse(input)
+        .finish()
+        .unwrap();
+    assert_eq!(rest, "");
+    assert_eq!(sample.metricname, "a_total");
+    assert!(sample.exemplar.is_some());
+}
+
+#[test]
+fn test_many_samples() {
+    let input = "a 1\nb 2\nrest";
+    let (rest, samples) = many0(sample::<_, Error<_>>).parse(input).finish().unwrap();
+    assert_eq!(rest, "rest");
+    assert_eq!(samples.len(), 2);
+}
+
+#[test]
+fn test_selector() {
+    // a PromQL-like selector `name{labels}` wrapped in brackets
+    let input = "[a_total{job=\"x\"}]";
+    let (rest, (metricname, labels)) = delimited(
+        char('['),
+        (metricname::<_, Error<_>>, opt(labels)),
+        char(']'),
+    )
+    .parse(input)
+    .finish()
+    .unwrap();
+    assert_eq!(rest, "");
+    assert_eq!(metricname, "a_total");
+    assert_eq!(labels.unwrap().label.len(), 1);
+}
+
+#[test]
+fn test_error_keeps_input() {
+    let input = "a{job=x} 1\n";
+    let (rest, sample) = opt(sample::<_, Error<_>>).parse(input).finish().unwrap();
+    assert_eq!(rest, input);
+    assert!(sample.is_none());
+}
@@ -0,0 +1,283 @@
+use crate::lint;
+use nom::error::Error;
+use nom::{Input, Offset};
+use std::collections::BTreeMap;
+use std::ops::Range;
+use std::process::ExitCode;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "lowercase")
+)]
+pub enum Severity {
+    Info,
+    Warning,
+    Error,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Diagnostic {
+    pub rule: &'static str,
+    pub severity: Severity,
+    pub message: String,
+    // byte range in the parsed input
+    pub span: Option<Range<usize>>,
+    pub family: Option<String>,
+    // the sample's metricname and labels as written, e.g. `foo_total{a="b"}`
+    pub series: Option<String>,
+}
+
+pub const SYNTAX: &str = "syntax";
+
+impl Diagnostic {
+    pub fn from_error<I>(input: &I, error: &Error<I>) -> Self
+    where
+        I: Input + Offset,
+    {
+        let start = input.offset(&error.input);
+        Self {
+            rule: SYNTAX,
+            severity: Severity::Error,
+            message: format!("invalid exposition ({})", error.code.description()),
+            span: Some(start..start + error.input.input_len().min(1)),
+            family: None,
+            series: None,
+        }
+    }
+
+    // The stable code of the rule, if it is one of this crate.
+    pub fn code(&self) -> Option<&'static str> {
+        match self.rule {
+            SYNTAX => Some("OM-SYN-001"),
+            lint::LABEL_POLICY => Some("OM-SEM-001"),
+            lint::RESERVED_SUFFIX => Some("OM-SEM-002"),
+            lint::HELP_UNIT_CONSISTENCY => Some("OM-SEM-003"),
+            lint::LABEL_VALUE_LENGTH => Some("OM-SEM-004"),
+            lint::JOB_INSTANCE => Some("OM-SEM-005"),
+            lint::INTERLEAVED_FAMILIES => Some("OM-SEM-006"),
+            lint::MISSING_EOF => Some("OM-SYN-002"),
+            crate::scrape::SCRAPE_TIMEOUT => Some("OM-SCR-001"),
+            crate::validate::METADATA_ORDER => Some("OM-VAL-001"),
+            crate::validate::FAMILY_PREFIX => Some("OM-VAL-002"),
+            crate::validate::NAME_CLASH => Some("OM-VAL-003"),
+            crate::validate::EXEMPLAR_LENGTH => Some("OM-VAL-004"),
+            crate::validate::COUNTER_VALUE => Some("OM-VAL-005"),
+            crate::validate::EXEMPLAR_PLACEMENT => Some("OM-VAL-006"),
+            _ => None,
+        }
+    }
+}
+
+// Identifies a diagnostic rule (`Diagnostic::code`), a parse error (`error::Kind::code`) or a
+// model error (`model::Error::code`).
+// Codes are never renumbered nor reused, so that tooling can rely on them instead of messages.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Code {
+    pub code: &'static str,
+    pub description: &'static str,
+}
+
+pub const CODES: &[Code] = &[
+    Code {
+        code: "OM-SYN-001",
+        description: "The input does not match the OpenMetrics grammar.",
+    },
+    Code {
+        code: "OM-SYN-002",
+        description: "The input ends without `# EOF`.",
+    },
+    Code {
+        code: "OM-SYN-003",
+        description: "A family is described again with another TYPE, HELP or UNIT.",
+    },
+    Code {
+        code: "OM-SEM-001",
+        description: "A label name or value violates the configured label policy.",
+    },
+    Code {
+        code: "OM-SEM-002",
+        description: "A metric name ends with a suffix reserved for another type.",
+    },
+    Code {
+        code: "OM-SEM-003",
+        description: "Related families disagree on their UNIT, or a HELP names another unit.",
+    },
+    Code {
+        code: "OM-SEM-004",
+        description: "A label value is longer than the configured maximum.",
+    },
+    Code {
+        code: "OM-SEM-005",
+        description: "Samples disagree on their job or instance label.",
+    },
+    Code {
+        code: "OM-SEM-006",
+        description: "A family continues after lines of other families.",
+    },
+    Code {
+        code: "OM-SEM-101",
+        description: "A family has neither descriptors nor samples.",
+    },
+    Code {
+        code: "OM-SEM-102",
+        description: "Descriptors of a family name different metrics.",
+    },
+    Code {
+        code: "OM-SEM-103",
+        description: "A family has more than one TYPE, HELP or UNIT.",
+    },
+    Code {
+        code: "OM-SEM-104",
+        description: "A metric name does not end with its unit.",
+    },
+    Code {
+        code: "OM-SEM-105",
+        description: "A sample name does not belong to its family.",
+    },
+    Code {
+        code: "OM-SEM-106",
+        description: "A family is not of the type it is read as.",
+    },
+    Code {
+        code: "OM-SEM-107",
+        description: "A sample lacks a label its type requires.",
+    },
+    Code {
+        code: "OM-SEM-108",
+        description: "A sample value or label is not a number.",
+    },
+    Code {
+        code: "OM-SEM-109",
+        description: "A metric point lacks a sample its type requires.",
+    },
+    Code {
+        code: "OM-SEM-110",
+        description: "A stateset sample lacks the label named after its family.",
+    },
+    Code {
+        code: "OM-SEM-111",
+        description: "A stateset sample value is neither 0 nor 1.",
+    },
+    Code {
+        code: "OM-SEM-112",
+        description: "An info sample value is not 1.",
+    },
+    Code {
+        code: "OM-SEM-113",
+        description: "An info labelset is exposed more than once.",
+    },
+    Code {
+        code: "OM-SEM-114",
+        description: "A summary metric point exposes a quantile more than once.",
+    },
+    Code {
+        code: "OM-SEM-115",
+        description: "A summary metric point lacks a quantile exposed by the others.",
+    },
+    Code {
+        code: "OM-SCR-001",
+        description: "The scrape did not finish within its timeout.",
+    },
+    Code {
+        code: "OM-VAL-001",
+        description: "A descriptor follows the samples of its family.",
+    },
+    Code {
+        code: "OM-VAL-002",
+        description: "A sample is named after its family with a suffix its type does not have.",
+    },
+    Code {
+        code: "OM-VAL-003",
+        description: "A family is named like a sample of another family.",
+    },
+    Code {
+        code: "OM-VAL-004",
+        description: "The labels of an exemplar are longer than 128 characters.",
+    },
+    Code {
+        code: "OM-VAL-005",
+        description: "A counter value is negative or not finite, \
+                      or its created value is not a timestamp.",
+    },
+    Code {
+        code: "OM-VAL-006",
+        description: "An exemplar is on a sample other than a counter total or a histogram bucket.",
+    },
+];
+
+pub fn describe(code: &str) -> Option<&'static Code> {
+    CODES.iter().find(|registered| registered.code == code)
+}
+
+// Decides whether a run fails, e.g. `Policy { deny: Severity::Warning }` behaves like
+// `--deny warnings`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Policy {
+    pub deny: Severity,
+}
+
+impl Default for Policy {
+    fn default() -> Self {
+        Self {
+            deny: Severity::Error,
+        }
+    }
+}
+
+impl Policy {
+    pub fn summarize(&self, diagnostics: &[Diagnostic]) -> Summary {
+        let mut summary = Summary::default();
+        for diagnostic in diagnostics {
+            let counts = summary.rules.entry(diagnostic.rule).or_default();
+            match diagnostic.severity {
+                Severity::Info => counts.info += 1,
+                Severity::Warning => counts.warning += 1,
+                Severity::Error => counts.error += 1,
+            }
+            summary.failed |= diagnostic.severity >= self.deny;
+        }
+        summary
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Summary {
+    pub rules: BTreeMap<&'static str, Counts>,
+    pub failed: bool,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Counts {
+    pub info: usize,
+    pub warning: usize,
+    pub error: usize,
+}
+
+impl Summary {
+    pub fn exit_code(&self) -> ExitCode {
+        if self.failed {
+            ExitCode::FAILURE
+        } else {
+            ExitCode::SUCCESS
+        }
+    }
+}
+
+// One JSON object per line, ready to be shipped to a log pipeline.
+#[cfg(feature = "serde")]
+pub fn to_json_lines(diagnostics: &[Diagnostic]) -> serde_json::Result<String> {
+    let mut lines = String::new();
+    for diagnostic in diagnostics {
+        lines.push_str(&serde_json::to_string(diagnostic)?);
+        lines.push('\n');
+    }
+    Ok(lines)
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,138 @@
+use super::{Counts, Diagnostic, Policy, Severity};
+use nom::error::Error;
+use nom::{Finish, Parser};
+
+#[test]
+fn test_from_error() {
+    let input = "a 1\nb{c=1} 2\n# EOF\n";
+    let error = crate::exposition::<_, Error<_>>
+        .parse(input)
+        .finish()
+        .unwrap_err();
+    let diagnostic = Diagnostic::from_error(&input, &error);
+    assert_eq!(diagnostic.rule, super::SYNTAX);
+    assert_eq!(diagnostic.severity, Severity::Error);
+    assert_eq!(diagnostic.span, Some(4..5));
+}
+
+#[rstest::rstest]
+#[case(Severity::Error, false)]
+#[case(Severity::Warning, true)]
+#[case(Severity::Info, true)]
+fn test_policy(#[case] deny: Severity, #[case] failed: bool) {
+    let diagnostic = |rule, severity| Diagnostic {
+        rule,
+        severity,
+        message: String::new(),
+        span: None,
+        family: None,
+        series: None,
+    };
+    let diagnostics = [
+        diagnostic("a", Severity::Warning),
+        diagnostic("a", Severity::Warning),
+        diagnostic("b", Severity::Info),
+    ];
+
+    let summary = Policy { deny }.summarize(&diagnostics);
+    assert_eq!(summary.failed, failed);
+    assert_eq!(
+        summary.rules.into_iter().collect::<Vec<_>>(),
+        [
+            (
+                "a",
+                Counts {
+                    warning: 2,
+                    ..Counts::default()
+                },
+            ),
+            (
+                "b",
+                Counts {
+                    info: 1,
+                    ..Counts::default()
+                },
+            ),
+        ],
+    );
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_to_json_lines() {
+    let diagnostics = [
+        Diagnostic {
+            rule: "a",
+            severity: Severity::Warning,
+            message: "b".to_owned(),
+            span: Some(1..2),
+            family: Some("c".to_owned()),
+            series: Some("c_total{d=\"e\"}".to_owned()),
+        },
+        Diagnostic {
+            rule: "f",
+            severity: Severity::Info,
+            message: "g".to_owned(),
+            span: None,
+            family: None,
+            series: None,
+        },
+    ];
+    assert_eq!(
+        super::to_json_lines(&diagnostics).unwrap(),
+        r#"{"rule":"a","severity":"warning","message":"b","span":{"start":1,"end":2},"family":"c","series":"c_total{d=\"e\"}"}
+{"rule":"f","severity":"info","message":"g","span":null,"family":null,"series":null}
+"#,
+    );
+}
+
+#[test]
+fn test_codes() {
+    for (i, code) in super::CODES.iter().enumerate() {
+        assert!(
+            super::CODES[..i]
+                .iter()
+                .all(|registered| registered.code != code.code),
+            "{} is registered twice",
+            code.code,
+        );
+        assert_eq!(super::describe(code.code), Some(code));
+    }
+    assert_eq!(super::describe("OM-SYN-000"), None);
+
+    let rules = [
+        super::SYNTAX,
+        crate::lint::LABEL_POLICY,
+        crate::lint::RESERVED_SUFFIX,
+        crate::lint::HELP_UNIT_CONSISTENCY,
+        crate::lint::LABEL_VALUE_LENGTH,
+        crate::lint::JOB_INSTANCE,
+        crate::lint::INTERLEAVED_FAMILIES,
+        crate::lint::MISSING_EOF,
+        crate::scrape::SCRAPE_TIMEOUT,
+        crate::validate::METADATA_ORDER,
+        crate::validate::FAMILY_PREFIX,
+        crate::validate::NAME_CLASH,
+        crate::validate::EXEMPLAR_LENGTH,
+        crate::validate::COUNTER_VALUE,
+        crate::validate::EXEMPLAR_PLACEMENT,
+    ];
+    for rule in rules {
+        let diagnostic = Diagnostic {
+            rule,
+            severity: Severity::Error,
+            message: String::new(),
+            span: None,
+            family: None,
+            series: None,
+        };
+        assert!(super::describe(diagnostic.code().unwrap()).is_some());
+    }
+    for kind in [
+        crate::error::Kind::ExpectedNumber,
+        crate::error::Kind::MissingEof,
+        crate::error::Kind::ConflictingDescriptor,
+    ] {
+        assert!(super::describe(kind.code()).is_some());
+    }
+}
@@ -0,0 +1,240 @@
+use crate::lint;
+use crate::{Exposition, Metricset, Sample};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::io::{self, Write};
+
+// A series as interned strings: the metricname and the (name, value) pairs of its labels,
+// sorted so that the label order in the exposition does not matter.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Series {
+    pub metricname: u32,
+    pub labels: Vec<(u32, u32)>,
+}
+
+// Interns metricnames, label names and (unescaped) label values. IDs are stable for the
+// lifetime of the dictionary, so it can be reused across scrapes of the same target.
+#[derive(Clone, Debug, Default)]
+pub struct Dictionary {
+    ids: HashMap<String, u32>,
+    strings: Vec<String>,
+}
+
+impl Dictionary {
+    // `None` once all `u32` IDs are taken.
+    pub fn intern(&mut self, s: &str) -> Option<u32> {
+        if let Some(id) = self.ids.get(s) {
+            return Some(*id);
+        }
+        let id = u32::try_from(self.strings.len()).ok()?;
+        self.ids.insert(s.to_owned(), id);
+        self.strings.push(s.to_owned());
+        Some(id)
+    }
+
+    pub fn get(&self, s: &str) -> Option<u32> {
+        self.ids.get(s).copied()
+    }
+
+    pub fn resolve(&self, id: u32) -> Option<&str> {
+        self.strings.get(id as usize).map(String::as_str)
+    }
+
+    pub fn len(&self) -> usize {
+        self.strings.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.strings.is_empty()
+    }
+
+    // `None` once all `u32` IDs are taken.
+    pub fn series<I>(&mut self, sample: &Sample<I>) -> Option<Series>
+    where
+        I: AsRef<str>,
+    {
+        let metricname = self.intern(sample.metricname.as_ref())?;
+        let mut labels = sample
+            .labels
+            .iter()
+            .flat_map(|labels| &labels.label)
+            .map(|label| {
+                Some((
+                    self.intern(label.label_name.as_ref())?,
+                    self.intern(&label.escaped_string.unescape())?,
+                ))
+            })
+            .collect::<Option<Vec<_>>>()?;
+        labels.sort_unstable();
+        Some(Series { metricname, labels })
+    }
+}
+
+// Series that appeared or disappeared since the previous scrape, by ID.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Churn {
+    pub new: Vec<u64>,
+    pub disappeared: Vec<u64>,
+}
+
+// How `SeriesTracker::write_stale` marks a series as stale.
+// Prometheus uses a NaN with a dedicated bit pattern, which the text format cannot carry.
+#[derive(Clone, Debug, PartialEq)]
+pub enum StaleMarker {
+    // the series with `NaN` as its value
+    NaN,
+    // the series with an extra label, e.g. `__stale__="true"`, and `NaN` as its value
+    Label { name: String, value: String },
+}
+
+// Assigns every series a stable ID on first sight and tracks which series the latest scrape
+// exposed.
+#[derive(Clone, Debug, Default)]
+pub struct SeriesTracker {
+    dictionary: Dictionary,
+    ids: HashMap<Series, u64>,
+    series: Vec<Series>,
+    // the family name of every series, by ID
+    family: Vec<u32>,
+    // the descriptor lines of every family when last seen, by family name
+    descriptors: HashMap<u32, String>,
+    current: BTreeSet<u64>,
+}
+
+impl SeriesTracker {
+    // `None`, leaving the latest scrape as it was, once the dictionary runs out of IDs.
+    pub fn observe<I>(&mut self, metricset: &Metricset<I>) -> Option<Churn>
+    where
+        I: AsRef<str>,
+    {
+        let mut current = BTreeSet::new();
+        let mut descriptors = HashMap::<_, String>::new();
+        for line in lint::family_lines(metricset) {
+            let family = self.dictionary.intern(line.name)?;
+            let Some(sample) = line.sample else {
+                descriptors
+                    .entry(family)
+                    .or_default()
+                    .push_str(line.raw.as_ref());
+                continue;
+            };
+            let series = self.dictionary.series(sample)?;
+            let id = match self.ids.get(&series) {
+                Some(id) => *id,
+                None => {
+                    let id = self.series.len() as u64;
+                    self.ids.insert(series.clone(), id);
+                    self.series.push(series);
+                    self.family.push(family);
+                    id
+                }
+            };
+            if let Some(entry) = usize::try_from(id)
+                .ok()
+                .and_then(|index| self.family.get_mut(index))
+            {
+                *entry = family;
+            }
+            current.insert(id);
+        }
+        self.descriptors.extend(descriptors);
+        let churn = Churn {
+            new: current.difference(&self.current).copied().collect(),
+            disappeared: self.current.difference(&current).copied().collect(),
+        };
+        self.current = current;
+        Some(churn)
+    }
+
+    pub fn id(&self, series: &Series) -> Option<u64> {
+        self.ids.get(series).copied()
+    }
+
+    pub fn series(&self, id: u64) -> Option<&Series> {
+        self.series.get(usize::try_from(id).ok()?)
+    }
+
+    pub fn dictionary(&self) -> &Dictionary {
+        &self.dictionary
+    }
+
+    // Writes `exposition`, the one observed last, with a sample per disappeared series of
+    // `churn`. A stale sample follows the last line of its family if the family is still exposed,
+    // or else is written before `# EOF` in a family of its own with the descriptors it was last
+    // seen with, so that families are neither split nor renamed.
+    pub fn write_stale<I, W>(
+        &self,
+        exposition: &Exposition<I>,
+        churn: &Churn,
+        marker: &StaleMarker,
+        mut writer: W,
+    ) -> io::Result<()>
+    where
+        I: AsRef<str>,
+        W: Write,
+    {
+        // family name -> stale samples
+        let mut stale = BTreeMap::<_, String>::new();
+        for id in &churn.disappeared {
+            let index = usize::try_from(*id).ok();
+            let (Some(series), Some(family)) = (
+                index.and_then(|index| self.series.get(index)),
+                index.and_then(|index| self.family.get(index)),
+            ) else {
+                continue;
+            };
+            stale
+                .entry(*family)
+                .or_default()
+                .push_str(&self.stale_line(series, marker));
+        }
+
+        let source = exposition.metricset.raw.as_ref();
+        let start = exposition.metricset.range.start;
+        let mut ends = HashMap::new();
+        for line in lint::family_lines(&exposition.metricset) {
+            ends.insert(line.name, line.range.end - start);
+        }
+        let mut insertions = ends
+            .into_iter()
+            .filter_map(|(name, end)| Some((end, stale.remove(&self.dictionary.get(name)?)?)))
+            .collect::<Vec<_>>();
+        insertions.sort_unstable();
+        let mut position = 0;
+        for (end, samples) in insertions {
+            writer.write_all(&source.as_bytes()[position..end])?;
+            writer.write_all(samples.as_bytes())?;
+            position = end;
+        }
+        writer.write_all(&source.as_bytes()[position..])?;
+        for (family, samples) in stale {
+            if let Some(descriptors) = self.descriptors.get(&family) {
+                writer.write_all(descriptors.as_bytes())?;
+            }
+            writer.write_all(samples.as_bytes())?;
+        }
+        writer.write_all(b"# EOF\n")
+    }
+
+    fn stale_line(&self, series: &Series, marker: &StaleMarker) -> String {
+        let resolve = |id| self.dictionary.resolve(id).unwrap_or_default();
+        let mut labels = series
+            .labels
+            .iter()
+            .map(|(name, value)| {
+                format!("{}=\"{}\"", resolve(*name), crate::escape(resolve(*value)))
+            })
+            .collect::<Vec<_>>();
+        if let StaleMarker::Label { name, value } = marker {
+            labels.push(format!("{name}=\"{}\"", crate::escape(value)));
+        }
+        let mut line = resolve(series.metricname).to_owned();
+        if !labels.is_empty() {
+            line.push_str(&format!("{{{}}}", labels.join(",")));
+        }
+        line.push_str(" NaN\n");
+        line
+    }
+}
+
+#[cfg(test)]
+mod tests;
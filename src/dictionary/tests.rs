@@ -0,0 +1,122 @@
+use super::{Churn, Dictionary, Series, SeriesTracker, StaleMarker};
+use nom::error::Error;
+use nom::{Finish, Parser};
+
+#[test]
+fn test_series() {
+    let mut dictionary = Dictionary::default();
+    let mut series = Vec::new();
+    for input in [
+        "a{job=\"x\",instance=\"i\"} 1\n# EOF\n",
+        "a{instance=\"i\",job=\"x\"} 2\nb{job=\"x\"} 3\n# EOF\n",
+    ] {
+        let (_, exposition) = crate::exposition::<_, Error<_>>
+            .parse(input)
+            .finish()
+            .unwrap();
+        for metricfamily in &exposition.metricset.value.metricfamily {
+            for sample in metricfamily.samples() {
+                series.push(dictionary.series(sample).unwrap());
+            }
+        }
+    }
+    assert_eq!(
+        series,
+        [
+            Series {
+                metricname: 0,
+                labels: vec![(1, 2), (3, 4)],
+            },
+            Series {
+                metricname: 0,
+                labels: vec![(1, 2), (3, 4)],
+            },
+            Series {
+                metricname: 5,
+                labels: vec![(1, 2)],
+            },
+        ],
+    );
+    assert_eq!(dictionary.len(), 6);
+    assert_eq!(dictionary.resolve(4), Some("i"));
+    assert_eq!(dictionary.get("b"), Some(5));
+}
+
+#[test]
+fn test_series_tracker() {
+    let mut tracker = SeriesTracker::default();
+    let mut churn = Vec::new();
+    for input in [
+        "a{job=\"x\"} 1\na{job=\"y\"} 1\n# EOF\n",
+        "a{job=\"y\"} 1\na{job=\"z\"} 1\n# EOF\n",
+        "a{job=\"x\"} 1\n# EOF\n",
+    ] {
+        let (_, exposition) = crate::exposition::<_, Error<_>>
+            .parse(input)
+            .finish()
+            .unwrap();
+        churn.push(tracker.observe(&exposition.metricset.value).unwrap());
+    }
+    assert_eq!(
+        churn,
+        [
+            Churn {
+                new: vec![0, 1],
+                disappeared: vec![],
+            },
+            Churn {
+                new: vec![2],
+                disappeared: vec![0],
+            },
+            Churn {
+                new: vec![0],
+                disappeared: vec![1, 2],
+            },
+        ],
+    );
+    let series = tracker.series(2).unwrap();
+    assert_eq!(tracker.id(series), Some(2));
+    assert_eq!(tracker.dictionary().resolve(series.labels[0].1), Some("z"));
+}
+
+#[rstest::rstest]
+#[case(
+    StaleMarker::NaN,
+    "# TYPE a gauge\na{job=\"y\"} 2\na{job=\"x\\\"\"} NaN\nc 1\n\
+     # TYPE b counter\nb_total NaN\nd NaN\n# EOF\n"
+)]
+#[case(
+    StaleMarker::Label { name: "__stale__".to_owned(), value: "true".to_owned() },
+    "# TYPE a gauge\na{job=\"y\"} 2\na{job=\"x\\\"\",__stale__=\"true\"} NaN\nc 1\n\
+     # TYPE b counter\nb_total{__stale__=\"true\"} NaN\nd{__stale__=\"true\"} NaN\n# EOF\n",
+)]
+fn test_write_stale(#[case] marker: StaleMarker, #[case] expected: &str) {
+    let mut tracker = SeriesTracker::default();
+    let mut churn = None;
+    let inputs = [
+        "# TYPE a gauge\na{job=\"x\\\"\"} 1\nc 1\n# TYPE b counter\nb_total 1\nd 1\n# EOF\n",
+        "# TYPE a gauge\na{job=\"y\"} 2\nc 1\n# EOF\n",
+    ];
+    let mut last = None;
+    for input in inputs {
+        let (_, exposition) = crate::exposition::<_, Error<_>>
+            .parse(input)
+            .finish()
+            .unwrap();
+        churn = Some(tracker.observe(&exposition.metricset.value).unwrap());
+        last = Some(exposition);
+    }
+    let mut output = Vec::new();
+    tracker
+        .write_stale(&last.unwrap(), &churn.unwrap(), &marker, &mut output)
+        .unwrap();
+    let output = String::from_utf8(output).unwrap();
+    assert_eq!(output, expected);
+
+    let (_, exposition) = crate::exposition::<_, Error<_>>
+        .parse(output.as_str())
+        .finish()
+        .unwrap();
+    assert_eq!(crate::lint::interleaved_families(&exposition), []);
+    assert_eq!(crate::validate::name_clash(&exposition), []);
+}
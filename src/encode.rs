@@ -0,0 +1,237 @@
+use crate::{
+    EscapedString, EscapedStringFragment, Exemplar, Exposition, Extension, HelpEscapedString,
+    HelpEscapedStringFragment, Label, Labels, Metric, MetricDescriptor, MetricType, Metricfamily,
+    Metricset, Sample, Spanned,
+};
+use std::fmt::{self, Display};
+use std::io::{self, Write};
+
+// Writes OpenMetrics text built from the nodes rather than from their raw input, so that edited
+// or constructed trees are encoded as well. Escape sequences are written as parsed. Extension
+// lines are written before the families, as their relative order is not kept in the tree;
+// comments are not written.
+pub fn encode<T, W>(value: &T, mut sink: W) -> io::Result<()>
+where
+    T: Display + ?Sized,
+    W: Write,
+{
+    write!(sink, "{value}")
+}
+
+impl<I, T> Display for Spanned<I, T>
+where
+    T: Display,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.value.fmt(f)
+    }
+}
+
+impl<I> Display for Exposition<I>
+where
+    I: AsRef<str>,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}# EOF", self.metricset.value)?;
+        // keeps the framing of a parsed exposition
+        if self.eof.as_ref() == "# EOF" {
+            return Ok(());
+        }
+        writeln!(f)
+    }
+}
+
+impl<I> Display for Metricset<I>
+where
+    I: AsRef<str>,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for extension in &self.extension {
+            write!(f, "{extension}")?;
+        }
+        for metricfamily in &self.metricfamily {
+            write!(f, "{metricfamily}")?;
+        }
+        Ok(())
+    }
+}
+
+impl<I> Display for Extension<I>
+where
+    I: AsRef<str>,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.body.as_ref() {
+            "" => writeln!(f, "# {}", self.keyword.as_ref()),
+            body => writeln!(f, "# {} {body}", self.keyword.as_ref()),
+        }
+    }
+}
+
+impl<I> Display for Metricfamily<I>
+where
+    I: AsRef<str>,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for metric_descriptor in &self.metric_descriptor {
+            write!(f, "{metric_descriptor}")?;
+        }
+        for metric in &self.metric {
+            write!(f, "{metric}")?;
+        }
+        Ok(())
+    }
+}
+
+impl<I> Display for MetricDescriptor<I>
+where
+    I: AsRef<str>,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Type {
+                metricname,
+                metric_type,
+            } => writeln!(f, "# TYPE {} {metric_type}", metricname.as_ref()),
+            Self::Help {
+                metricname,
+                escaped_string,
+            } => writeln!(f, "# HELP {} {escaped_string}", metricname.as_ref()),
+            Self::Unit {
+                metricname,
+                metricname_char,
+            } => writeln!(
+                f,
+                "# UNIT {} {}",
+                metricname.as_ref(),
+                metricname_char.as_ref(),
+            ),
+        }
+    }
+}
+
+impl Display for MetricType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Self::Counter => crate::COUNTER,
+            Self::Gauge => crate::GAUGE,
+            Self::Histogram => crate::HISTOGRAM,
+            Self::Gaugehistogram => crate::GAUGEHISTOGRAM,
+            Self::Stateset => crate::STATESET,
+            Self::Info => crate::INFO,
+            Self::Summary => crate::SUMMARY,
+            Self::Unknown => crate::UNKNOWN,
+        })
+    }
+}
+
+impl<I> Display for Metric<I>
+where
+    I: AsRef<str>,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for sample in &self.sample {
+            write!(f, "{sample}")?;
+        }
+        Ok(())
+    }
+}
+
+impl<I> Display for Sample<I>
+where
+    I: AsRef<str>,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.metricname.as_ref())?;
+        if let Some(labels) = &self.labels {
+            write!(f, "{labels}")?;
+        }
+        write!(f, " {}", self.number.as_ref())?;
+        if let Some(timestamp) = &self.timestamp {
+            write!(f, " {}", timestamp.as_ref())?;
+        }
+        if let Some(exemplar) = &self.exemplar {
+            write!(f, "{exemplar}")?;
+        }
+        writeln!(f)
+    }
+}
+
+impl<I> Display for Exemplar<I>
+where
+    I: AsRef<str>,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, " # {} {}", self.labels, self.number.as_ref())?;
+        if let Some(timestamp) = &self.timestamp {
+            write!(f, " {}", timestamp.as_ref())?;
+        }
+        Ok(())
+    }
+}
+
+impl<I> Display for Labels<I>
+where
+    I: AsRef<str>,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("{")?;
+        for (i, label) in self.label.iter().enumerate() {
+            if i > 0 {
+                f.write_str(",")?;
+            }
+            write!(f, "{label}")?;
+        }
+        f.write_str("}")
+    }
+}
+
+impl<I> Display for Label<I>
+where
+    I: AsRef<str>,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}=\"{}\"",
+            self.label_name.as_ref(),
+            self.escaped_string,
+        )
+    }
+}
+
+impl<I> Display for EscapedString<I>
+where
+    I: AsRef<str>,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for fragment in &self.0 {
+            f.write_str(match &fragment.value {
+                EscapedStringFragment::Normal(normal) => normal.as_ref(),
+                EscapedStringFragment::Lf => "\\n",
+                EscapedStringFragment::Dquote => "\\\"",
+                EscapedStringFragment::Bs => "\\\\",
+            })?;
+        }
+        Ok(())
+    }
+}
+
+impl<I> Display for HelpEscapedString<I>
+where
+    I: AsRef<str>,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for fragment in &self.0 {
+            f.write_str(match &fragment.value {
+                HelpEscapedStringFragment::Normal(normal) => normal.as_ref(),
+                HelpEscapedStringFragment::Lf => "\\n",
+                HelpEscapedStringFragment::Bs => "\\\\",
+            })?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,46 @@
+use crate::options::ParseOptions;
+use nom::error::Error;
+use nom::{Finish, Parser};
+
+#[rstest::rstest]
+#[case("# EOF\n")]
+#[case("a 1\n# EOF")]
+#[case(
+    r#"# TYPE a counter
+# HELP a Help with \\ and \n and "quotes".
+# UNIT a_seconds seconds
+a_total{b="c\"d\\e\nf",g=""} 1 0 # {trace_id="t"} 1.5 0.5
+a_created 0
+# TYPE b summary
+b{quantile="0.5"} NaN
+b_count{} 1
+# EOF
+"#
+)]
+fn test_encode(#[case] input: &str) {
+    let (_, exposition) = crate::exposition::<_, Error<_>>
+        .parse(input)
+        .finish()
+        .unwrap();
+    assert_eq!(exposition.to_string(), input);
+
+    let mut output = Vec::new();
+    super::encode(&exposition.into_owned(), &mut output).unwrap();
+    assert_eq!(String::from_utf8(output).unwrap(), input);
+}
+
+#[test]
+fn test_encode_edited() {
+    let input = "# SCOPE x\na{b=\"c\"} 1\n# EOF\n";
+    let (_, mut exposition) = ParseOptions::default()
+        .extension("SCOPE", |_| true)
+        .exposition::<_, Error<_>>(input)
+        .finish()
+        .unwrap();
+    let metricfamily = &mut exposition.metricset.value.metricfamily[0];
+    let sample = &mut metricfamily.metric[0].sample[0];
+    sample.number = "2";
+    sample.timestamp = Some("3");
+    sample.labels.as_mut().unwrap().label.clear();
+    assert_eq!(exposition.to_string(), "# SCOPE x\na{} 2 3\n# EOF\n");
+}
@@ -0,0 +1,318 @@
+use crate::cancel::Cancelled;
+use crate::{BS, COMMA, DQUOTE, LF};
+use nom::branch::alt;
+use nom::character::complete::char;
+use nom::error::{ContextError, ErrorKind, ParseError};
+use nom::sequence::preceded;
+use nom::{AsChar, Compare, CompareResult, IResult, Input, Offset, Parser};
+use std::fmt;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Kind {
+    ExpectedMetricName,
+    ExpectedLabelName,
+    ExpectedNumber,
+    UnknownMetricType,
+    // a backslash that does not start an escape sequence, e.g. before LF
+    BadEscape,
+    // a character required by the grammar, e.g. SP or LF
+    Expected(char),
+    MissingEof,
+    // a line that is neither a descriptor nor a sample, e.g. an unknown `#` line
+    InvalidLine,
+    // a family described again with another TYPE, HELP or UNIT, see
+    // `options::ParseOptions::merge_repeated_families`
+    ConflictingDescriptor,
+    // see `streaming::Streaming::cancel_on`
+    Cancelled,
+    Nom(ErrorKind),
+}
+
+impl fmt::Display for Kind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::ExpectedMetricName => write!(f, "expected a metricname"),
+            Self::ExpectedLabelName => write!(f, "expected a label name"),
+            Self::ExpectedNumber => write!(f, "expected a number"),
+            Self::UnknownMetricType => write!(f, "unknown metric type"),
+            Self::BadEscape => write!(f, "invalid escape sequence"),
+            Self::Expected(c) => write!(f, "expected {c:?}"),
+            Self::MissingEof => write!(f, "missing `# EOF`"),
+            Self::InvalidLine => write!(f, "invalid line"),
+            Self::ConflictingDescriptor => write!(f, "conflicting descriptor"),
+            Self::Cancelled => write!(f, "{Cancelled}"),
+            Self::Nom(kind) => write!(f, "invalid input ({})", kind.description()),
+        }
+    }
+}
+
+impl Kind {
+    // See `diagnostic::CODES`.
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::MissingEof => "OM-SYN-002",
+            Self::ConflictingDescriptor => "OM-SYN-003",
+            _ => "OM-SYN-001",
+        }
+    }
+}
+
+// An error of the parsers of this crate, e.g. `crate::exposition::<_, OpenMetricsError<_>>`.
+// The grammar backtracks over a failing line or label, e.g. in `many0`: the error is then
+// replaced by the one of parsing that line or label alone, so that `input` points into it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct OpenMetricsError<I> {
+    // the input at the failure
+    pub input: I,
+    pub kind: Kind,
+    // the contexts of the parsers that failed, innermost first
+    pub context: Vec<&'static str>,
+}
+
+impl<I> OpenMetricsError<I> {
+    // The offset of the failure in `input`, the input given to the parser.
+    pub fn offset(&self, input: &I) -> usize
+    where
+        I: Offset,
+    {
+        input.offset(&self.input)
+    }
+}
+
+impl<I> OpenMetricsError<I>
+where
+    I: Input,
+    I::Item: AsChar,
+{
+    fn retry<P>(self, input: I, mut parser: P) -> Self
+    where
+        P: Parser<I, Error = Self>,
+    {
+        match parser.parse(input) {
+            Err(nom::Err::Error(error) | nom::Err::Failure(error)) => error,
+            _ => self,
+        }
+    }
+
+    fn starts_with(&self, c: char) -> bool {
+        self.input
+            .iter_elements()
+            .next()
+            .is_some_and(|item| item.as_char() == c)
+    }
+}
+
+impl<I> ParseError<I> for OpenMetricsError<I>
+where
+    I: Input,
+{
+    fn from_error_kind(input: I, kind: ErrorKind) -> Self {
+        Self {
+            input,
+            kind: Kind::Nom(kind),
+            context: Vec::new(),
+        }
+    }
+
+    fn append(_: I, _: ErrorKind, other: Self) -> Self {
+        other
+    }
+
+    fn from_char(input: I, c: char) -> Self {
+        Self {
+            input,
+            kind: Kind::Expected(c),
+            context: Vec::new(),
+        }
+    }
+
+    // the branch of `alt` that got further, e.g. `# HELP` rather than `# TYPE` on a HELP line
+    fn or(self, other: Self) -> Self {
+        if self.input.input_len() < other.input.input_len() {
+            self
+        } else {
+            other
+        }
+    }
+}
+
+impl<I> ContextError<I> for OpenMetricsError<I>
+where
+    I: Compare<&'static str> + Input + Offset,
+    I::Item: AsChar,
+{
+    fn add_context(input: I, ctx: &'static str, other: Self) -> Self {
+        let mut error = match ctx {
+            // `separated_list0` stops before the label that failed
+            "labels" if other.starts_with(COMMA) => {
+                let input = other.input.clone();
+                other.retry(input, preceded(char(COMMA), crate::label))
+            }
+            "labels" if input.offset(&other.input) == 1 => {
+                let input = other.input.clone();
+                other.retry(input, crate::label)
+            }
+            // `many0` stops before the line that failed, where `# EOF` is then expected
+            "exposition" if other.input.input_len() > 0 => {
+                let start = input
+                    .take(input.offset(&other.input))
+                    .iter_indices()
+                    .filter(|(_, item)| item.as_char() == LF)
+                    .last()
+                    .map_or(0, |(index, _)| index + 1);
+                other.retry(input.take_from(start), line)
+            }
+            _ => other,
+        };
+        match ctx {
+            "metricname" => error.kind = Kind::ExpectedMetricName,
+            "label_name" => error.kind = Kind::ExpectedLabelName,
+            "number" | "realnumber" => error.kind = Kind::ExpectedNumber,
+            "metric_type" => error.kind = Kind::UnknownMetricType,
+            "conflicting_descriptor" => error.kind = Kind::ConflictingDescriptor,
+            "cancelled" => error.kind = Kind::Cancelled,
+            "label" if error.kind == Kind::Expected(DQUOTE) && error.starts_with(BS) => {
+                error.kind = Kind::BadEscape;
+            }
+            "metric_descriptor"
+                if error.kind == Kind::Expected(LF)
+                    && error.starts_with(BS)
+                    && input.compare("# HELP ") == CompareResult::Ok =>
+            {
+                error.kind = Kind::BadEscape;
+            }
+            "exposition" if error.input.input_len() == 0 => error.kind = Kind::MissingEof,
+            "exposition" if matches!(error.kind, Kind::Nom(_)) => error.kind = Kind::InvalidLine,
+            _ => {}
+        }
+        error.context.push(ctx);
+        error
+    }
+}
+
+// A descriptor or sample line, to find out why a line is invalid.
+pub(crate) fn line<I>(input: I) -> IResult<I, (), OpenMetricsError<I>>
+where
+    I: Compare<&'static str> + Input + Offset,
+    I::Item: AsChar,
+{
+    alt((
+        crate::metric_descriptor.map(|_| ()),
+        crate::sample.map(|_| ()),
+    ))
+    .parse(input)
+}
+
+impl<I> fmt::Display for OpenMetricsError<I>
+where
+    I: AsRef<str>,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.input.as_ref().lines().next() {
+            Some(line) => write!(f, "{} at {line:?}", self.kind),
+            None => write!(f, "{} at the end of the input", self.kind),
+        }
+    }
+}
+
+impl<I> std::error::Error for OpenMetricsError<I> where I: AsRef<str> + fmt::Debug {}
+
+// An error located in the text given to the parser, displayed like
+// `nom::error::convert_error` with the line and a caret under the failure.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Report {
+    pub kind: Kind,
+    pub context: Vec<&'static str>,
+    // 1-based, the column in characters
+    pub line: usize,
+    pub column: usize,
+    // the line of the failure, without its LF
+    pub text: String,
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{}: {}", self.line, self.column, self.kind)?;
+        if !self.context.is_empty() {
+            write!(f, " ({})", self.context.join(", "))?;
+        }
+        writeln!(f)?;
+        writeln!(f, "{}", self.text)?;
+        writeln!(f, "{:>1$}", '^', self.column)
+    }
+}
+
+// `input` is the input given to the parser that failed with `error`.
+pub fn diagnose<I>(input: &I, error: &OpenMetricsError<I>) -> Report
+where
+    I: AsRef<str> + Offset,
+{
+    let (before, after) = input.as_ref().split_at(error.offset(input));
+    let start = before.rfind(LF).map_or(0, |index| index + 1);
+    Report {
+        kind: error.kind,
+        context: error.context.clone(),
+        line: before.matches(LF).count() + 1,
+        column: before[start..].chars().count() + 1,
+        text: [&before[start..], after.lines().next().unwrap_or_default()].concat(),
+    }
+}
+
+// An error with the input given to the parser, so that it can point into the whole text, e.g.
+// with `miette` where `SourceError<&str>` renders the failing line among its neighbours.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SourceError<I> {
+    pub input: I,
+    pub error: OpenMetricsError<I>,
+}
+
+impl<I> fmt::Display for SourceError<I>
+where
+    I: AsRef<str> + Offset,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let report = diagnose(&self.input, &self.error);
+        write!(f, "{}:{}: {}", report.line, report.column, report.kind)
+    }
+}
+
+impl<I> std::error::Error for SourceError<I> where I: AsRef<str> + Offset + fmt::Debug {}
+
+// The failure is labeled with its kind, over its first character.
+#[cfg(feature = "miette")]
+impl<I> miette::Diagnostic for SourceError<I>
+where
+    I: AsRef<str> + Offset + miette::SourceCode + fmt::Debug,
+{
+    fn code<'a>(&'a self) -> Option<Box<dyn fmt::Display + 'a>> {
+        Some(Box::new(self.error.kind.code()))
+    }
+
+    fn help<'a>(&'a self) -> Option<Box<dyn fmt::Display + 'a>> {
+        (!self.error.context.is_empty())
+            .then(|| Box::new(format!("in {}", self.error.context.join(", "))) as Box<_>)
+    }
+
+    fn source_code(&self) -> Option<&dyn miette::SourceCode> {
+        Some(&self.input)
+    }
+
+    fn labels(&self) -> Option<Box<dyn Iterator<Item = miette::LabeledSpan> + '_>> {
+        let offset = self.error.offset(&self.input);
+        let len = self
+            .error
+            .input
+            .as_ref()
+            .chars()
+            .next()
+            .map_or(0, char::len_utf8);
+        Some(Box::new(std::iter::once(miette::LabeledSpan::new(
+            Some(self.error.kind.to_string()),
+            offset,
+            len,
+        ))))
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,108 @@
+use super::{Kind, OpenMetricsError, Report, SourceError};
+use nom::{Finish, Parser};
+
+fn parse(input: &str) -> OpenMetricsError<&str> {
+    crate::exposition::<_, OpenMetricsError<_>>
+        .parse(input)
+        .finish()
+        .unwrap_err()
+}
+
+#[rstest::rstest]
+#[case("a 1\n", Kind::MissingEof, 4)]
+#[case("a x\n# EOF\n", Kind::ExpectedNumber, 2)]
+#[case("a 1\n1 2\n# EOF\n", Kind::ExpectedMetricName, 4)]
+#[case("# TYPE a foo\n# EOF\n", Kind::UnknownMetricType, 9)]
+#[case("# HELP a \\\n# EOF\n", Kind::BadEscape, 9)]
+#[case("# FOO\n# EOF\n", Kind::InvalidLine, 2)]
+#[case("a{b=\"\\\n\"} 1\n# EOF\n", Kind::BadEscape, 5)]
+#[case("a{b=\"c\",1=\"d\"} 1\n# EOF\n", Kind::ExpectedLabelName, 8)]
+#[case("a{b=\"c\" } 1\n# EOF\n", Kind::Expected('}'), 7)]
+#[case("a 1 # {a=\"b\"}\n# EOF\n", Kind::Expected(' '), 13)]
+fn test_kind(#[case] input: &str, #[case] kind: Kind, #[case] offset: usize) {
+    let error = parse(input);
+    assert_eq!((error.kind, error.offset(&input)), (kind, offset));
+}
+
+#[test]
+fn test_context() {
+    let error = parse("a x\n# EOF\n");
+    assert_eq!(error.context, ["number", "sample", "exposition"]);
+}
+
+#[rstest::rstest]
+#[case("a x\n# EOF\n", "expected a number at \"x\"")]
+#[case("a 1\n", "missing `# EOF` at the end of the input")]
+fn test_display(#[case] input: &str, #[case] expected: &str) {
+    assert_eq!(parse(input).to_string(), expected);
+}
+
+#[test]
+fn test_diagnose() {
+    let input = "a 1\nb{c=\"é\"} x\n# EOF\n";
+    let report = super::diagnose(&input, &parse(input));
+    assert_eq!(
+        report,
+        Report {
+            kind: Kind::ExpectedNumber,
+            context: vec!["number", "sample", "exposition"],
+            line: 2,
+            column: 10,
+            text: "b{c=\"é\"} x".to_owned(),
+        }
+    );
+    assert_eq!(
+        report.to_string(),
+        "2:10: expected a number (number, sample, exposition)\nb{c=\"é\"} x\n         ^\n"
+    );
+
+    let input = "a 1\n";
+    let report = super::diagnose(&input, &parse(input));
+    assert_eq!(
+        (report.line, report.column, report.text.as_str()),
+        (2, 1, "")
+    );
+}
+
+#[test]
+fn test_source_error() {
+    let input = "a 1\nb{c=\"é\"} x\n# EOF\n";
+    let error = SourceError {
+        input,
+        error: parse(input),
+    };
+    assert_eq!(error.to_string(), "2:10: expected a number");
+}
+
+#[cfg(feature = "miette")]
+#[test]
+fn test_miette() {
+    use miette::Diagnostic;
+
+    let input = "a 1\nb{c=\"é\"} é\n# EOF\n";
+    let error = SourceError {
+        input,
+        error: parse(input),
+    };
+    let labels = error.labels().unwrap().collect::<Vec<_>>();
+    assert_eq!(
+        labels
+            .iter()
+            .map(|label| (label.label(), label.offset(), label.len()))
+            .collect::<Vec<_>>(),
+        [(Some("expected a number"), 14, 2)]
+    );
+    assert_eq!(
+        error.help().unwrap().to_string(),
+        "in number, sample, exposition"
+    );
+    assert!(error.source_code().is_some());
+    assert_eq!(error.code().unwrap().to_string(), "OM-SYN-001");
+
+    let input = "a 1\n";
+    let error = SourceError {
+        input,
+        error: parse(input),
+    };
+    assert_eq!(error.code().unwrap().to_string(), "OM-SYN-002");
+}
@@ -0,0 +1,86 @@
+use crate::Metricset;
+use std::collections::{BTreeSet, HashSet};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct SizeEstimate {
+    // the serialized size, including `# EOF`
+    pub text_bytes: usize,
+    pub gzip_bytes_approx: usize,
+    pub series: usize,
+    pub samples: usize,
+}
+
+// gzip header and trailer (RFC 1952)
+const GZIP_OVERHEAD: usize = 18;
+// a deflate back-reference costs roughly this many bytes
+const MATCH_COST: usize = 3;
+
+// Estimates the size of `metricset` without compressing it.
+// Runs of bytes whose 4-grams were already seen are priced as one deflate back-reference,
+// the remaining bytes at their order-0 entropy.
+pub fn estimate<I>(metricset: &Metricset<I>) -> SizeEstimate
+where
+    I: AsRef<str>,
+{
+    let mut lines = Vec::new();
+    let mut series = BTreeSet::new();
+    let mut samples = 0;
+    for metricfamily in &metricset.metricfamily {
+        for metric_descriptor in &metricfamily.metric_descriptor {
+            lines.push(metric_descriptor.raw.as_ref());
+        }
+        for sample in metricfamily.samples() {
+            lines.push(sample.raw.as_ref());
+            let labels = sample.labels.as_ref().map(|labels| labels.raw.as_ref());
+            series.insert((sample.metricname.as_ref(), labels.unwrap_or_default()));
+            samples += 1;
+        }
+    }
+    lines.push("# EOF\n");
+
+    let mut seen = HashSet::new();
+    let mut counts = [0_usize; 256];
+    let mut literals = 0_usize;
+    let mut matches = 0;
+    let mut matching = false;
+    let mut window = [0; 4];
+    let bytes = lines.iter().flat_map(|line| line.bytes());
+    for (i, byte) in bytes.clone().enumerate() {
+        window = [window[1], window[2], window[3], byte];
+        let seen = i >= 3 && !seen.insert(window);
+        match (seen, matching) {
+            (true, true) => {}
+            (true, false) => {
+                // the first 3 bytes of the match were counted as literals
+                literals = literals.saturating_sub(3);
+                matches += 1;
+            }
+            (false, _) => {
+                counts[usize::from(byte)] += 1;
+                literals += 1;
+            }
+        }
+        matching = seen;
+    }
+
+    let total = counts.iter().sum::<usize>();
+    let entropy = counts
+        .iter()
+        .filter(|count| **count > 0)
+        .map(|count| {
+            let p = *count as f64 / total as f64;
+            -p * p.log2()
+        })
+        .sum::<f64>();
+    let literal_bytes = (literals as f64 * entropy / 8.).ceil() as usize;
+
+    SizeEstimate {
+        text_bytes: bytes.count(),
+        gzip_bytes_approx: GZIP_OVERHEAD + literal_bytes + matches * MATCH_COST,
+        series: series.len(),
+        samples,
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,27 @@
+use nom::error::Error;
+use nom::{Finish, Parser};
+
+#[test]
+fn test_estimate() {
+    let mut input = "# TYPE a counter\n".to_owned();
+    for i in 0..100 {
+        input.push_str(&format!(
+            "a_total{{instance=\"host-{i}\",job=\"node\"}} {i}\n"
+        ));
+        input.push_str(&format!(
+            "a_total{{instance=\"host-{i}\",job=\"node\"}} {i} 1\n"
+        ));
+    }
+    input.push_str("# EOF\n");
+    let (_, exposition) = crate::exposition::<_, Error<_>>
+        .parse(input.as_str())
+        .finish()
+        .unwrap();
+
+    let estimate = super::estimate(&exposition.metricset.value);
+    assert_eq!(estimate.text_bytes, input.len());
+    assert_eq!(estimate.series, 100);
+    assert_eq!(estimate.samples, 200);
+    assert!(estimate.gzip_bytes_approx > 0);
+    assert!(estimate.gzip_bytes_approx < estimate.text_bytes / 2);
+}
@@ -0,0 +1,88 @@
+use nom::error::{ErrorKind, ParseError};
+use nom::{Err, Mode, Needed, OutputMode, PResult, Parser};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Fault {
+    Error,
+    Failure,
+    Incomplete(Needed),
+}
+
+// Wraps `parser` and makes it fail deterministically. A fault registered at `position`
+// (a byte offset in the stream) fires the first time the parser is invoked on an input
+// starting exactly there; later invocations at the same position run the parser normally,
+// which lets callers exercise their retry paths. Positions are located in `origin`, the buffer
+// starting at `consumed` in the stream; a driver moving to another buffer, e.g. a grown or
+// reallocated one, calls `rebase`. Inputs outside of `origin` are parsed without any fault.
+#[derive(Clone, Debug)]
+pub struct FaultInjector<I, P> {
+    parser: P,
+    origin: I,
+    consumed: usize,
+    faults: Vec<(usize, Fault)>,
+}
+
+impl<I, P> FaultInjector<I, P> {
+    pub fn new(parser: P, origin: I) -> Self {
+        Self {
+            parser,
+            origin,
+            consumed: 0,
+            faults: Vec::new(),
+        }
+    }
+
+    pub fn fault(mut self, position: usize, fault: Fault) -> Self {
+        self.faults.push((position, fault));
+        self
+    }
+
+    pub fn rebase(&mut self, origin: I, consumed: usize) {
+        self.origin = origin;
+        self.consumed = consumed;
+    }
+
+    // faults that have not fired yet
+    pub fn pending(&self) -> &[(usize, Fault)] {
+        &self.faults
+    }
+
+    // in the stream, if `input` is a part of `origin`
+    fn position(&self, input: &I) -> Option<usize>
+    where
+        I: AsRef<[u8]>,
+    {
+        let (origin, input) = (self.origin.as_ref(), input.as_ref());
+        let start = input.as_ptr().addr().checked_sub(origin.as_ptr().addr())?;
+        (start.checked_add(input.len())? <= origin.len()).then_some(self.consumed + start)
+    }
+}
+
+impl<I, P> Parser<I> for FaultInjector<I, P>
+where
+    I: AsRef<[u8]>,
+    P: Parser<I>,
+{
+    type Output = P::Output;
+    type Error = P::Error;
+
+    fn process<OM: OutputMode>(&mut self, input: I) -> PResult<OM, I, Self::Output, Self::Error> {
+        let position = self.position(&input);
+        match self.faults.iter().position(|(p, _)| Some(*p) == position) {
+            Some(index) => match self.faults.remove(index).1 {
+                Fault::Error => Err(Err::Error(OM::Error::bind(|| {
+                    P::Error::from_error_kind(input, ErrorKind::Fail)
+                }))),
+                Fault::Failure => Err(Err::Failure(P::Error::from_error_kind(
+                    input,
+                    ErrorKind::Fail,
+                ))),
+                Fault::Incomplete(needed) => Err(Err::Incomplete(needed)),
+            },
+            None => self.parser.process::<OM>(input),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests;
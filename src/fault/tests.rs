@@ -0,0 +1,55 @@
+use super::{Fault, FaultInjector};
+use nom::error::{Error, ErrorKind};
+use nom::multi::many0;
+use nom::{Err, Needed, Parser};
+
+const INPUT: &str = "a 1\nb 2\nc 3\n";
+
+#[test]
+fn test_incomplete_then_retry() {
+    let mut parser = many0(
+        FaultInjector::new(crate::sample::<_, Error<_>>, INPUT)
+            .fault(4, Fault::Incomplete(Needed::new(1))),
+    );
+
+    assert_eq!(parser.parse(INPUT), Err(Err::Incomplete(Needed::new(1))));
+    let (rest, sample) = parser.parse(INPUT).unwrap();
+    assert_eq!(rest, "");
+    assert_eq!(sample.len(), 3);
+}
+
+#[rstest::rstest]
+#[case(Fault::Error, Ok(("b 2\nc 3\n", 1)))]
+#[case(
+    Fault::Failure,
+    Err(Err::Failure(Error::new("b 2\nc 3\n", ErrorKind::Fail)))
+)]
+fn test_fault(#[case] fault: Fault, #[case] expected: Result<(&str, usize), Err<Error<&str>>>) {
+    let mut injector = FaultInjector::new(crate::sample::<_, Error<_>>, INPUT).fault(4, fault);
+    let result = many0(|input| injector.parse(input))
+        .parse(INPUT)
+        .map(|(rest, sample)| (rest, sample.len()));
+    assert_eq!(result, expected);
+    assert!(injector.pending().is_empty());
+}
+
+#[test]
+fn test_rebase() {
+    let mut injector = FaultInjector::new(crate::sample::<_, Error<_>>, INPUT)
+        .fault(8, Fault::Incomplete(Needed::new(1)));
+
+    // another buffer with the same contents is not located in `INPUT`
+    let buffer = INPUT.to_owned();
+    assert!(injector.parse(&buffer[8..]).is_ok());
+    assert_eq!(injector.pending().len(), 1);
+
+    // a buffer holding the stream from its byte 4
+    let buffer = buffer[4..].to_owned();
+    injector.rebase(&buffer, 4);
+    assert!(injector.parse(&buffer[..]).is_ok());
+    assert_eq!(
+        injector.parse(&buffer[4..]),
+        Err(Err::Incomplete(Needed::new(1)))
+    );
+    assert!(injector.pending().is_empty());
+}
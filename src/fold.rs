@@ -0,0 +1,262 @@
+use crate::{BS, COMMA, DQUOTE, EQ, HASH, LF, MetricType, SP};
+use nom::branch::alt;
+use nom::bytes::complete::{tag, take_while};
+use nom::character::complete::char;
+use nom::combinator::{opt, recognize};
+use nom::error::{ContextError, ParseError, context};
+use nom::multi::fold_many0;
+use nom::sequence::preceded;
+use nom::{AsChar, Compare, IResult, Input, Offset, Parser};
+
+// Callbacks of `exposition_fold`, in the order of the input. A sample is reported as `sample`,
+// its `label`s and `value`, followed by `exemplar`, its `label`s and `value` if it has one.
+// Escaped strings are passed as written, `crate::escaped_string` and
+// `crate::help_escaped_string` unescape them.
+pub trait Visitor<I> {
+    fn metric_type(&mut self, _metricname: I, _metric_type: MetricType) {}
+    fn help(&mut self, _metricname: I, _escaped_string: I) {}
+    fn unit(&mut self, _metricname: I, _metricname_char: I) {}
+    fn sample(&mut self, _metricname: I) {}
+    fn label(&mut self, _label_name: I, _escaped_string: I) {}
+    fn value(&mut self, _number: I, _timestamp: Option<I>) {}
+    fn exemplar(&mut self) {}
+    fn eof(&mut self) {}
+}
+
+// `crate::exposition` without building the tree, hence without allocating. A line is reported
+// once it was parsed as a whole, so the callbacks of a failing parse cover the lines before the
+// error.
+pub fn exposition_fold<I, E, V>(input: I, visitor: &mut V) -> IResult<I, (), E>
+where
+    I: Compare<&'static str> + Input + Offset,
+    I::Item: AsChar,
+    E: ContextError<I> + ParseError<I>,
+    V: Visitor<I> + ?Sized,
+{
+    let mut input = input;
+    loop {
+        match line::<_, E, _>(input.clone(), visitor) {
+            Ok((rest, ())) => input = rest,
+            Err(nom::Err::Error(_)) => break,
+            Err(e) => return Err(e),
+        }
+    }
+    let (input, _) = context(
+        "exposition",
+        (char(HASH), char(SP), tag(crate::EOF), opt(char(LF))),
+    )
+    .parse(input)?;
+    visitor.eof();
+    Ok((input, ()))
+}
+
+enum Descriptor<I> {
+    Type(I, MetricType),
+    Help(I, I),
+    Unit(I, I),
+}
+
+fn line<I, E, V>(input: I, visitor: &mut V) -> IResult<I, (), E>
+where
+    I: Compare<&'static str> + Input + Offset,
+    I::Item: AsChar,
+    E: ContextError<I> + ParseError<I>,
+    V: Visitor<I> + ?Sized,
+{
+    match metric_descriptor::<_, E>(input.clone()) {
+        Ok((input, descriptor)) => {
+            match descriptor {
+                Descriptor::Type(metricname, metric_type) => {
+                    visitor.metric_type(metricname, metric_type)
+                }
+                Descriptor::Help(metricname, escaped_string) => {
+                    visitor.help(metricname, escaped_string)
+                }
+                Descriptor::Unit(metricname, metricname_char) => {
+                    visitor.unit(metricname, metricname_char)
+                }
+            }
+            return Ok((input, ()));
+        }
+        Err(nom::Err::Error(_)) => {}
+        Err(e) => return Err(e),
+    }
+
+    let validate = |input| labels(input, |_, _| ());
+    let (input, (metricname, sample_labels, _, number, timestamp, exemplar, _)) = context(
+        "sample",
+        (
+            crate::metricname,
+            opt(recognize(validate)),
+            char(SP),
+            crate::number,
+            opt(preceded(char(SP), crate::timestamp)),
+            opt((
+                (char(SP), char(HASH), char(SP)),
+                recognize(validate),
+                char(SP),
+                crate::number,
+                opt(preceded(char(SP), crate::timestamp)),
+            )),
+            char(LF),
+        ),
+    )
+    .parse(input)?;
+    visitor.sample(metricname);
+    if let Some(sample_labels) = sample_labels {
+        labels::<_, E, _>(sample_labels, |label_name, escaped_string| {
+            visitor.label(label_name, escaped_string)
+        })?;
+    }
+    visitor.value(number, timestamp);
+    if let Some((_, exemplar_labels, _, number, timestamp)) = exemplar {
+        visitor.exemplar();
+        labels::<_, E, _>(exemplar_labels, |label_name, escaped_string| {
+            visitor.label(label_name, escaped_string)
+        })?;
+        visitor.value(number, timestamp);
+    }
+    Ok((input, ()))
+}
+
+// The rest of a sample after its labels, validated without building anything, e.g. for a sample
+// dropped by `options::ParseOptions::sample_filter`.
+pub(crate) fn sample_value<I, E>(input: I) -> IResult<I, (), E>
+where
+    I: Compare<&'static str> + Input + Offset,
+    I::Item: AsChar,
+    E: ContextError<I> + ParseError<I>,
+{
+    let validate = |input| labels(input, |_, _| ());
+    (
+        char(SP),
+        crate::number,
+        opt(preceded(char(SP), crate::timestamp)),
+        opt((
+            (char(SP), char(HASH), char(SP)),
+            validate,
+            char(SP),
+            crate::number,
+            opt(preceded(char(SP), crate::timestamp)),
+        )),
+        char(LF),
+    )
+        .map(|_| ())
+        .parse(input)
+}
+
+fn metric_descriptor<I, E>(input: I) -> IResult<I, Descriptor<I>, E>
+where
+    I: Compare<&'static str> + Input + Offset,
+    I::Item: AsChar,
+    E: ContextError<I> + ParseError<I>,
+{
+    let keyword = |keyword| {
+        (
+            char(HASH),
+            char(SP),
+            tag(keyword),
+            char(SP),
+            crate::metricname,
+            char(SP),
+        )
+    };
+    context(
+        "metric_descriptor",
+        alt((
+            (keyword(crate::TYPE), crate::metric_type, char(LF)).map(
+                |((_, _, _, _, metricname, _), metric_type, _)| {
+                    Descriptor::Type(metricname, metric_type)
+                },
+            ),
+            (keyword(crate::HELP), help_escaped_string, char(LF)).map(
+                |((_, _, _, _, metricname, _), escaped_string, _)| {
+                    Descriptor::Help(metricname, escaped_string)
+                },
+            ),
+            (
+                keyword(crate::UNIT),
+                take_while(|c: I::Item| crate::is_metricname_char(c.as_char())),
+                char(LF),
+            )
+                .map(|((_, _, _, _, metricname, _), metricname_char, _)| {
+                    Descriptor::Unit(metricname, metricname_char)
+                }),
+        )),
+    )
+    .parse(input)
+}
+
+// `crate::labels`, calling `f` for every label instead of collecting them.
+pub(crate) fn labels<I, E, F>(input: I, mut f: F) -> IResult<I, (), E>
+where
+    I: Input + Offset,
+    I::Item: AsChar,
+    E: ContextError<I> + ParseError<I>,
+    F: FnMut(I, I),
+{
+    let (mut input, _) = char('{').parse(input)?;
+    let mut first = true;
+    loop {
+        if let Ok((input, _)) = char::<_, E>('}').parse(input.clone()) {
+            return Ok((input, ()));
+        }
+        if !first {
+            (input, _) = char(COMMA).parse(input)?;
+        }
+        let (rest, (label_name, _, _, escaped_string, _)) = context(
+            "label",
+            (
+                crate::label_name,
+                char(EQ),
+                char(DQUOTE),
+                escaped_string,
+                char(DQUOTE),
+            ),
+        )
+        .parse(input)?;
+        f(label_name, escaped_string);
+        input = rest;
+        first = false;
+    }
+}
+
+// `crate::escaped_string` and `crate::help_escaped_string` accept any character but LF after a
+// backslash.
+fn escaped_string<I, E>(input: I) -> IResult<I, I, E>
+where
+    I: Input + Offset,
+    I::Item: AsChar,
+    E: ParseError<I>,
+{
+    escaped(input, crate::is_normal_char)
+}
+
+fn help_escaped_string<I, E>(input: I) -> IResult<I, I, E>
+where
+    I: Input + Offset,
+    I::Item: AsChar,
+    E: ParseError<I>,
+{
+    escaped(input, crate::is_help_normal_char)
+}
+
+fn escaped<I, E>(input: I, is_normal_char: fn(char) -> bool) -> IResult<I, I, E>
+where
+    I: Input + Offset,
+    I::Item: AsChar,
+    E: ParseError<I>,
+{
+    recognize(fold_many0(
+        alt((
+            crate::item(is_normal_char).map(|_| ()),
+            (char(BS), crate::item(|c| c != LF)).map(|_| ()),
+        )),
+        || (),
+        |_, _| (),
+    ))
+    .parse(input)
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,92 @@
+use super::Visitor;
+use crate::MetricType;
+use nom::error::Error;
+use nom::{Finish, Parser};
+
+#[derive(Default)]
+struct Calls(Vec<String>);
+
+impl Visitor<&str> for Calls {
+    fn metric_type(&mut self, metricname: &str, metric_type: MetricType) {
+        self.0.push(format!("type {metricname} {metric_type:?}"));
+    }
+    fn help(&mut self, metricname: &str, escaped_string: &str) {
+        self.0.push(format!("help {metricname} {escaped_string}"));
+    }
+    fn unit(&mut self, metricname: &str, metricname_char: &str) {
+        self.0.push(format!("unit {metricname} {metricname_char}"));
+    }
+    fn sample(&mut self, metricname: &str) {
+        self.0.push(format!("sample {metricname}"));
+    }
+    fn label(&mut self, label_name: &str, escaped_string: &str) {
+        self.0.push(format!("label {label_name} {escaped_string}"));
+    }
+    fn value(&mut self, number: &str, timestamp: Option<&str>) {
+        self.0.push(format!("value {number} {timestamp:?}"));
+    }
+    fn exemplar(&mut self) {
+        self.0.push("exemplar".to_owned());
+    }
+    fn eof(&mut self) {
+        self.0.push("eof".to_owned());
+    }
+}
+
+#[test]
+fn test_exposition_fold() {
+    let input = r#"# TYPE a counter
+# HELP a Some \\ "help".
+# UNIT a_seconds seconds
+a_total{b="c\"",d=""} 1 2 # {e="f"} 3
+a_created 4
+# EOF
+"#;
+    let mut calls = Calls::default();
+    let (rest, ()) = super::exposition_fold::<_, Error<_>, _>(input, &mut calls)
+        .finish()
+        .unwrap();
+    assert_eq!(rest, "");
+    assert_eq!(
+        calls.0,
+        [
+            "type a Counter",
+            r#"help a Some \\ "help"."#,
+            "unit a_seconds seconds",
+            "sample a_total",
+            r#"label b c\""#,
+            "label d ",
+            r#"value 1 Some("2")"#,
+            "exemplar",
+            "label e f",
+            "value 3 None",
+            "sample a_created",
+            "value 4 None",
+            "eof",
+        ],
+    );
+}
+
+// accepts exactly what `crate::exposition` accepts
+#[rstest::rstest]
+#[case("# EOF")]
+#[case("a{} 1\n# EOF\n")]
+#[case("a{b=\"c\",} 1\n# EOF\n")]
+#[case("a{b=\"c\\\n\"} 1\n# EOF\n")]
+#[case("# TYPE a foo\n# EOF\n")]
+#[case("# HELP a\n# EOF\n")]
+#[case("a 1 # {} 1\n# EOF\n")]
+#[case("a 1\n# EOF\nrest")]
+#[case("a 1\n")]
+fn test_exposition_fold_accepts(#[case] input: &str) {
+    let expected = crate::exposition::<_, Error<_>>
+        .parse(input)
+        .finish()
+        .map(|(rest, _)| rest);
+    let mut calls = Calls::default();
+    let parsed = super::exposition_fold::<_, Error<_>, _>(input, &mut calls)
+        .finish()
+        .map(|(rest, ())| rest);
+    assert_eq!(parsed.is_ok(), expected.is_ok());
+    assert_eq!(parsed.ok(), expected.ok());
+}
@@ -0,0 +1,138 @@
+use crate::MetricType;
+use crate::transform::{self, LabelConflict};
+use nom::error::Error;
+use nom::{Finish, Parser};
+use std::io::{self, Write};
+
+// Copies the families of `input` accepted by `filter` to `sink` as written, followed by `# EOF`.
+// Lines are only scanned for their metricname, so `input` is not validated.
+pub fn forward<F, W>(input: &str, filter: F, sink: W) -> io::Result<()>
+where
+    F: FnMut(&str) -> bool,
+    W: Write,
+{
+    forward_with_labels(input, filter, &[], LabelConflict::default(), sink)
+}
+
+// Like `forward`, setting the `target` labels (e.g. from service discovery) on every forwarded
+// sample while copying it rather than in a second pass. A target label exposed by the sample is
+// resolved by `conflict` as in `transform::set_target_labels`. Exemplars are copied as is.
+// Samples are validated, since their labels have to be parsed.
+pub fn forward_with_labels<F, W>(
+    input: &str,
+    mut filter: F,
+    target: &[(&str, &str)],
+    conflict: LabelConflict,
+    mut sink: W,
+) -> io::Result<()>
+where
+    F: FnMut(&str) -> bool,
+    W: Write,
+{
+    // name, type and whether `filter` accepted the current family
+    let mut family: Option<(&str, MetricType, bool)> = None;
+    for line in input.split_inclusive('\n') {
+        if line.trim_end_matches('\n') == "# EOF" {
+            sink.write_all(b"# EOF\n")?;
+            return sink.flush();
+        }
+        let scanned = scan(line).ok_or_else(|| invalid_data("invalid line"))?;
+        let is_sample = matches!(scanned, Line::Sample { .. });
+        match scanned {
+            Line::Descriptor { name, metric_type } => {
+                let (_, current_type, _) = match &mut family {
+                    Some(family) if family.0 == name => family,
+                    family => family.insert((name, MetricType::Unknown, filter(name))),
+                };
+                if let Some(metric_type) = metric_type {
+                    *current_type = metric_type;
+                }
+            }
+            Line::Sample { name } => {
+                let continued = family.is_some_and(|(current, metric_type, _)| {
+                    name.strip_prefix(current)
+                        .is_some_and(|suffix| metric_type.sample_suffixes().contains(&suffix))
+                });
+                if !continued {
+                    family = Some((name, MetricType::Unknown, filter(name)));
+                }
+            }
+        }
+        if family.is_some_and(|(_, _, accepted)| accepted) {
+            if is_sample && !target.is_empty() {
+                write_with_labels(line, target, conflict, &mut sink)?;
+            } else {
+                sink.write_all(line.as_bytes())?;
+            }
+        }
+    }
+    Err(io::Error::new(
+        io::ErrorKind::UnexpectedEof,
+        "missing `# EOF`",
+    ))
+}
+
+enum Line<'a> {
+    Descriptor {
+        name: &'a str,
+        metric_type: Option<MetricType>,
+    },
+    Sample {
+        name: &'a str,
+    },
+}
+
+fn scan(line: &str) -> Option<Line<'_>> {
+    match line.strip_prefix("# ") {
+        Some(descriptor) => {
+            let mut words = descriptor.trim_end_matches('\n').splitn(3, ' ');
+            let keyword = words.next()?;
+            let name = words.next()?;
+            let metric_type = match keyword {
+                "TYPE" => Some(
+                    crate::metric_type::<_, Error<_>>
+                        .parse(words.next()?)
+                        .finish()
+                        .ok()?
+                        .1,
+                ),
+                "HELP" | "UNIT" => None,
+                _ => return None,
+            };
+            Some(Line::Descriptor { name, metric_type })
+        }
+        None => Some(Line::Sample {
+            name: &line[..line.find(['{', ' '])?],
+        }),
+    }
+}
+
+fn write_with_labels<W>(
+    line: &str,
+    target: &[(&str, &str)],
+    conflict: LabelConflict,
+    sink: &mut W,
+) -> io::Result<()>
+where
+    W: Write,
+{
+    let (_, sample) = crate::sample::<_, Error<_>>
+        .parse(line)
+        .finish()
+        .map_err(|_| invalid_data("invalid sample"))?;
+    let Some(labels) = transform::target_labels(&sample, target, conflict) else {
+        return sink.write_all(line.as_bytes());
+    };
+    let rest = match &sample.labels {
+        Some(labels) => &line[labels.range.end..],
+        None => &line[sample.metricname.len()..],
+    };
+    write!(sink, "{}{{{}}}{rest}", sample.metricname, labels.join(","))
+}
+
+fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,97 @@
+use crate::transform::LabelConflict;
+
+#[test]
+fn test_forward() {
+    let input = r#"# TYPE a counter
+# HELP a Help.
+a_total 1
+a_created 0
+# TYPE b histogram
+b_bucket{le="+Inf"} 1
+b_count 1
+b_sum 1
+c 1
+a_other 1
+# EOF
+"#;
+    let mut output = Vec::new();
+    super::forward(input, |name| name != "b", &mut output).unwrap();
+    assert_eq!(
+        String::from_utf8(output).unwrap(),
+        r#"# TYPE a counter
+# HELP a Help.
+a_total 1
+a_created 0
+c 1
+a_other 1
+# EOF
+"#,
+    );
+
+    let mut output = Vec::new();
+    super::forward(input, |name| name == "b", &mut output).unwrap();
+    assert_eq!(
+        String::from_utf8(output).unwrap(),
+        r#"# TYPE b histogram
+b_bucket{le="+Inf"} 1
+b_count 1
+b_sum 1
+# EOF
+"#,
+    );
+}
+
+#[test]
+fn test_forward_truncated() {
+    let mut output = Vec::new();
+    let e = super::forward("a 1\n", |_| true, &mut output).unwrap_err();
+    assert_eq!(e.kind(), std::io::ErrorKind::UnexpectedEof);
+}
+
+#[rstest::rstest]
+#[case("a 1\n", LabelConflict::Export, "a{job=\"x\",instance=\"i\"} 1\n")]
+#[case(
+    "a{} 1 0\n",
+    LabelConflict::Export,
+    "a{job=\"x\",instance=\"i\"} 1 0\n"
+)]
+#[case(
+    "a{job=\"y\",b=\"c\"} 1 # {trace_id=\"t\"} 1\n",
+    LabelConflict::Export,
+    "a{exported_job=\"y\",b=\"c\",job=\"x\",instance=\"i\"} 1 # {trace_id=\"t\"} 1\n"
+)]
+#[case(
+    "a{job=\"y\",b=\"c\"} 1\n",
+    LabelConflict::Honor,
+    "a{job=\"y\",b=\"c\",instance=\"i\"} 1\n"
+)]
+#[case(
+    "a{job=\"y\",b=\"c\"} 1\n",
+    LabelConflict::Overwrite,
+    "a{b=\"c\",job=\"x\",instance=\"i\"} 1\n"
+)]
+#[case(
+    "a{job=\"x\",instance=\"i\"} 1\n",
+    LabelConflict::Honor,
+    "a{job=\"x\",instance=\"i\"} 1\n"
+)]
+fn test_forward_with_labels(
+    #[case] sample: &str,
+    #[case] conflict: LabelConflict,
+    #[case] expected: &str,
+) {
+    let input = format!("# TYPE a gauge\n{sample}# EOF\n");
+    let mut output = Vec::new();
+    super::forward_with_labels(
+        &input,
+        |_| true,
+        &[("job", "x"), ("instance", "i")],
+        conflict,
+        &mut output,
+    )
+    .unwrap();
+    assert_eq!(
+        String::from_utf8(output).unwrap(),
+        format!("# TYPE a gauge\n{expected}# EOF\n"),
+    );
+}
@@ -0,0 +1,65 @@
+use std::fmt;
+
+// A pattern where `*` matches any characters and every other character itself, e.g. `http_*`
+// or `*_seconds`. There is no escape: a `*` in the value is matched by `*` like any other
+// character. Matching takes time linear in the pattern and the value, whatever the input, so
+// that untrusted patterns and values can be matched without a regex engine.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Glob {
+    // the literal parts between the `*`s; a single part without any `*`
+    parts: Vec<String>,
+}
+
+impl Glob {
+    pub fn new(pattern: &str) -> Self {
+        Self {
+            parts: pattern.split('*').map(str::to_owned).collect(),
+        }
+    }
+
+    // The value matched by the whole pattern, if it has no `*`.
+    pub fn as_literal(&self) -> Option<&str> {
+        match &self.parts[..] {
+            [literal] => Some(literal),
+            _ => None,
+        }
+    }
+
+    // The prefix of every matching value, if the pattern is a prefix followed by a single `*`.
+    pub fn as_prefix(&self) -> Option<&str> {
+        match &self.parts[..] {
+            [prefix, suffix] if suffix.is_empty() => Some(prefix),
+            _ => None,
+        }
+    }
+
+    pub fn is_match(&self, value: &str) -> bool {
+        let [first, middle @ .., last] = &self.parts[..] else {
+            return self.parts.first().is_some_and(|literal| literal == value);
+        };
+        let Some(rest) = value
+            .strip_prefix(first.as_str())
+            .and_then(|rest| rest.strip_suffix(last.as_str()))
+        else {
+            return false;
+        };
+        // the leftmost match of every part leaves the most room for the next ones
+        let mut rest = rest;
+        for part in middle {
+            match rest.find(part.as_str()) {
+                Some(position) => rest = &rest[position + part.len()..],
+                None => return false,
+            }
+        }
+        true
+    }
+}
+
+impl fmt::Display for Glob {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.parts.join("*"))
+    }
+}
+
+#[cfg(test)]
+mod tests;
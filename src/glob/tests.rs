@@ -0,0 +1,38 @@
+use super::Glob;
+
+#[rstest::rstest]
+#[case("a", "a", true)]
+#[case("a", "ab", false)]
+#[case("", "", true)]
+#[case("*", "", true)]
+#[case("http_*", "http_requests", true)]
+#[case("http_*", "grpc_requests", false)]
+#[case("*_seconds", "a_seconds", true)]
+#[case("a*b*c", "abc", true)]
+#[case("a*b*c", "a_c_b_c", true)]
+#[case("a*b*c", "a_c_c", false)]
+#[case("a*a", "a", false)]
+#[case("**", "x", true)]
+#[case("a.*", "ab", false)]
+#[case("*é*", "café", true)]
+fn test_is_match(#[case] pattern: &str, #[case] value: &str, #[case] expected: bool) {
+    let glob = Glob::new(pattern);
+    assert_eq!(glob.is_match(value), expected);
+    assert_eq!(glob.to_string(), pattern);
+}
+
+#[test]
+fn test_as_literal() {
+    assert_eq!(Glob::new("a").as_literal(), Some("a"));
+    assert_eq!(Glob::new("a*").as_literal(), None);
+    assert_eq!(Glob::new("a*").as_prefix(), Some("a"));
+    assert_eq!(Glob::new("a*b").as_prefix(), None);
+}
+
+// No backtracking: a pattern that is exponential for a naive matcher is matched in one pass.
+#[test]
+fn test_is_match_linear() {
+    let value = "a".repeat(100_000);
+    assert!(Glob::new(&"a*".repeat(1_000)).is_match(&value));
+    assert!(!Glob::new(&format!("{}*b*", "*a".repeat(1_000))).is_match(&value));
+}
@@ -0,0 +1,138 @@
+use std::borrow::Cow;
+use std::fmt;
+use std::sync::OnceLock;
+
+// https://github.com/prometheus/OpenMetrics/blob/main/specification/OpenMetrics.md#abnf
+//
+// The rules implemented by the parsers of the crate root, in order.
+const RULES: &[(&str, &[&str])] = &[
+    ("exposition", &["metricset HASH SP eof [ LF ]"]),
+    ("metricset", &["*metricfamily"]),
+    ("metricfamily", &["*metric-descriptor *metric"]),
+    (
+        "metric-descriptor",
+        &[
+            "HASH SP type SP metricname SP metric-type LF",
+            "HASH SP help SP metricname SP help-escaped-string LF",
+            "HASH SP unit SP metricname SP *metricname-char LF",
+        ],
+    ),
+    ("metric", &["*sample"]),
+    (
+        "metric-type",
+        &[
+            "counter / gauge / histogram / gaugehistogram / stateset",
+            "info / summary / unknown",
+        ],
+    ),
+    (
+        "sample",
+        &["metricname [labels] SP number [SP timestamp] [exemplar] LF"],
+    ),
+    ("exemplar", &["SP HASH SP labels SP number [SP timestamp]"]),
+    ("labels", &["\"{\" [label *(COMMA label)] \"}\""]),
+    ("label", &["label-name EQ DQUOTE escaped-string DQUOTE"]),
+    (
+        "number",
+        &["realnumber", "[SIGN] (\"inf\" / \"infinity\")", "\"nan\""],
+    ),
+    ("timestamp", &["realnumber"]),
+    (
+        "realnumber",
+        &[
+            "[SIGN] 1*DIGIT",
+            "[SIGN] 1*DIGIT [\".\" *DIGIT] [ \"e\" [SIGN] 1*DIGIT ]",
+            "[SIGN] *DIGIT \".\" 1*DIGIT [ \"e\" [SIGN] 1*DIGIT ]",
+        ],
+    ),
+    ("eof", &["%d69.79.70"]),
+    ("type", &["%d84.89.80.69"]),
+    ("help", &["%d72.69.76.80"]),
+    ("unit", &["%d85.78.73.84"]),
+    ("counter", &["%d99.111.117.110.116.101.114"]),
+    ("gauge", &["%d103.97.117.103.101"]),
+    ("histogram", &["%d104.105.115.116.111.103.114.97.109"]),
+    ("gaugehistogram", &["gauge histogram"]),
+    ("stateset", &["%d115.116.97.116.101.115.101.116"]),
+    ("info", &["%d105.110.102.111"]),
+    ("summary", &["%d115.117.109.109.97.114.121"]),
+    ("unknown", &["%d117.110.107.110.111.119.110"]),
+    ("BS", &["\"\\\""]),
+    ("EQ", &["\"=\""]),
+    ("COMMA", &["\",\""]),
+    ("HASH", &["\"#\""]),
+    ("SIGN", &["\"-\" / \"+\""]),
+    ("metricname", &["metricname-initial-char 0*metricname-char"]),
+    ("metricname-char", &["metricname-initial-char / DIGIT"]),
+    ("metricname-initial-char", &["ALPHA / \"_\" / \":\""]),
+    ("label-name", &["label-name-initial-char *label-name-char"]),
+    ("label-name-char", &["label-name-initial-char / DIGIT"]),
+    ("label-name-initial-char", &["ALPHA / \"_\""]),
+    ("escaped-string", &["*escaped-char"]),
+    (
+        "escaped-char",
+        &["normal-char", "BS (\"n\" / DQUOTE / BS)", "BS normal-char"],
+    ),
+    (
+        "normal-char",
+        &["%x00-09 / %x0B-21 / %x23-5B / %x5D-D7FF / %xE000-10FFFF"],
+    ),
+    // not in the specification: HELP text escapes `\\` and `\n` only, see `help_escaped_string`
+    ("help-escaped-string", &["*help-escaped-char"]),
+    (
+        "help-escaped-char",
+        &["help-normal-char", "BS (\"n\" / BS)", "BS help-normal-char"],
+    ),
+    (
+        "help-normal-char",
+        &["%x00-09 / %x0B-5B / %x5D-D7FF / %xE000-10FFFF"],
+    ),
+];
+
+// An ABNF rule. Every alternative after the first one is written as an incremental alternative
+// (`=/`).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Rule {
+    pub name: Cow<'static, str>,
+    pub alternatives: Vec<Cow<'static, str>>,
+    // written as `;` lines before the rule
+    pub comments: Vec<Cow<'static, str>>,
+}
+
+impl fmt::Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for comment in &self.comments {
+            writeln!(f, "; {comment}")?;
+        }
+        for (i, alternative) in self.alternatives.iter().enumerate() {
+            let operator = if i == 0 { "=" } else { "=/" };
+            writeln!(f, "{} {operator} {alternative}", self.name)?;
+        }
+        Ok(())
+    }
+}
+
+// The rules of the strict grammar, i.e. of `exposition`.
+pub fn rules() -> Vec<Rule> {
+    RULES
+        .iter()
+        .map(|(name, alternatives)| Rule {
+            name: Cow::Borrowed(name),
+            alternatives: alternatives.iter().copied().map(Cow::Borrowed).collect(),
+            comments: Vec::new(),
+        })
+        .collect()
+}
+
+// The strict grammar as ABNF text.
+pub fn grammar() -> &'static str {
+    static GRAMMAR: OnceLock<String> = OnceLock::new();
+    GRAMMAR.get_or_init(|| to_abnf(&rules()))
+}
+
+pub fn to_abnf(rules: &[Rule]) -> String {
+    rules.iter().map(Rule::to_string).collect()
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,57 @@
+use crate::options::{Comments, ParseOptions};
+
+#[test]
+fn test_grammar() {
+    let grammar = super::grammar();
+    assert!(
+        grammar
+            .starts_with("exposition = metricset HASH SP eof [ LF ]\nmetricset = *metricfamily\n")
+    );
+    assert!(grammar.contains("\nnumber = realnumber\nnumber =/ [SIGN] (\"inf\" / \"infinity\")\n"));
+    assert_eq!(ParseOptions::default().grammar(), grammar);
+
+    // every referenced rule is defined, except the core rules of RFC 5234
+    let rules = super::rules();
+    for rule in &rules {
+        for alternative in &rule.alternatives {
+            for name in alternative
+                .split([' ', '(', ')', '[', ']'])
+                .map(|element| element.trim_start_matches(|c: char| c.is_ascii_digit() || c == '*'))
+                .filter(|element| element.starts_with(|c: char| c.is_ascii_alphabetic()))
+            {
+                assert!(
+                    ["ALPHA", "DIGIT", "DQUOTE", "LF", "SP"].contains(&name)
+                        || rules.iter().any(|rule| rule.name == name),
+                    "`{name}` is not defined",
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn test_grammar_options() {
+    let grammar = ParseOptions::federation()
+        .extension("SCOPE", |_| true)
+        .extension("VENDOR", |_| true)
+        .extension("SCOPE", |_| true)
+        .grammar();
+    assert!(grammar.contains(
+        "; families described under the same name are merged into the first one\nmetricset = *(metricfamily / extension)\n"
+    ));
+    assert!(grammar.contains(
+        "extension = HASH SP extension-keyword [SP *extension-char] LF\nextension-keyword = %s\"SCOPE\" / %s\"VENDOR\"\n"
+    ));
+
+    let grammar = ParseOptions::default()
+        .extension("SCOPE", |_| true)
+        .comments(Comments::Skip)
+        .grammar();
+    assert!(grammar.contains("\nmetricset = *(metricfamily / extension / comment)\n"));
+    assert!(grammar.contains(
+        "; a `comment` may also precede any `metric-descriptor` or `sample`\nmetricfamily = *metric-descriptor *metric\n"
+    ));
+    assert!(grammar.contains(
+        "extension-char = %x00-09 / %x0B-10FFFF\n; neither a descriptor, `# EOF` nor an extension line\ncomment = HASH *comment-char LF\n"
+    ));
+}
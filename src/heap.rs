@@ -0,0 +1,300 @@
+use crate::model::MetricFamily;
+use crate::{
+    Comment, EscapedString, EscapedStringFragment, Exemplar, Exposition, Extension,
+    HelpEscapedString, HelpEscapedStringFragment, Label, Labels, Merged, Metric, MetricDescriptor,
+    MetricType, Metricfamily, Metricset, Sample, Spanned,
+};
+use std::mem::size_of;
+use std::rc::Rc;
+use std::sync::Arc;
+
+// Bytes allocated on the heap by a value, excluding the value itself. Counted by capacity, without
+// allocator overhead, hence approximate.
+pub trait HeapSize {
+    fn heap_size(&self) -> usize;
+}
+
+// Borrowed input is accounted to its owner.
+impl HeapSize for &str {
+    fn heap_size(&self) -> usize {
+        0
+    }
+}
+
+impl HeapSize for String {
+    fn heap_size(&self) -> usize {
+        self.capacity()
+    }
+}
+
+impl HeapSize for Box<str> {
+    fn heap_size(&self) -> usize {
+        self.len()
+    }
+}
+
+// Shared strings are counted by every holder.
+impl HeapSize for Rc<str> {
+    fn heap_size(&self) -> usize {
+        self.len()
+    }
+}
+
+impl HeapSize for Arc<str> {
+    fn heap_size(&self) -> usize {
+        self.len()
+    }
+}
+
+// Like borrowed input, a slice of a shared buffer is accounted to the buffer.
+#[cfg(feature = "bytes")]
+impl HeapSize for crate::shared::Shared {
+    fn heap_size(&self) -> usize {
+        0
+    }
+}
+
+impl<T> HeapSize for Vec<T>
+where
+    T: HeapSize,
+{
+    fn heap_size(&self) -> usize {
+        self.capacity() * size_of::<T>() + self.iter().map(T::heap_size).sum::<usize>()
+    }
+}
+
+impl<T> HeapSize for Option<T>
+where
+    T: HeapSize,
+{
+    fn heap_size(&self) -> usize {
+        self.as_ref().map_or(0, T::heap_size)
+    }
+}
+
+impl<I, T> HeapSize for Spanned<I, T>
+where
+    I: HeapSize,
+    T: HeapSize,
+{
+    fn heap_size(&self) -> usize {
+        self.raw.heap_size() + self.value.heap_size()
+    }
+}
+
+impl HeapSize for MetricType {
+    fn heap_size(&self) -> usize {
+        0
+    }
+}
+
+impl<I> HeapSize for Exposition<I>
+where
+    I: HeapSize,
+{
+    fn heap_size(&self) -> usize {
+        self.metricset.heap_size() + self.eof.heap_size()
+    }
+}
+
+impl<I> HeapSize for Metricset<I>
+where
+    I: HeapSize,
+{
+    fn heap_size(&self) -> usize {
+        self.metricfamily.heap_size()
+            + self.extension.heap_size()
+            + self.comment.heap_size()
+            + self.merged.heap_size()
+    }
+}
+
+impl<I> HeapSize for Comment<I>
+where
+    I: HeapSize,
+{
+    fn heap_size(&self) -> usize {
+        self.text.heap_size()
+    }
+}
+
+impl<I> HeapSize for Merged<I>
+where
+    I: HeapSize,
+{
+    fn heap_size(&self) -> usize {
+        self.metricname.heap_size()
+    }
+}
+
+impl<I> HeapSize for Extension<I>
+where
+    I: HeapSize,
+{
+    fn heap_size(&self) -> usize {
+        self.keyword.heap_size() + self.body.heap_size()
+    }
+}
+
+impl<I> HeapSize for Metricfamily<I>
+where
+    I: HeapSize,
+{
+    fn heap_size(&self) -> usize {
+        self.metric_descriptor.heap_size() + self.metric.heap_size()
+    }
+}
+
+impl<I> HeapSize for MetricDescriptor<I>
+where
+    I: HeapSize,
+{
+    fn heap_size(&self) -> usize {
+        match self {
+            Self::Type {
+                metricname,
+                metric_type,
+            } => metricname.heap_size() + metric_type.heap_size(),
+            Self::Help {
+                metricname,
+                escaped_string,
+            } => metricname.heap_size() + escaped_string.heap_size(),
+            Self::Unit {
+                metricname,
+                metricname_char,
+            } => metricname.heap_size() + metricname_char.heap_size(),
+        }
+    }
+}
+
+impl<I> HeapSize for Metric<I>
+where
+    I: HeapSize,
+{
+    fn heap_size(&self) -> usize {
+        self.sample.heap_size()
+    }
+}
+
+impl<I> HeapSize for Sample<I>
+where
+    I: HeapSize,
+{
+    fn heap_size(&self) -> usize {
+        self.metricname.heap_size()
+            + self.labels.heap_size()
+            + self.number.heap_size()
+            + self.timestamp.heap_size()
+            + self.exemplar.heap_size()
+            + self.normalized_number.heap_size()
+    }
+}
+
+impl<I> HeapSize for Exemplar<I>
+where
+    I: HeapSize,
+{
+    fn heap_size(&self) -> usize {
+        self.labels.heap_size()
+            + self.number.heap_size()
+            + self.timestamp.heap_size()
+            + self.normalized_number.heap_size()
+    }
+}
+
+impl<I> HeapSize for Labels<I>
+where
+    I: HeapSize,
+{
+    fn heap_size(&self) -> usize {
+        self.label.heap_size()
+    }
+}
+
+impl<I> HeapSize for Label<I>
+where
+    I: HeapSize,
+{
+    fn heap_size(&self) -> usize {
+        self.label_name.heap_size() + self.escaped_string.heap_size()
+    }
+}
+
+impl<I> HeapSize for EscapedString<I>
+where
+    I: HeapSize,
+{
+    fn heap_size(&self) -> usize {
+        self.0.heap_size()
+    }
+}
+
+impl<I> HeapSize for EscapedStringFragment<I>
+where
+    I: HeapSize,
+{
+    fn heap_size(&self) -> usize {
+        match self {
+            Self::Normal(normal) => normal.heap_size(),
+            Self::Lf | Self::Dquote | Self::Bs => 0,
+        }
+    }
+}
+
+impl<I> HeapSize for HelpEscapedString<I>
+where
+    I: HeapSize,
+{
+    fn heap_size(&self) -> usize {
+        self.0.heap_size()
+    }
+}
+
+impl<I> HeapSize for HelpEscapedStringFragment<I>
+where
+    I: HeapSize,
+{
+    fn heap_size(&self) -> usize {
+        match self {
+            Self::Normal(normal) => normal.heap_size(),
+            Self::Lf | Self::Bs => 0,
+        }
+    }
+}
+
+impl<I> HeapSize for MetricFamily<I>
+where
+    I: HeapSize,
+{
+    fn heap_size(&self) -> usize {
+        self.name.heap_size()
+            + self.help.heap_size()
+            + self.unit.heap_size()
+            + self.metrics.heap_size()
+            + self.sample_ordinals.capacity() * size_of::<usize>()
+    }
+}
+
+// e.g. to enforce a memory budget per target. Not recorded while parsing but computed by walking
+// every node on each call, hence linear in the size of the exposition.
+impl<I> Exposition<I>
+where
+    I: HeapSize,
+{
+    pub fn approx_heap_size(&self) -> usize {
+        self.heap_size()
+    }
+}
+
+// Walks the family like `Exposition::approx_heap_size`; see `model::approx_heap_size` for a set.
+impl<I> MetricFamily<I>
+where
+    I: HeapSize,
+{
+    pub fn approx_heap_size(&self) -> usize {
+        self.heap_size()
+    }
+}
+
+#[cfg(test)]
+mod tests;
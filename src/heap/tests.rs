@@ -0,0 +1,57 @@
+use super::HeapSize;
+use crate::model::MetricFamily;
+use crate::{Exposition, Metric, MetricDescriptor, Metricfamily, Sample, Spanned};
+use nom::error::Error;
+use nom::{Finish, Parser};
+use std::mem::size_of;
+
+// the vectors of a single-sample exposition
+fn vectors<I>(exposition: &Exposition<I>) -> usize {
+    let metricset = &exposition.metricset.value;
+    let metricfamily = &metricset.metricfamily[0];
+    metricset.metricfamily.capacity() * size_of::<Spanned<I, Metricfamily<I>>>()
+        + metricfamily.metric_descriptor.capacity() * size_of::<Spanned<I, MetricDescriptor<I>>>()
+        + metricfamily.metric.capacity() * size_of::<Spanned<I, Metric<I>>>()
+        + metricfamily.metric[0].sample.capacity() * size_of::<Spanned<I, Sample<I>>>()
+}
+
+#[test]
+fn test_approx_heap_size() {
+    let (_, exposition) = crate::exposition::<_, Error<_>>
+        .parse("a 1\n# EOF\n")
+        .finish()
+        .unwrap();
+    // the input is borrowed
+    assert_eq!(exposition.approx_heap_size(), vectors(&exposition));
+
+    // metricset, metricfamily, metric and sample lines, metricname, number and eof
+    let owned = exposition.clone().into_owned();
+    assert_eq!(
+        owned.approx_heap_size(),
+        vectors(&owned) + 4 * 4 + 1 + 1 + 6
+    );
+
+    let metricfamily = exposition.metricset.value.metricfamily[0].clone();
+    let family = MetricFamily::try_from(metricfamily.value).unwrap();
+    assert_eq!(
+        family.approx_heap_size(),
+        family.metrics.capacity() * size_of::<Metric<&str>>()
+            + family.metrics[0].heap_size()
+            + family.sample_ordinals.capacity() * size_of::<usize>(),
+    );
+}
+
+#[test]
+fn test_model_approx_heap_size() {
+    let (_, exposition) = crate::exposition::<_, Error<_>>
+        .parse("# TYPE a gauge\na 1\n# TYPE b gauge\nb 1\nb{c=\"d\"} 2\n# EOF\n")
+        .finish()
+        .unwrap();
+    let families = crate::model::metric_families(exposition.metricset.value).unwrap();
+    assert_eq!(
+        crate::model::approx_heap_size(&families),
+        2 * size_of::<MetricFamily<&str>>()
+            + families[0].approx_heap_size()
+            + families[1].approx_heap_size(),
+    );
+}
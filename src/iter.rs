@@ -0,0 +1,81 @@
+use crate::{Metricfamily, Spanned};
+use nom::bytes::complete::tag;
+use nom::character::complete::char;
+use nom::combinator::opt;
+use nom::error::{ContextError, ParseError, context};
+use nom::{AsChar, Compare, Finish, Input, Offset, Parser};
+use std::marker::PhantomData;
+
+// The families of `crate::exposition`, each parsed by `next`, so that processing starts before
+// the rest of the input is parsed and stopping early skips it. Ends after `# EOF`, or after the
+// error of the first line that is neither part of a family nor `# EOF`.
+pub struct ExpositionIter<I, E> {
+    whole: I,
+    input: I,
+    done: bool,
+    error: PhantomData<E>,
+}
+
+impl<I, E> ExpositionIter<I, E>
+where
+    I: Clone,
+{
+    pub fn new(input: I) -> Self {
+        Self {
+            whole: input.clone(),
+            input,
+            done: false,
+            error: PhantomData,
+        }
+    }
+
+    // The input following `# EOF` once the iterator ended, else the unparsed input.
+    pub fn rest(&self) -> &I {
+        &self.input
+    }
+}
+
+impl<I, E> Iterator for ExpositionIter<I, E>
+where
+    I: Compare<&'static str> + Input + Offset,
+    I::Item: AsChar,
+    E: ContextError<I> + ParseError<I>,
+{
+    type Item = Result<Spanned<I, Metricfamily<I>>, E>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        if let Ok((input, metricfamily)) =
+            crate::spanned(self.whole.clone(), crate::metricfamily::<_, E>)
+                .parse(self.input.clone())
+                .finish()
+        {
+            self.input = input;
+            return Some(Ok(metricfamily));
+        }
+        self.done = true;
+        match context(
+            "exposition",
+            (
+                char(crate::HASH),
+                char(crate::SP),
+                tag(crate::EOF),
+                opt(char(crate::LF)),
+            ),
+        )
+        .parse(self.input.clone())
+        .finish()
+        {
+            Ok((input, _)) => {
+                self.input = input;
+                None
+            }
+            Err(e) => Some(Err(e)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests;
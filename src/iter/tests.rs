@@ -0,0 +1,38 @@
+use super::ExpositionIter;
+use nom::error::Error;
+use nom::{Finish, Parser};
+
+#[test]
+fn test_exposition_iter() {
+    let input = r#"# TYPE a counter
+a_total 1
+# TYPE b gauge
+b 2
+c 3
+# EOF
+rest"#;
+    let (rest, exposition) = crate::exposition::<_, Error<_>>
+        .parse(input)
+        .finish()
+        .unwrap();
+    let mut iter = ExpositionIter::<_, Error<_>>::new(input);
+    let metricfamily = iter.by_ref().collect::<Result<Vec<_>, _>>().unwrap();
+    assert_eq!(metricfamily, exposition.metricset.value.metricfamily);
+    assert_eq!(*iter.rest(), rest);
+
+    // stopping early leaves the rest unparsed
+    let mut iter = ExpositionIter::<_, Error<_>>::new(input);
+    let metricfamily = iter.next().unwrap().unwrap();
+    assert_eq!(metricfamily.raw, "# TYPE a counter\na_total 1\n");
+    assert!(iter.rest().starts_with("# TYPE b gauge\n"));
+}
+
+#[rstest::rstest]
+#[case("a 1\nb\n# EOF\n", 1)]
+#[case("a 1\n", 1)]
+#[case("", 0)]
+fn test_exposition_iter_error(#[case] input: &str, #[case] families: usize) {
+    let results = ExpositionIter::<_, Error<_>>::new(input).collect::<Vec<_>>();
+    assert_eq!(results.len(), families + 1);
+    assert!(results[families].is_err());
+}
@@ -0,0 +1,235 @@
+use crate::error::{self, Kind, OpenMetricsError};
+use crate::options;
+use crate::{
+    Exposition, Merged, Metric, MetricDescriptor, Metricfamily, Metricset, Sample, Spanned,
+};
+use nom::bytes::complete::tag;
+use nom::character::complete::char;
+use nom::combinator::{opt, recognize};
+use nom::error::ErrorKind;
+use nom::{AsChar, Compare, Input, Offset, Parser};
+use std::borrow::Cow;
+use std::ops::Range;
+
+// A line skipped by `parse_lenient`, a missing `# EOF`, the input following it or a descriptor
+// dropped by `Lenient::merge_interleaved_families`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ParseIssue {
+    // byte range of the line including its LF, or of the input following `# EOF`
+    pub line: Range<usize>,
+    // byte offset of the failure
+    pub offset: usize,
+    pub kind: Kind,
+}
+
+// Deviations accepted by `Lenient::parse` on top of skipping invalid lines.
+#[derive(Clone, Debug, Default)]
+pub struct Lenient {
+    merge_interleaved_families: bool,
+}
+
+impl Lenient {
+    // Merges every family into its first block, e.g. samples of `a`, then of `b`, then of `a`
+    // again into one family `a`, so that the model sees a single family. This covers samples the
+    // grammar groups with the family before them, and families described again as with
+    // `options::ParseOptions::merge_repeated_families`. `lint::interleaved_families` flags such
+    // inputs. A descriptor conflicting with the first ones of its family is dropped as an issue.
+    pub fn merge_interleaved_families(mut self, merge_interleaved_families: bool) -> Self {
+        self.merge_interleaved_families = merge_interleaved_families;
+        self
+    }
+
+    // `crate::exposition` skipping the lines that are neither a descriptor, a sample nor
+    // `# EOF`, e.g. to keep the other samples of a large scrape. A family interrupted by an
+    // invalid line continues as another family. A missing `# EOF` is an issue at the end of the
+    // input, and so is any input following `# EOF`, which is left out of the exposition.
+    pub fn parse<I>(&self, input: I) -> (Exposition<I>, Vec<ParseIssue>)
+    where
+        I: AsRef<str> + Compare<&'static str> + Input + Offset,
+        I::Item: AsChar,
+    {
+        let origin = input.clone();
+        let mut metricfamily = Vec::new();
+        let mut issues = Vec::new();
+        let mut rest = input;
+        let eof = loop {
+            if let Ok((suffix, family)) = crate::spanned(
+                origin.clone(),
+                crate::metricfamily::<_, OpenMetricsError<_>>,
+            )
+            .parse(rest.clone())
+            {
+                metricfamily.push(family);
+                rest = suffix;
+                continue;
+            }
+            let eof = recognize((
+                char::<_, OpenMetricsError<_>>(crate::HASH),
+                char(crate::SP),
+                tag(crate::EOF),
+                opt(char(crate::LF)),
+            ))
+            .parse(rest.clone());
+            if let Ok((suffix, eof)) = eof {
+                if suffix.input_len() > 0 {
+                    let start = origin.offset(&suffix);
+                    issues.push(ParseIssue {
+                        line: start..start + suffix.input_len(),
+                        offset: start,
+                        kind: Kind::Nom(ErrorKind::Eof),
+                    });
+                }
+                break eof;
+            }
+            let start = origin.offset(&rest);
+            let len = rest
+                .position(|item| item.as_char() == crate::LF)
+                .map_or(rest.input_len(), |index| index + 1);
+            if len == 0 {
+                issues.push(ParseIssue {
+                    line: start..start,
+                    offset: start,
+                    kind: Kind::MissingEof,
+                });
+                break rest.take(0);
+            }
+            let (offset, kind) = match error::line(rest.clone()) {
+                Err(nom::Err::Error(e) | nom::Err::Failure(e)) => (origin.offset(&e.input), e.kind),
+                _ => (start, Kind::InvalidLine),
+            };
+            issues.push(ParseIssue {
+                line: start..start + len,
+                offset,
+                kind: match kind {
+                    Kind::Nom(_) => Kind::InvalidLine,
+                    kind => kind,
+                },
+            });
+            rest = rest.take_from(len);
+        };
+        let mut merged = Vec::new();
+        if self.merge_interleaved_families {
+            for conflict in merge_interleaved_families(&mut metricfamily, &mut merged) {
+                issues.push(ParseIssue {
+                    line: conflict.range.clone(),
+                    offset: conflict.range.start,
+                    kind: Kind::ConflictingDescriptor,
+                });
+            }
+            issues.sort_by_key(|issue| issue.line.start);
+        }
+        let len = origin.offset(&eof);
+        let exposition = Exposition {
+            metricset: Spanned {
+                range: 0..len,
+                raw: origin.take(len),
+                value: Metricset {
+                    metricfamily,
+                    extension: Vec::new(),
+                    comment: Vec::new(),
+                    merged,
+                },
+            },
+            eof,
+        };
+        (exposition, issues)
+    }
+}
+
+// `Lenient::parse` with the default options.
+pub fn parse_lenient<I>(input: I) -> (Exposition<I>, Vec<ParseIssue>)
+where
+    I: AsRef<str> + Compare<&'static str> + Input + Offset,
+    I::Item: AsChar,
+{
+    Lenient::default().parse(input)
+}
+
+// `input` with CRLF line endings and spaces or tabs before LF removed, e.g. of a scrape passed
+// through Windows tooling, for any parser of this crate; borrowed if there is none. Ranges of the
+// parsed nodes are then offsets into the normalized text. Trailing spaces of a HELP text are
+// removed as well, although the grammar allows them.
+pub fn normalize_line_endings(input: &str) -> Cow<'_, str> {
+    let is_trailing = |c: char| c == '\r' || c == crate::SP || c == '\t';
+    let dirty = input
+        .split_inclusive(crate::LF)
+        .any(|line| line.trim_end_matches(crate::LF).ends_with(is_trailing));
+    if !dirty {
+        return Cow::Borrowed(input);
+    }
+    let mut normalized = String::with_capacity(input.len());
+    for line in input.split_inclusive(crate::LF) {
+        match line.strip_suffix(crate::LF) {
+            Some(line) => {
+                normalized.push_str(line.trim_end_matches(is_trailing));
+                normalized.push(crate::LF);
+            }
+            None => normalized.push_str(line.trim_end_matches(is_trailing)),
+        }
+    }
+    Cow::Owned(normalized)
+}
+
+// A moved sample becomes a metric of its own, spanning the sample. Metrics are kept in the order
+// of the input. Returns the descriptors dropped for conflicting with the first ones of their
+// family.
+fn merge_interleaved_families<I>(
+    metricfamily: &mut Vec<Spanned<I, Metricfamily<I>>>,
+    merged: &mut Vec<Spanned<I, Merged<I>>>,
+) -> Vec<Spanned<I, MetricDescriptor<I>>>
+where
+    I: AsRef<str> + Clone,
+{
+    let conflicts = options::merge_repeated_families(metricfamily, merged);
+    for index in 0..metricfamily.len() {
+        for mut metric in std::mem::take(&mut metricfamily[index].metric) {
+            for sample in std::mem::take(&mut metric.sample) {
+                match owner(metricfamily, index, &sample) {
+                    Some(owner) => metricfamily[owner].metric.push(Spanned {
+                        range: sample.range.clone(),
+                        raw: sample.raw.clone(),
+                        value: Metric {
+                            sample: vec![sample],
+                        },
+                    }),
+                    None => metric.sample.push(sample),
+                }
+            }
+            if !metric.sample.is_empty() {
+                metricfamily[index].metric.push(metric);
+            }
+        }
+    }
+    metricfamily.retain(|metricfamily| {
+        !metricfamily.metric_descriptor.is_empty() || !metricfamily.metric.is_empty()
+    });
+    for metricfamily in metricfamily {
+        metricfamily.metric.sort_by_key(|metric| metric.range.start);
+    }
+    conflicts
+}
+
+// The other family a sample of `metricfamily[index]` belongs to, if it does not belong there.
+fn owner<I>(
+    metricfamily: &[Spanned<I, Metricfamily<I>>],
+    index: usize,
+    sample: &Sample<I>,
+) -> Option<usize>
+where
+    I: AsRef<str>,
+{
+    let metricname = sample.metricname.as_ref();
+    let belongs = |metricfamily: &Metricfamily<I>| {
+        metricfamily.family_name(sample) != metricname
+            || options::name(metricfamily) == Some(metricname)
+    };
+    if belongs(&metricfamily[index]) {
+        return None;
+    }
+    metricfamily
+        .iter()
+        .position(|metricfamily| belongs(metricfamily))
+}
+
+#[cfg(test)]
+mod tests;
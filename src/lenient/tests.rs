@@ -0,0 +1,138 @@
+use super::{Lenient, ParseIssue};
+use crate::error::Kind;
+use nom::error::ErrorKind;
+
+#[test]
+fn test_parse_lenient() {
+    let input = r#"# TYPE a counter
+a_total 1
+a_total{b="c} 2
+a_total{b="d"} 3
+x
+b 4
+# EOF
+"#;
+    let (exposition, issues) = super::parse_lenient(input);
+    assert_eq!(
+        issues,
+        [
+            ParseIssue {
+                line: 27..43,
+                offset: 42,
+                kind: Kind::Expected('"'),
+            },
+            ParseIssue {
+                line: 60..62,
+                offset: 61,
+                kind: Kind::Expected(' '),
+            },
+        ]
+    );
+    let samples = exposition
+        .metricset
+        .metricfamily
+        .iter()
+        .map(|metricfamily| {
+            metricfamily
+                .samples()
+                .map(|sample| sample.number)
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(samples, [vec!["1"], vec!["3"], vec!["4"]]);
+    assert_eq!(exposition.reconstruct(), input);
+}
+
+#[rstest::rstest]
+#[case("a 1\n", "a 1\n", ParseIssue { line: 4..4, offset: 4, kind: Kind::MissingEof })]
+#[case(
+    "a 1\n# EOF\nb 2\n",
+    "a 1\n# EOF\n",
+    ParseIssue { line: 10..14, offset: 10, kind: Kind::Nom(ErrorKind::Eof) }
+)]
+#[case("# FOO\n# EOF", "# FOO\n# EOF", ParseIssue { line: 0..6, offset: 2, kind: Kind::InvalidLine })]
+fn test_issue(#[case] input: &str, #[case] parsed: &str, #[case] issue: ParseIssue) {
+    let (exposition, issues) = super::parse_lenient(input);
+    assert_eq!(exposition.reconstruct(), parsed);
+    assert_eq!(issues, [issue]);
+}
+
+#[test]
+fn test_merge_interleaved_families() {
+    let input = r#"# TYPE a counter
+a_total 1
+# TYPE b gauge
+b 2
+a_total 3
+c 4
+# TYPE a counter
+a_total 5
+# EOF
+"#;
+    let (exposition, issues) = Lenient::default()
+        .merge_interleaved_families(true)
+        .parse(input);
+    assert_eq!(issues, []);
+    let families = exposition
+        .metricset
+        .metricfamily
+        .iter()
+        .map(|metricfamily| {
+            (
+                metricfamily.metric_descriptor.len(),
+                metricfamily
+                    .samples()
+                    .map(|sample| sample.raw)
+                    .collect::<Vec<_>>(),
+            )
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        families,
+        [
+            (1, vec!["a_total 1\n", "a_total 3\n", "a_total 5\n"]),
+            (1, vec!["b 2\n", "c 4\n"]),
+        ]
+    );
+    let model =
+        crate::model::MetricFamily::try_from(exposition.metricset.metricfamily[0].value.clone());
+    assert!(model.is_ok());
+}
+
+#[test]
+fn test_merge_conflicting_descriptor() {
+    let input = "# TYPE a counter\na_total 1\nb 2\n# TYPE a gauge\na 3\n# EOF\n";
+    let (_, issues) = Lenient::default()
+        .merge_interleaved_families(true)
+        .parse(input);
+    assert_eq!(
+        issues,
+        [ParseIssue {
+            line: 31..46,
+            offset: 31,
+            kind: Kind::ConflictingDescriptor,
+        }]
+    );
+}
+
+#[rstest::rstest]
+#[case(
+    "# TYPE a gauge\r\na 1 \r\na{b=\"c\"} 2\t\n# EOF",
+    "# TYPE a gauge\na 1\na{b=\"c\"} 2\n# EOF"
+)]
+#[case("a 1\r\n# EOF \r\n", "a 1\n# EOF\n")]
+fn test_normalize_line_endings(#[case] input: &str, #[case] expected: &str) {
+    let normalized = super::normalize_line_endings(input);
+    assert_eq!(normalized, expected);
+    assert!(crate::exposition::<_, nom::error::Error<_>>(&*normalized).is_ok());
+    assert!(crate::exposition::<_, nom::error::Error<_>>(input).is_err());
+}
+
+#[test]
+fn test_normalize_line_endings_borrowed() {
+    let input = "a 1\n# EOF\n";
+    assert!(matches!(
+        super::normalize_line_endings(input),
+        std::borrow::Cow::Borrowed(normalized) if normalized == input
+    ));
+}
@@ -1,22 +1,197 @@
+// Parsing never panics, whatever the input, see `tests::test_no_panic`.
+#![cfg_attr(
+    not(test),
+    deny(
+        clippy::expect_used,
+        clippy::panic,
+        clippy::unreachable,
+        clippy::unwrap_used
+    )
+)]
+
+pub mod approx;
+pub mod backfill;
+pub mod baseline;
+pub mod batch;
+pub mod bridge;
+pub mod cancel;
+#[cfg(feature = "prometheus-client")]
+pub mod client;
+pub mod clock;
+pub mod combinators;
+pub mod diagnostic;
+pub mod dictionary;
+pub mod encode;
+pub mod error;
+pub mod estimate;
+pub mod fault;
+pub mod fold;
+pub mod forward;
+pub mod glob;
+pub mod grammar;
+pub mod heap;
+pub mod iter;
+pub mod lenient;
+pub mod line;
+pub mod lint;
+pub mod located;
+mod map;
+pub mod matcher;
+pub mod middleware;
+pub mod model;
+pub mod options;
+#[cfg(feature = "serde")]
+pub mod pipeline;
+pub mod prometheus;
+pub mod reader;
+#[cfg(feature = "prometheus")]
+pub mod registry;
+pub mod regroup;
+pub mod replay;
+#[cfg(feature = "serde")]
+pub mod sarif;
+pub mod scrape;
+pub mod search;
+#[cfg(feature = "bytes")]
+pub mod shared;
+#[cfg(feature = "simulator")]
+pub mod simulator;
+pub mod sink;
+pub mod streaming;
+pub mod target;
+pub mod transform;
+pub mod validate;
+
 use nom::branch::alt;
-use nom::bytes::complete::{tag, tag_no_case, take_while};
+use nom::bytes::complete::{tag, tag_no_case, take_while, take_while_m_n};
 use nom::character::complete::{char, satisfy};
-use nom::combinator::{consumed, opt, recognize};
+use nom::combinator::{all_consuming, consumed, map_opt, opt, recognize};
 use nom::error::{ContextError, ParseError, context};
 use nom::multi::{fold_many0, fold_many1, many0, many1, separated_list0};
 use nom::number::complete::recognize_float;
 use nom::{AsChar, Compare, IResult, Input, Offset, Parser};
+use std::borrow::Cow;
+use std::fmt;
+use std::ops::{Deref, DerefMut, Range};
+use std::time::{SystemTime, UNIX_EPOCH};
 
 // RFC 5234 B.1.
 const DQUOTE: char = '"';
 const SP: char = ' ';
 const LF: char = '\n';
 
+// The media type of the exposition, see "Protocol Negotiation" in the specification.
+pub const CONTENT_TYPE: &str = "application/openmetrics-text; version=1.0.0; charset=utf-8";
+
 // https://github.com/prometheus/OpenMetrics/blob/main/specification/OpenMetrics.md#abnf
 
 #[derive(Clone, Debug, PartialEq)]
 pub struct Exposition<I> {
-    pub metricset: (I, Metricset<I>),
+    pub metricset: Spanned<I, Metricset<I>>,
+    // The `# EOF` line, with its line feed if any. Empty if parsing stopped before it, see
+    // `options::ParseOptions::exposition_until`.
+    pub eof: I,
+}
+impl<I> Exposition<I>
+where
+    I: AsRef<str>,
+{
+    // The byte range of `eof` from the start of the exposition.
+    pub fn eof_span(&self) -> Range<usize> {
+        let start = self.metricset.raw.as_ref().len();
+        start..start + self.eof.as_ref().len()
+    }
+
+    pub fn had_trailing_newline(&self) -> bool {
+        self.eof.as_ref().ends_with(LF)
+    }
+
+    // The parsed input, byte for byte. Nodes are not consulted, so editing them has no effect;
+    // splice `Raw::raw` of the untouched nodes to rewrite a part of the input.
+    pub fn reconstruct(&self) -> String {
+        [self.metricset.raw.as_ref(), self.eof.as_ref()].concat()
+    }
+}
+
+// The input a node was parsed from, as paired with it by the grammar.
+pub trait Raw {
+    fn raw(&self) -> &str;
+}
+impl<I, T> Raw for Spanned<I, T>
+where
+    I: AsRef<str>,
+{
+    fn raw(&self) -> &str {
+        self.raw.as_ref()
+    }
+}
+
+// A node with the input it was parsed from. `range` is the byte range of `raw` from the start of
+// the input given to the outermost parser, e.g. to `exposition`, or to `metricfamily` when it is
+// called on its own, so that `&input[range] == raw` for a `&str` input at any depth. The families
+// of `reader::parse_reader` are ranged from the start of everything read.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Spanned<I, T> {
+    pub range: Range<usize>,
+    pub raw: I,
+    pub value: T,
+}
+impl<I, T> Spanned<I, T> {
+    pub fn as_ref(&self) -> Spanned<&I, &T> {
+        Spanned {
+            range: self.range.clone(),
+            raw: &self.raw,
+            value: &self.value,
+        }
+    }
+
+    pub fn map<U, F>(self, f: F) -> Spanned<I, U>
+    where
+        F: FnOnce(T) -> U,
+    {
+        Spanned {
+            range: self.range,
+            raw: self.raw,
+            value: f(self.value),
+        }
+    }
+}
+impl<I, T> Deref for Spanned<I, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.value
+    }
+}
+impl<I, T> DerefMut for Spanned<I, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.value
+    }
+}
+
+// Moves every range of a node by `by` bytes, see `spanned`.
+pub(crate) trait Shift {
+    fn shift(&mut self, by: usize);
+}
+
+// `consumed`, with the range taken from the start of `origin`. `parser` sees a suffix of `origin`,
+// so the ranges within its output are moved from the start of that suffix.
+fn spanned<I, O, E, P>(origin: I, parser: P) -> impl Parser<I, Output = Spanned<I, O>, Error = E>
+where
+    I: Input + Offset,
+    O: Shift,
+    E: ParseError<I>,
+    P: Parser<I, Output = O, Error = E>,
+{
+    consumed(parser).map(move |(raw, mut value)| {
+        let start = origin.offset(&raw);
+        value.shift(start);
+        Spanned {
+            range: start..start + raw.input_len(),
+            raw,
+            value,
+        }
+    })
 }
 pub fn exposition<I, E>(input: I) -> IResult<I, Exposition<I>, E>
 where
@@ -27,20 +202,36 @@ where
     context(
         "exposition",
         (
-            consumed(metricset),
-            char(HASH),
-            char(SP),
-            tag(EOF),
-            opt(char(LF)),
+            spanned(input.clone(), metricset),
+            recognize((char(HASH), char(SP), tag(EOF), opt(char(LF)))),
         ),
     )
-    .map(|(metricset, _, _, _, _)| Exposition { metricset })
+    .map(|(metricset, eof)| Exposition { metricset, eof })
     .parse(input)
 }
 
+// `exposition` on bytes, e.g. a body not known to be UTF-8. Characters are classified byte by
+// byte, so bytes of multi-byte or invalid UTF-8 sequences are accepted where the grammar allows
+// any character but LF, `"` and `\`: in escaped strings and HELP text. See `unescape_bytes`.
+pub fn exposition_bytes<'a, E>(input: &'a [u8]) -> IResult<&'a [u8], Exposition<&'a [u8]>, E>
+where
+    E: ContextError<&'a [u8]> + ParseError<&'a [u8]>,
+{
+    exposition(input)
+}
+
 #[derive(Clone, Debug, PartialEq)]
 pub struct Metricset<I> {
-    pub metricfamily: Vec<(I, Metricfamily<I>)>,
+    pub metricfamily: Vec<Spanned<I, Metricfamily<I>>>,
+    // `# KEYWORD ...` lines captured by `options::ParseOptions::extension`, always empty in the
+    // strict grammar.
+    pub extension: Vec<Spanned<I, Extension<I>>>,
+    // Other `#` lines kept by `options::ParseOptions::comments`, always empty in the strict
+    // grammar.
+    pub comment: Vec<Spanned<I, Comment<I>>>,
+    // The later blocks of the families merged by `options::ParseOptions::merge_repeated_families`,
+    // always empty in the strict grammar.
+    pub merged: Vec<Spanned<I, Merged<I>>>,
 }
 pub fn metricset<I, E>(input: I) -> IResult<I, Metricset<I>, E>
 where
@@ -48,15 +239,76 @@ where
     I::Item: AsChar,
     E: ContextError<I> + ParseError<I>,
 {
-    context("metricset", many0(consumed(metricfamily)))
-        .map(|metricfamily| Metricset { metricfamily })
+    context("metricset", many0(spanned(input.clone(), metricfamily)))
+        .map(|metricfamily| Metricset {
+            metricfamily,
+            extension: Vec::new(),
+            comment: Vec::new(),
+            merged: Vec::new(),
+        })
         .parse(input)
 }
 
+// Not part of the ABNF: a comment line `# <keyword> <body>` with a registered keyword.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Extension<I> {
+    pub keyword: I,
+    pub body: I,
+}
+
+// Not part of the ABNF: any other `#` line, see `options::Comments`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Comment<I> {
+    // the line after `#`, without LF
+    pub text: I,
+}
+
+// Not part of the ABNF: a block of a family described again, see `Metricset::merged`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Merged<I> {
+    // the name the family is merged under: the metricname of its first descriptor, or else of
+    // its first sample
+    pub metricname: I,
+}
+
 #[derive(Clone, Debug, PartialEq)]
 pub struct Metricfamily<I> {
-    pub metric_descriptor: Vec<(I, MetricDescriptor<I>)>,
-    pub metric: Vec<(I, Metric<I>)>,
+    pub metric_descriptor: Vec<Spanned<I, MetricDescriptor<I>>>,
+    pub metric: Vec<Spanned<I, Metric<I>>>,
+}
+impl<I> Metricfamily<I> {
+    pub fn metric_type(&self) -> MetricType {
+        self.metric_descriptor
+            .iter()
+            .find_map(|metric_descriptor| match &metric_descriptor.value {
+                MetricDescriptor::Type { metric_type, .. } => Some(metric_type.value),
+                _ => None,
+            })
+            .unwrap_or(MetricType::Unknown)
+    }
+
+    pub fn samples(&self) -> impl Iterator<Item = &Spanned<I, Sample<I>>> {
+        self.metric.iter().flat_map(|metric| &metric.sample)
+    }
+}
+impl<I> Metricfamily<I>
+where
+    I: AsRef<str>,
+{
+    // The grammar groups every sample following the descriptors into their family. Samples whose
+    // name does not belong to the described family are treated as their own family.
+    pub fn family_name<'a>(&'a self, sample: &'a Sample<I>) -> &'a str {
+        let metricname = sample.metricname.as_ref();
+        self.metric_descriptor
+            .first()
+            .map(|metric_descriptor| metric_descriptor.metricname().as_ref())
+            .filter(|name| {
+                metricname
+                    .strip_prefix(name)
+                    .is_some_and(|suffix| self.metric_type().sample_suffixes().contains(&suffix))
+            })
+            .unwrap_or(metricname)
+    }
 }
 pub fn metricfamily<I, E>(input: I) -> IResult<I, Metricfamily<I>, E>
 where
@@ -67,8 +319,14 @@ where
     context(
         "metricfamily",
         alt((
-            (many1(consumed(metric_descriptor)), many0(consumed(metric))),
-            (many0(consumed(metric_descriptor)), many1(consumed(metric))),
+            (
+                many1(spanned(input.clone(), metric_descriptor)),
+                many0(spanned(input.clone(), metric)),
+            ),
+            (
+                many0(spanned(input.clone(), metric_descriptor)),
+                many1(spanned(input.clone(), metric)),
+            ),
         )),
     )
     .map(|(metric_descriptor, metric)| Metricfamily {
@@ -82,17 +340,38 @@ where
 pub enum MetricDescriptor<I> {
     Type {
         metricname: I,
-        metric_type: (I, MetricType),
+        metric_type: Spanned<I, MetricType>,
     },
     Help {
         metricname: I,
-        escaped_string: (I, EscapedString<I>),
+        escaped_string: Spanned<I, HelpEscapedString<I>>,
     },
     Unit {
         metricname: I,
         metricname_char: I,
     },
 }
+impl<I> MetricDescriptor<I> {
+    pub fn metricname(&self) -> &I {
+        match self {
+            Self::Type { metricname, .. }
+            | Self::Help { metricname, .. }
+            | Self::Unit { metricname, .. } => metricname,
+        }
+    }
+}
+impl<I> MetricDescriptor<I>
+where
+    I: AsRef<str>,
+{
+    // The unescaped text of a `# HELP` line.
+    pub fn help(&self) -> Option<Cow<'_, str>> {
+        match self {
+            Self::Help { escaped_string, .. } => Some(escaped_string.unescape()),
+            _ => None,
+        }
+    }
+}
 pub fn metric_descriptor<I, E>(input: I) -> IResult<I, MetricDescriptor<I>, E>
 where
     I: Compare<&'static str> + Input + Offset,
@@ -109,7 +388,7 @@ where
                 char(SP),
                 metricname,
                 char(SP),
-                consumed(metric_type),
+                spanned(input.clone(), metric_type),
                 char(LF),
             )
                 .map(|(_, _, _, _, metricname, _, metric_type, _)| {
@@ -125,7 +404,7 @@ where
                 char(SP),
                 metricname,
                 char(SP),
-                consumed(escaped_string),
+                spanned(input.clone(), help_escaped_string),
                 char(LF),
             )
                 .map(|(_, _, _, _, metricname, _, escaped_string, _)| {
@@ -157,7 +436,7 @@ where
 
 #[derive(Clone, Debug, PartialEq)]
 pub struct Metric<I> {
-    pub sample: Vec<(I, Sample<I>)>,
+    pub sample: Vec<Spanned<I, Sample<I>>>,
 }
 pub fn metric<I, E>(input: I) -> IResult<I, Metric<I>, E>
 where
@@ -165,12 +444,12 @@ where
     I::Item: AsChar,
     E: ContextError<I> + ParseError<I>,
 {
-    context("metric", many1(consumed(sample)))
+    context("metric", many1(spanned(input.clone(), sample)))
         .map(|sample| Metric { sample })
         .parse(input)
 }
 
-#[derive(Clone, Copy, Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum MetricType {
     Counter,
     Gauge,
@@ -181,6 +460,19 @@ pub enum MetricType {
     Summary,
     Unknown,
 }
+impl MetricType {
+    // https://github.com/prometheus/OpenMetrics/blob/main/specification/OpenMetrics.md#metric-types
+    pub fn sample_suffixes(self) -> &'static [&'static str] {
+        match self {
+            Self::Counter => &["_total", "_created"],
+            Self::Gauge | Self::Stateset | Self::Unknown => &[""],
+            Self::Histogram => &["_bucket", "_count", "_sum", "_created"],
+            Self::Gaugehistogram => &["_bucket", "_gcount", "_gsum"],
+            Self::Info => &["_info"],
+            Self::Summary => &["", "_count", "_sum", "_created"],
+        }
+    }
+}
 pub fn metric_type<I, E>(input: I) -> IResult<I, MetricType, E>
 where
     I: Compare<&'static str> + Input + Offset,
@@ -207,10 +499,12 @@ where
 #[derive(Clone, Debug, PartialEq)]
 pub struct Sample<I> {
     pub metricname: I,
-    pub labels: Option<(I, Labels<I>)>,
+    pub labels: Option<Spanned<I, Labels<I>>>,
     pub number: I,
     pub timestamp: Option<I>,
-    pub exemplar: Option<(I, Exemplar<I>)>,
+    pub exemplar: Option<Spanned<I, Exemplar<I>>>,
+    // Set by `options::ParseOptions::normalize_numbers`, see `normalize_number`.
+    pub normalized_number: Option<String>,
 }
 pub fn sample<I, E>(input: I) -> IResult<I, Sample<I>, E>
 where
@@ -218,35 +512,61 @@ where
     I::Item: AsChar,
     E: ContextError<I> + ParseError<I>,
 {
-    context(
-        "sample",
-        (
-            metricname,
-            opt(consumed(labels)),
-            char(SP),
-            number,
-            opt((char(SP), timestamp)),
-            opt(consumed(exemplar)),
-            char(LF),
-        ),
-    )
-    .map(
-        |(metricname, labels, _, number, timestamp, exemplar, _)| Sample {
-            metricname,
-            labels,
-            number,
-            timestamp: timestamp.map(|(_, timestamp)| timestamp),
-            exemplar,
-        },
+    context("sample", (sample_series, sample_value(input.clone())))
+        .map(
+            |((metricname, labels), (number, timestamp, exemplar))| Sample {
+                metricname,
+                labels,
+                number,
+                timestamp,
+                exemplar,
+                normalized_number: None,
+            },
+        )
+        .parse(input)
+}
+
+// The metricname and labels at the start of a sample.
+#[allow(clippy::type_complexity)]
+fn sample_series<I, E>(input: I) -> IResult<I, (I, Option<Spanned<I, Labels<I>>>), E>
+where
+    I: Input + Offset,
+    I::Item: AsChar,
+    E: ContextError<I> + ParseError<I>,
+{
+    (metricname, opt_from('{', spanned(input.clone(), labels))).parse(input)
+}
+
+// The rest of a sample after `sample_series`, with spans relative to `origin`, the start of the
+// sample.
+#[allow(clippy::type_complexity)]
+fn sample_value<I, E>(
+    origin: I,
+) -> impl Parser<I, Output = (I, Option<I>, Option<Spanned<I, Exemplar<I>>>), Error = E>
+where
+    I: Compare<&'static str> + Input + Offset,
+    I::Item: AsChar,
+    E: ContextError<I> + ParseError<I>,
+{
+    (
+        char(SP),
+        number,
+        opt((char(SP), timestamp)),
+        opt_from(SP, spanned(origin, exemplar)),
+        char(LF),
     )
-    .parse(input)
+        .map(|(_, number, timestamp, exemplar, _)| {
+            (number, timestamp.map(|(_, timestamp)| timestamp), exemplar)
+        })
 }
 
 #[derive(Clone, Debug, PartialEq)]
 pub struct Exemplar<I> {
-    pub labels: (I, Labels<I>),
+    pub labels: Spanned<I, Labels<I>>,
     pub number: I,
     pub timestamp: Option<I>,
+    // Set by `options::ParseOptions::normalize_numbers`, see `normalize_number`.
+    pub normalized_number: Option<String>,
 }
 pub fn exemplar<I, E>(input: I) -> IResult<I, Exemplar<I>, E>
 where
@@ -260,7 +580,7 @@ where
             char(SP),
             char(HASH),
             char(SP),
-            consumed(labels),
+            spanned(input.clone(), labels),
             char(SP),
             number,
             opt((char(SP), timestamp)),
@@ -270,13 +590,14 @@ where
         labels,
         number,
         timestamp: timestamp.map(|(_, timestamp)| timestamp),
+        normalized_number: None,
     })
     .parse(input)
 }
 
 #[derive(Clone, Debug, PartialEq)]
 pub struct Labels<I> {
-    pub label: Vec<(I, Label<I>)>,
+    pub label: Vec<Spanned<I, Label<I>>>,
 }
 pub fn labels<I, E>(input: I) -> IResult<I, Labels<I>, E>
 where
@@ -288,7 +609,7 @@ where
         "labels",
         (
             char('{'),
-            separated_list0(char(COMMA), consumed(label)),
+            separated_list0(char(COMMA), spanned(input.clone(), label)),
             char('}'),
         ),
     )
@@ -299,7 +620,7 @@ where
 #[derive(Clone, Debug, PartialEq)]
 pub struct Label<I> {
     pub label_name: I,
-    pub escaped_string: (I, EscapedString<I>),
+    pub escaped_string: Spanned<I, EscapedString<I>>,
 }
 pub fn label<I, E>(input: I) -> IResult<I, Label<I>, E>
 where
@@ -313,7 +634,7 @@ where
             label_name,
             char(EQ),
             char(DQUOTE),
-            consumed(escaped_string),
+            spanned(input.clone(), escaped_string),
             char(DQUOTE),
         ),
     )
@@ -336,7 +657,8 @@ where
             realnumber,
             recognize((
                 opt(satisfy(is_sign)),
-                alt((tag_no_case("inf"), tag_no_case("infinity"))),
+                // try `infinity` before `inf`
+                alt((tag_no_case("infinity"), tag_no_case("inf"))),
             )),
             recognize(tag_no_case("nan")),
         )),
@@ -344,8 +666,172 @@ where
     .parse(input)
 }
 
+// `number`, converted to its value.
+pub fn number_value<I, E>(input: I) -> IResult<I, f64, E>
+where
+    I: AsRef<str> + Compare<&'static str> + Input + Offset,
+    I::Item: AsChar,
+    E: ContextError<I> + ParseError<I>,
+{
+    map_opt(number, |number: I| {
+        let number = number.as_ref();
+        let (sign, magnitude) = match number.strip_prefix('-') {
+            Some(magnitude) => (-1., magnitude),
+            None => (1., number.strip_prefix('+').unwrap_or(number)),
+        };
+        if magnitude.eq_ignore_ascii_case("inf") || magnitude.eq_ignore_ascii_case("infinity") {
+            Some(sign * f64::INFINITY)
+        } else if magnitude.eq_ignore_ascii_case("nan") {
+            Some(f64::NAN)
+        } else {
+            number.parse().ok()
+        }
+    })
+    .parse(input)
+}
+
+// The value of `s` if it is a `number` as a whole, e.g. a `le` or `quantile` label value.
+pub fn parse_number(s: &str) -> Option<f64> {
+    all_consuming(number_value::<_, nom::error::Error<_>>)
+        .parse(s)
+        .ok()
+        .map(|(_, value)| value)
+}
+
+// The canonical text of the `number` `s`, so that equal values are written alike: the shortest
+// decimal parsing back to the same value, in scientific notation for exponents below -4 or above
+// 20 (e.g. `0042` -> `42`, `1.890E-07` -> `1.89e-7`), `NaN`, `+Inf` or `-Inf`.
+pub fn normalize_number(s: &str) -> Option<String> {
+    let value = parse_number(s)?;
+    if value.is_nan() {
+        return Some("NaN".to_owned());
+    }
+    if value.is_infinite() {
+        return Some(if value > 0. { "+Inf" } else { "-Inf" }.to_owned());
+    }
+    let scientific = format!("{value:e}");
+    let exponent = scientific
+        .rsplit_once('e')
+        .and_then(|(_, exponent)| exponent.parse::<i32>().ok())?;
+    if (-4..=20).contains(&exponent) {
+        Some(value.to_string())
+    } else {
+        Some(scientific)
+    }
+}
+
 pub use self::realnumber as timestamp;
 
+// A `timestamp` without the rounding of `f64`, truncated to nanoseconds. `nanos` is always less
+// than one second, also for negative timestamps, so that the derived order is chronological.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Timestamp {
+    pub seconds: i64,
+    pub nanos: u32,
+}
+impl Timestamp {
+    // `s` has to be a `timestamp` as a whole.
+    pub fn parse(s: &str) -> Option<Self> {
+        let (_, s) = all_consuming(timestamp::<_, nom::error::Error<_>>)
+            .parse(s)
+            .ok()?;
+        let (negative, s) = match s.strip_prefix('-') {
+            Some(s) => (true, s),
+            None => (false, s.strip_prefix('+').unwrap_or(s)),
+        };
+        let (mantissa, exponent) = match s.find(['e', 'E']) {
+            Some(position) => (&s[..position], s[position + 1..].parse::<i32>().ok()?),
+            None => (s, 0),
+        };
+        let (integer, fraction) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+        let digits = integer.bytes().chain(fraction.bytes()).map(|b| b - b'0');
+        // position of the decimal point within the digits
+        let point = integer.len() as i64 + i64::from(exponent);
+
+        let mut seconds = 0_i64;
+        let mut nanos = 0_u32;
+        for (position, digit) in (0..).zip(digits) {
+            if position < point {
+                seconds = seconds.checked_mul(10)?.checked_add(i64::from(digit))?;
+            } else if position < point + 9 {
+                nanos += u32::from(digit) * 10_u32.pow((point + 8 - position) as u32);
+            }
+        }
+        // trailing zeros implied by the exponent
+        if seconds != 0 {
+            for _ in integer.len() + fraction.len()..usize::try_from(point).unwrap_or(0) {
+                seconds = seconds.checked_mul(10)?;
+            }
+        }
+        if negative && nanos > 0 {
+            Some(Self {
+                seconds: -seconds - 1,
+                nanos: 1_000_000_000 - nanos,
+            })
+        } else {
+            Some(Self {
+                seconds: if negative { -seconds } else { seconds },
+                nanos,
+            })
+        }
+    }
+
+    // e.g. to stamp samples, see `clock::Clock::timestamp`
+    pub fn from_system_time(time: SystemTime) -> Self {
+        match time.duration_since(UNIX_EPOCH) {
+            Ok(duration) => Self {
+                seconds: i64::try_from(duration.as_secs()).unwrap_or(i64::MAX),
+                nanos: duration.subsec_nanos(),
+            },
+            Err(e) => {
+                let duration = e.duration();
+                let seconds = i64::try_from(duration.as_secs()).unwrap_or(i64::MAX);
+                let nanos = duration.subsec_nanos();
+                if nanos > 0 {
+                    Self {
+                        seconds: -seconds - 1,
+                        nanos: 1_000_000_000 - nanos,
+                    }
+                } else {
+                    Self {
+                        seconds: -seconds,
+                        nanos,
+                    }
+                }
+            }
+        }
+    }
+}
+// A `timestamp` parsing back to the same value, without trailing zeros. `nanos` of a second or
+// more, e.g. set by hand, are carried into the seconds.
+impl fmt::Display for Timestamp {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let total = i128::from(self.seconds) * 1_000_000_000 + i128::from(self.nanos);
+        let sign = if total < 0 { "-" } else { "" };
+        let (seconds, nanos) = (
+            total.unsigned_abs() / 1_000_000_000,
+            total.unsigned_abs() % 1_000_000_000,
+        );
+        write!(f, "{sign}{seconds}")?;
+        if nanos > 0 {
+            let fraction = format!("{nanos:09}");
+            write!(f, ".{}", fraction.trim_end_matches('0'))?;
+        }
+        Ok(())
+    }
+}
+pub fn timestamp_value<I, E>(input: I) -> IResult<I, Timestamp, E>
+where
+    I: AsRef<str> + Input + Offset,
+    I::Item: AsChar,
+    E: ContextError<I> + ParseError<I>,
+{
+    map_opt(timestamp, |timestamp: I| {
+        Timestamp::parse(timestamp.as_ref())
+    })
+    .parse(input)
+}
+
 pub fn realnumber<I, E>(input: I) -> IResult<I, I, E>
 where
     I: Input + Offset,
@@ -427,7 +913,77 @@ fn is_label_name_initial_char(c: char) -> bool {
 }
 
 #[derive(Clone, Debug, PartialEq)]
-pub struct EscapedString<I>(pub Vec<(I, EscapedStringFragment<I>)>);
+pub struct EscapedString<I>(pub Vec<Spanned<I, EscapedStringFragment<I>>>);
+impl<I> EscapedString<I>
+where
+    I: AsRef<str>,
+{
+    // Borrows the input unless there is an escape sequence.
+    pub fn unescape(&self) -> Cow<'_, str> {
+        match &self.0[..] {
+            [] => Cow::Borrowed(""),
+            [
+                Spanned {
+                    value: EscapedStringFragment::Normal(normal),
+                    ..
+                },
+            ] => Cow::Borrowed(normal.as_ref()),
+            fragments => Cow::Owned(
+                fragments
+                    .iter()
+                    .map(|fragment| match &fragment.value {
+                        EscapedStringFragment::Normal(normal) => normal.as_ref(),
+                        EscapedStringFragment::Lf => "\n",
+                        EscapedStringFragment::Dquote => "\"",
+                        EscapedStringFragment::Bs => "\\",
+                    })
+                    .collect(),
+            ),
+        }
+    }
+}
+impl<I> EscapedString<I>
+where
+    I: AsRef<[u8]>,
+{
+    // `unescape` for any bytes, e.g. parsed by `exposition_bytes`.
+    pub fn unescape_bytes(&self) -> Cow<'_, [u8]> {
+        match &self.0[..] {
+            [] => Cow::Borrowed(b""),
+            [
+                Spanned {
+                    value: EscapedStringFragment::Normal(normal),
+                    ..
+                },
+            ] => Cow::Borrowed(normal.as_ref()),
+            fragments => Cow::Owned(
+                fragments
+                    .iter()
+                    .flat_map(|fragment| match &fragment.value {
+                        EscapedStringFragment::Normal(normal) => normal.as_ref(),
+                        EscapedStringFragment::Lf => b"\n",
+                        EscapedStringFragment::Dquote => b"\"",
+                        EscapedStringFragment::Bs => b"\\",
+                    })
+                    .copied()
+                    .collect(),
+            ),
+        }
+    }
+}
+// The inverse of `EscapedString::unescape`.
+pub fn escape(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            '"' => escaped.push_str("\\\""),
+            '\n' => escaped.push_str("\\n"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub enum EscapedStringFragment<I> {
     Normal(I),
@@ -443,28 +999,166 @@ where
 {
     context(
         "escaped_string",
-        many0(consumed(alt((
-            recognize(fold_many1(
-                alt((
-                    satisfy(is_normal_char).map(|_| ()),
-                    (char(BS), satisfy(|c| is_normal_char(c) && c != 'n')).map(|_| ()),
-                )),
-                || (),
-                |_, _| (),
-            ))
-            .map(EscapedStringFragment::Normal),
-            (char(BS), char('n')).map(|_| EscapedStringFragment::Lf),
-            (char(BS), char(DQUOTE)).map(|_| EscapedStringFragment::Dquote),
-            (char(BS), char(BS)).map(|_| EscapedStringFragment::Bs),
-        )))),
+        many0(spanned(
+            input.clone(),
+            alt((
+                recognize(fold_many1(
+                    alt((
+                        item(is_normal_char).map(|_| ()),
+                        (char(BS), item(|c| is_normal_char(c) && c != 'n')).map(|_| ()),
+                    )),
+                    || (),
+                    |_, _| (),
+                ))
+                .map(EscapedStringFragment::Normal),
+                (char(BS), char('n')).map(|_| EscapedStringFragment::Lf),
+                (char(BS), char(DQUOTE)).map(|_| EscapedStringFragment::Dquote),
+                (char(BS), char(BS)).map(|_| EscapedStringFragment::Bs),
+            )),
+        )),
     )
     .map(EscapedString)
     .parse(input)
 }
 
+// Unlike `satisfy`, this advances by one input item, so bytes outside UTF-8 stay one byte wide
+// in `&[u8]`.
+fn item<I, E>(cond: impl Fn(char) -> bool) -> impl Parser<I, Output = I, Error = E>
+where
+    I: Input,
+    I::Item: AsChar,
+    E: ParseError<I>,
+{
+    take_while_m_n(1, 1, move |c: I::Item| cond(c.as_char()))
+}
+
+// `opt(parser)` for a parser starting with `c`: once `c` is seen, `parser` must succeed, so that
+// its error is reported rather than the one of whatever follows.
+fn opt_from<I, O, E, P>(c: char, mut parser: P) -> impl Parser<I, Output = Option<O>, Error = E>
+where
+    I: Input,
+    I::Item: AsChar,
+    E: ParseError<I>,
+    P: Parser<I, Output = O, Error = E>,
+{
+    move |input: I| match input.iter_elements().next() {
+        Some(item) if item.as_char() == c => parser.parse(input).map(|(input, o)| (input, Some(o))),
+        _ => Ok((input, None)),
+    }
+}
+
 fn is_normal_char(c: char) -> bool {
     c != LF && c != DQUOTE && c != BS
 }
 
+// HELP text is escaped like in the Prometheus text format: only `\\` and `\n`, a `"` is literal.
+#[derive(Clone, Debug, PartialEq)]
+pub struct HelpEscapedString<I>(pub Vec<Spanned<I, HelpEscapedStringFragment<I>>>);
+impl<I> HelpEscapedString<I>
+where
+    I: AsRef<str>,
+{
+    // Borrows the input unless there is an escape sequence.
+    pub fn unescape(&self) -> Cow<'_, str> {
+        match &self.0[..] {
+            [] => Cow::Borrowed(""),
+            [
+                Spanned {
+                    value: HelpEscapedStringFragment::Normal(normal),
+                    ..
+                },
+            ] => Cow::Borrowed(normal.as_ref()),
+            fragments => Cow::Owned(
+                fragments
+                    .iter()
+                    .map(|fragment| match &fragment.value {
+                        HelpEscapedStringFragment::Normal(normal) => normal.as_ref(),
+                        HelpEscapedStringFragment::Lf => "\n",
+                        HelpEscapedStringFragment::Bs => "\\",
+                    })
+                    .collect(),
+            ),
+        }
+    }
+}
+impl<I> HelpEscapedString<I>
+where
+    I: AsRef<[u8]>,
+{
+    // `unescape` for any bytes, e.g. parsed by `exposition_bytes`.
+    pub fn unescape_bytes(&self) -> Cow<'_, [u8]> {
+        match &self.0[..] {
+            [] => Cow::Borrowed(b""),
+            [
+                Spanned {
+                    value: HelpEscapedStringFragment::Normal(normal),
+                    ..
+                },
+            ] => Cow::Borrowed(normal.as_ref()),
+            fragments => Cow::Owned(
+                fragments
+                    .iter()
+                    .flat_map(|fragment| match &fragment.value {
+                        HelpEscapedStringFragment::Normal(normal) => normal.as_ref(),
+                        HelpEscapedStringFragment::Lf => b"\n",
+                        HelpEscapedStringFragment::Bs => b"\\",
+                    })
+                    .copied()
+                    .collect(),
+            ),
+        }
+    }
+}
+// The inverse of `HelpEscapedString::unescape`.
+pub fn escape_help(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum HelpEscapedStringFragment<I> {
+    Normal(I),
+    Lf,
+    Bs,
+}
+pub fn help_escaped_string<I, E>(input: I) -> IResult<I, HelpEscapedString<I>, E>
+where
+    I: Input + Offset,
+    I::Item: AsChar,
+    E: ContextError<I> + ParseError<I>,
+{
+    context(
+        "help_escaped_string",
+        many0(spanned(
+            input.clone(),
+            alt((
+                recognize(fold_many1(
+                    alt((
+                        item(is_help_normal_char).map(|_| ()),
+                        (char(BS), item(|c| is_help_normal_char(c) && c != 'n')).map(|_| ()),
+                    )),
+                    || (),
+                    |_, _| (),
+                ))
+                .map(HelpEscapedStringFragment::Normal),
+                (char(BS), char('n')).map(|_| HelpEscapedStringFragment::Lf),
+                (char(BS), char(BS)).map(|_| HelpEscapedStringFragment::Bs),
+            )),
+        )),
+    )
+    .map(HelpEscapedString)
+    .parse(input)
+}
+
+fn is_help_normal_char(c: char) -> bool {
+    c != LF && c != BS
+}
+
 #[cfg(test)]
 mod tests;
@@ -0,0 +1,165 @@
+use crate::{HelpEscapedString, MetricDescriptor, MetricType, Sample, Shift, Spanned};
+use nom::branch::alt;
+use nom::bytes::complete::tag;
+use nom::character::complete::char;
+use nom::combinator::opt;
+use nom::error::{ContextError, Error, ErrorKind, ParseError, context};
+use nom::{AsChar, Compare, Finish, IResult, Input, Offset, Parser};
+
+// A line of the exposition, for processing a scrape without building the tree.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Event<I> {
+    Type {
+        metricname: I,
+        metric_type: Spanned<I, MetricType>,
+    },
+    Help {
+        metricname: I,
+        escaped_string: Spanned<I, HelpEscapedString<I>>,
+    },
+    Unit {
+        metricname: I,
+        metricname_char: I,
+    },
+    Sample(Sample<I>),
+    Eof,
+}
+
+impl<I> From<MetricDescriptor<I>> for Event<I> {
+    fn from(metric_descriptor: MetricDescriptor<I>) -> Self {
+        match metric_descriptor {
+            MetricDescriptor::Type {
+                metricname,
+                metric_type,
+            } => Self::Type {
+                metricname,
+                metric_type,
+            },
+            MetricDescriptor::Help {
+                metricname,
+                escaped_string,
+            } => Self::Help {
+                metricname,
+                escaped_string,
+            },
+            MetricDescriptor::Unit {
+                metricname,
+                metricname_char,
+            } => Self::Unit {
+                metricname,
+                metricname_char,
+            },
+        }
+    }
+}
+
+impl<I> Shift for Event<I> {
+    fn shift(&mut self, by: usize) {
+        match self {
+            Self::Type { metric_type, .. } => metric_type.shift(by),
+            Self::Help { escaped_string, .. } => escaped_string.shift(by),
+            Self::Sample(sample) => sample.shift(by),
+            Self::Unit { .. } | Self::Eof => {}
+        }
+    }
+}
+
+// A descriptor or sample line including its LF, or `# EOF` and its optional trailing LF.
+pub fn parse_line<I, E>(input: I) -> IResult<I, Event<I>, E>
+where
+    I: Compare<&'static str> + Input + Offset,
+    I::Item: AsChar,
+    E: ContextError<I> + ParseError<I>,
+{
+    context(
+        "line",
+        alt((
+            crate::metric_descriptor.map(Event::from),
+            crate::sample.map(Event::Sample),
+            (
+                char(crate::HASH),
+                char(crate::SP),
+                tag(crate::EOF),
+                opt(char(crate::LF)),
+            )
+                .map(|_| Event::Eof),
+        )),
+    )
+    .parse(input)
+}
+
+// The lines of `input` with their span, through `Event::Eof` or the first invalid line.
+// Only lines are validated, not their order, e.g. a `# TYPE` in the middle of a family.
+pub fn events(input: &str) -> Events<'_> {
+    Events {
+        whole: input,
+        input,
+        done: false,
+    }
+}
+
+// Continues `events` of the same `input` where `cursor` was taken, e.g. after a restart. `None`
+// if the offset is out of `input` or not at a character boundary.
+pub fn resume(input: &str, cursor: Cursor) -> Option<Events<'_>> {
+    Some(Events {
+        whole: input,
+        input: input.get(cursor.offset..)?,
+        done: cursor.done,
+    })
+}
+
+// The position of `Events` in its input, to checkpoint a long-running job.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Cursor {
+    // in bytes, at the start of the next line
+    pub offset: usize,
+    // whether `Event::Eof` or an error was yielded
+    pub done: bool,
+}
+
+pub struct Events<'a> {
+    whole: &'a str,
+    input: &'a str,
+    done: bool,
+}
+
+impl Events<'_> {
+    pub fn cursor(&self) -> Cursor {
+        Cursor {
+            offset: self.whole.offset(self.input),
+            done: self.done,
+        }
+    }
+}
+
+impl<'a> Iterator for Events<'a> {
+    type Item = Result<Spanned<&'a str, Event<&'a str>>, Error<&'a str>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        if self.input.is_empty() {
+            self.done = true;
+            return Some(Err(Error::from_error_kind(self.input, ErrorKind::Eof)));
+        }
+        match crate::spanned(self.whole, parse_line)
+            .parse(self.input)
+            .finish()
+        {
+            Ok((input, line)) => {
+                self.input = input;
+                self.done = line.value == Event::Eof;
+                Some(Ok(line))
+            }
+            Err(e) => {
+                self.done = true;
+                Some(Err(e))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests;
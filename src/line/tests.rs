@@ -0,0 +1,89 @@
+use super::Event;
+use crate::{MetricType, Spanned};
+
+#[test]
+fn test_events() {
+    let input = r#"# TYPE a counter
+# HELP a A \"counter\".
+# UNIT a_seconds seconds
+a_total{b="c"} 1
+# EOF
+"#;
+    let events = super::events(input).collect::<Result<Vec<_>, _>>().unwrap();
+    assert_eq!(
+        events.iter().map(|event| event.raw).collect::<String>(),
+        input,
+    );
+    assert!(matches!(
+        events[0].value,
+        Event::Type {
+            metricname: "a",
+            metric_type: Spanned {
+                value: MetricType::Counter,
+                ..
+            },
+        },
+    ));
+    match &events[1].value {
+        Event::Help { escaped_string, .. } => {
+            assert_eq!(escaped_string.unescape(), r#"A \"counter\"."#)
+        }
+        event => panic!("{event:?}"),
+    }
+    assert!(matches!(
+        events[2].value,
+        Event::Unit {
+            metricname_char: "seconds",
+            ..
+        },
+    ));
+    assert!(matches!(&events[3].value, Event::Sample(sample) if sample.number == "1"));
+    assert_eq!(events[3].range, 66..83);
+    assert_eq!(events[4].value, Event::Eof);
+}
+
+#[rstest::rstest]
+#[case("a 1\nb\n# EOF\n", &[true, false])]
+#[case("a 1\n", &[true, false])]
+#[case("# EOF\nb 1\n", &[true])]
+fn test_events_end(#[case] input: &str, #[case] expected: &[bool]) {
+    let events = super::events(input)
+        .map(|event| event.is_ok())
+        .collect::<Vec<_>>();
+    assert_eq!(events, expected);
+}
+
+#[test]
+fn test_resume() {
+    let input = "a 1\nb 2\nc 3\n# EOF\n";
+    let mut events = super::events(input);
+    events.next();
+    events.next();
+    let cursor = events.cursor();
+    assert_eq!(
+        cursor,
+        super::Cursor {
+            offset: 8,
+            done: false,
+        },
+    );
+    let resumed = super::resume(input, cursor)
+        .unwrap()
+        .map(|event| event.unwrap().raw)
+        .collect::<Vec<_>>();
+    assert_eq!(resumed, ["c 3\n", "# EOF\n"]);
+    assert_eq!(
+        resumed,
+        events.map(|event| event.unwrap().raw).collect::<Vec<_>>()
+    );
+
+    let mut events = super::resume(input, cursor).unwrap();
+    events.by_ref().for_each(drop);
+    assert_eq!(super::resume(input, events.cursor()).unwrap().next(), None);
+
+    let cursor = super::Cursor {
+        offset: input.len() + 1,
+        done: false,
+    };
+    assert!(super::resume(input, cursor).is_none());
+}
@@ -0,0 +1,448 @@
+use crate::diagnostic::{Diagnostic, Severity};
+use crate::target::Target;
+use crate::{Exposition, MetricDescriptor, MetricType, Metricset, Sample};
+use nom::{Input, Offset};
+use std::collections::{BTreeMap, BTreeSet};
+use std::ops::Range;
+
+pub const LABEL_POLICY: &str = "label-policy";
+pub const RESERVED_SUFFIX: &str = "reserved-suffix";
+pub const HELP_UNIT_CONSISTENCY: &str = "help-unit-consistency";
+pub const LABEL_VALUE_LENGTH: &str = "label-value-length";
+pub const JOB_INSTANCE: &str = "job-instance";
+pub const INTERLEAVED_FAMILIES: &str = "interleaved-families";
+pub const MISSING_EOF: &str = "missing-eof";
+
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LabelPolicy {
+    // family name -> the only label names its samples may use;
+    // families not listed here are not restricted
+    pub allow: BTreeMap<String, BTreeSet<String>>,
+    // label names no sample may use
+    pub deny: BTreeSet<String>,
+}
+
+pub fn label_policy<I>(exposition: &Exposition<I>, policy: &LabelPolicy) -> Vec<Diagnostic>
+where
+    I: AsRef<str> + Input + Offset,
+{
+    let mut diagnostics = Vec::new();
+    for metricfamily in &exposition.metricset.metricfamily {
+        for sample in metricfamily.samples() {
+            let family = metricfamily.family_name(sample);
+            let allow = policy.allow.get(family);
+            for label in sample.labels.iter().flat_map(|labels| &labels.label) {
+                let label_name = label.label_name.as_ref();
+                let message = if policy.deny.contains(label_name) {
+                    format!("label `{label_name}` is denied")
+                } else if allow.is_some_and(|allow| !allow.contains(label_name)) {
+                    format!("label `{label_name}` is not allowed on `{family}`")
+                } else {
+                    continue;
+                };
+                diagnostics.push(Diagnostic {
+                    rule: LABEL_POLICY,
+                    severity: Severity::Error,
+                    message,
+                    span: Some(label.range.clone()),
+                    family: Some(family.to_owned()),
+                    series: Some(series(sample)),
+                });
+            }
+        }
+    }
+    diagnostics
+}
+
+// Suffixes that tooling associates with specific types, whatever the declared type is.
+const RESERVED_SUFFIXES: &[(&str, &[MetricType])] = &[
+    ("_total", &[MetricType::Counter]),
+    (
+        "_created",
+        &[
+            MetricType::Counter,
+            MetricType::Histogram,
+            MetricType::Summary,
+        ],
+    ),
+    (
+        "_bucket",
+        &[MetricType::Histogram, MetricType::Gaugehistogram],
+    ),
+    ("_count", &[MetricType::Histogram, MetricType::Summary]),
+    ("_sum", &[MetricType::Histogram, MetricType::Summary]),
+    ("_gcount", &[MetricType::Gaugehistogram]),
+    ("_gsum", &[MetricType::Gaugehistogram]),
+    ("_info", &[MetricType::Info]),
+];
+
+// Flags family names ending in a suffix reserved for samples of other types, e.g. a gauge
+// named `foo_count` or an untyped `foo_total`. A counter named `foo_total` is flagged as well
+// since its samples would be named `foo_total_total`.
+pub fn reserved_suffix<I>(exposition: &Exposition<I>) -> Vec<Diagnostic>
+where
+    I: AsRef<str> + Input + Offset,
+{
+    let origin = &exposition.metricset.raw;
+    families(exposition)
+        .into_iter()
+        .filter_map(|(metricname, metric_type)| {
+            let name = metricname.as_ref();
+            let (suffix, types) = RESERVED_SUFFIXES
+                .iter()
+                .find(|(suffix, _)| name.ends_with(suffix))?;
+            let types = types
+                .iter()
+                .map(|metric_type| format!("{metric_type:?}").to_lowercase())
+                .collect::<Vec<_>>()
+                .join("/");
+            let message = if metric_type.sample_suffixes().contains(suffix) {
+                format!("`{name}` ends with `{suffix}`, which is appended to {types} samples")
+            } else {
+                format!("`{name}` ends with `{suffix}`, which is reserved for {types} samples")
+            };
+            Some(Diagnostic {
+                rule: RESERVED_SUFFIX,
+                severity: Severity::Warning,
+                message,
+                span: Some(span(origin, metricname)),
+                family: Some(name.to_owned()),
+                series: None,
+            })
+        })
+        .collect()
+}
+
+// Catches copy-paste drift between related families:
+// - families whose names only differ by their unit suffix but declare different units,
+//   e.g. `foo_latency_seconds` (seconds) and `foo_latency_milliseconds` (milliseconds)
+// - a HELP naming another unit of the same kind as the one of its family (its UNIT, or else
+//   the last segment of its name), e.g. `Latency in milliseconds.` for `foo_latency_seconds`
+pub fn help_unit_consistency<I>(exposition: &Exposition<I>) -> Vec<Diagnostic>
+where
+    I: AsRef<str> + Input + Offset,
+{
+    let origin = &exposition.metricset.raw;
+    let mut units = BTreeMap::<&str, (&str, &str)>::new();
+    let mut diagnostics = Vec::new();
+    for metricfamily in &exposition.metricset.metricfamily {
+        let mut unit = None;
+        for metric_descriptor in &metricfamily.metric_descriptor {
+            let name = metric_descriptor.metricname().as_ref();
+            if let MetricDescriptor::Unit {
+                metricname_char, ..
+            } = &metric_descriptor.value
+            {
+                let declared = metricname_char.as_ref();
+                unit = unit.or(Some(declared));
+                let base = name
+                    .strip_suffix(declared)
+                    .and_then(|name| name.strip_suffix('_'))
+                    .unwrap_or(name);
+                let (other, other_unit) = *units.entry(base).or_insert((name, declared));
+                if other_unit != declared {
+                    diagnostics.push(Diagnostic {
+                        rule: HELP_UNIT_CONSISTENCY,
+                        severity: Severity::Warning,
+                        message: format!(
+                            "`{name}` has unit `{declared}` but `{other}` has unit `{other_unit}`"
+                        ),
+                        span: Some(span(origin, metricname_char)),
+                        family: Some(name.to_owned()),
+                        series: None,
+                    });
+                }
+            }
+        }
+        for metric_descriptor in &metricfamily.metric_descriptor {
+            let MetricDescriptor::Help { escaped_string, .. } = &metric_descriptor.value else {
+                continue;
+            };
+            let name = metric_descriptor.metricname().as_ref();
+            let Some(unit) = unit.or_else(|| name.rsplit('_').next()) else {
+                continue;
+            };
+            let Some(kind) = UNITS.iter().find(|kind| kind.contains(&unit)) else {
+                continue;
+            };
+            let help = escaped_string.unescape();
+            let named = help
+                .split(|c: char| !c.is_ascii_alphabetic())
+                .map(str::to_ascii_lowercase)
+                .filter_map(|word| {
+                    kind.iter()
+                        .find(|unit| **unit == word || unit.strip_suffix('s') == Some(&word))
+                })
+                .collect::<Vec<_>>();
+            if let Some(other) = named.first()
+                && !named.contains(&&unit)
+            {
+                diagnostics.push(Diagnostic {
+                    rule: HELP_UNIT_CONSISTENCY,
+                    severity: Severity::Warning,
+                    message: format!("HELP of `{name}` names `{other}` but its unit is `{unit}`"),
+                    span: Some(escaped_string.range.clone()),
+                    family: Some(name.to_owned()),
+                    series: None,
+                });
+            }
+        }
+    }
+    diagnostics
+}
+
+// Units of the same kind, as a HELP may name them.
+const UNITS: &[&[&str]] = &[
+    &[
+        "nanoseconds",
+        "microseconds",
+        "milliseconds",
+        "seconds",
+        "minutes",
+        "hours",
+        "days",
+    ],
+    &[
+        "bits",
+        "bytes",
+        "kilobytes",
+        "megabytes",
+        "gigabytes",
+        "kibibytes",
+        "mebibytes",
+        "gibibytes",
+    ],
+    &["celsius", "fahrenheit", "kelvin"],
+];
+
+// Flags sample label values longer than `max` characters once unescaped, which bloat every
+// series they are part of. `transform::truncate_label_values` shortens them.
+pub fn label_value_length<I>(exposition: &Exposition<I>, max: usize) -> Vec<Diagnostic>
+where
+    I: AsRef<str> + Input + Offset,
+{
+    let mut diagnostics = Vec::new();
+    for metricfamily in &exposition.metricset.metricfamily {
+        for sample in metricfamily.samples() {
+            for label in sample.labels.iter().flat_map(|labels| &labels.label) {
+                let length = label.escaped_string.unescape().chars().count();
+                if length <= max {
+                    continue;
+                }
+                diagnostics.push(Diagnostic {
+                    rule: LABEL_VALUE_LENGTH,
+                    severity: Severity::Warning,
+                    message: format!(
+                        "value of label `{}` has {length} characters, more than {max}",
+                        label.label_name.as_ref(),
+                    ),
+                    span: Some(label.escaped_string.range.clone()),
+                    family: Some(metricfamily.family_name(sample).to_owned()),
+                    series: Some(series(sample)),
+                });
+            }
+        }
+    }
+    diagnostics
+}
+
+// Flags samples whose `job` or `instance` differs from the first sample's, including a missing
+// one. A scrape exposes a single target, so this catches targets that set them inconsistently;
+// a federated exposition is expected to be flagged.
+pub fn job_instance<I>(exposition: &Exposition<I>) -> Vec<Diagnostic>
+where
+    I: AsRef<str> + Input + Offset,
+{
+    let mut first = None;
+    let mut diagnostics = Vec::new();
+    for metricfamily in &exposition.metricset.metricfamily {
+        for sample in metricfamily.samples() {
+            let target = Target::of(sample);
+            let first = first.get_or_insert_with(|| target.clone());
+            if target == *first {
+                continue;
+            }
+            let describe = |target: &Target| {
+                let value = |value: &Option<String>| {
+                    value
+                        .as_ref()
+                        .map_or("none".to_owned(), |value| format!("`{value}`"))
+                };
+                format!(
+                    "job {} and instance {}",
+                    value(&target.job),
+                    value(&target.instance),
+                )
+            };
+            diagnostics.push(Diagnostic {
+                rule: JOB_INSTANCE,
+                severity: Severity::Warning,
+                message: format!(
+                    "sample has {} but the first sample has {}",
+                    describe(&target),
+                    describe(first),
+                ),
+                span: Some(sample.range.clone()),
+                family: Some(metricfamily.family_name(sample).to_owned()),
+                series: Some(series(sample)),
+            });
+        }
+    }
+    diagnostics
+}
+
+// Flags the first line of every block of a family that already had lines before another
+// family's, e.g. samples of `a`, then of `b`, then of `a` again. The grammar accepts these as
+// separate families; `lenient::Lenient::merge_interleaved_families` merges them.
+pub fn interleaved_families<I>(exposition: &Exposition<I>) -> Vec<Diagnostic>
+where
+    I: AsRef<str> + Input + Offset,
+{
+    let mut seen = BTreeSet::new();
+    let mut current = None;
+    let mut diagnostics = Vec::new();
+    for line in family_lines(&exposition.metricset) {
+        if current == Some(line.name) {
+            continue;
+        }
+        current = Some(line.name);
+        if !seen.insert(line.name) {
+            diagnostics.push(Diagnostic {
+                rule: INTERLEAVED_FAMILIES,
+                severity: Severity::Error,
+                message: format!(
+                    "family `{}` continues after lines of other families",
+                    line.name,
+                ),
+                span: Some(line.range.clone()),
+                family: Some(line.name.to_owned()),
+                series: line.sample.map(series),
+            });
+        }
+    }
+    diagnostics
+}
+
+// A descriptor or sample line with the name of the family it belongs to.
+pub(crate) struct FamilyLine<'a, I> {
+    pub name: &'a str,
+    pub range: &'a Range<usize>,
+    pub raw: &'a I,
+    // if the line is a sample
+    pub sample: Option<&'a Sample<I>>,
+}
+
+// Every line in order. A sample the grammar grouped with the family before it, but whose name
+// does not belong to that family, belongs to the earlier described family it is a sample of, if
+// any, or else to the family named after it.
+pub(crate) fn family_lines<I>(metricset: &Metricset<I>) -> Vec<FamilyLine<'_, I>>
+where
+    I: AsRef<str>,
+{
+    let mut types = BTreeMap::new();
+    let mut lines = Vec::new();
+    for metricfamily in &metricset.metricfamily {
+        for metric_descriptor in &metricfamily.metric_descriptor {
+            let name = metric_descriptor.metricname().as_ref();
+            types.insert(name, metricfamily.metric_type());
+            lines.push(FamilyLine {
+                name,
+                range: &metric_descriptor.range,
+                raw: &metric_descriptor.raw,
+                sample: None,
+            });
+        }
+        for sample in metricfamily.samples() {
+            let metricname = sample.metricname.as_ref();
+            let mut name = metricfamily.family_name(sample);
+            let described = metricfamily
+                .metric_descriptor
+                .first()
+                .is_some_and(|metric_descriptor| metric_descriptor.metricname().as_ref() == name);
+            if name == metricname && !described {
+                name = types
+                    .iter()
+                    .find(|(family, metric_type)| {
+                        metricname
+                            .strip_prefix(**family)
+                            .is_some_and(|suffix| metric_type.sample_suffixes().contains(&suffix))
+                    })
+                    .map_or(metricname, |(family, _)| *family);
+            }
+            lines.push(FamilyLine {
+                name,
+                range: &sample.range,
+                raw: &sample.raw,
+                sample: Some(sample),
+            });
+        }
+    }
+    lines
+}
+
+// Warns about an exposition parsed without `# EOF`, see
+// `options::ParseOptions::missing_eof`. The data may be truncated; an exposition whose parse
+// `options::ParseOptions::exposition_until` stopped is flagged as well.
+pub fn missing_eof<I>(exposition: &Exposition<I>) -> Vec<Diagnostic>
+where
+    I: AsRef<str>,
+{
+    if !exposition.eof.as_ref().is_empty() {
+        return Vec::new();
+    }
+    vec![Diagnostic {
+        rule: MISSING_EOF,
+        severity: Severity::Warning,
+        message: "the exposition ends without `# EOF`".to_owned(),
+        span: Some(exposition.eof_span()),
+        family: None,
+        series: None,
+    }]
+}
+
+// Every family once, as the span of its first metricname and its type.
+fn families<I>(exposition: &Exposition<I>) -> Vec<(&I, MetricType)>
+where
+    I: AsRef<str>,
+{
+    let mut seen = BTreeSet::new();
+    let mut families = Vec::new();
+    for metricfamily in &exposition.metricset.metricfamily {
+        if let Some(metric_descriptor) = metricfamily.metric_descriptor.first() {
+            let metricname = metric_descriptor.metricname();
+            if seen.insert(metricname.as_ref()) {
+                families.push((metricname, metricfamily.metric_type()));
+            }
+        }
+        for sample in metricfamily.samples() {
+            let name = metricfamily.family_name(sample);
+            if name == sample.metricname.as_ref() && seen.insert(name) {
+                families.push((&sample.metricname, MetricType::Unknown));
+            }
+        }
+    }
+    families
+}
+
+pub(crate) fn series<I>(sample: &Sample<I>) -> String
+where
+    I: AsRef<str>,
+{
+    let mut series = sample.metricname.as_ref().to_owned();
+    if let Some(labels) = &sample.labels {
+        series.push_str(labels.raw.as_ref());
+    }
+    series
+}
+
+fn span<I>(origin: &I, raw: &I) -> Range<usize>
+where
+    I: Input + Offset,
+{
+    let start = origin.offset(raw);
+    start..start + raw.input_len()
+}
+
+#[cfg(test)]
+mod tests;
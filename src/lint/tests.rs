@@ -0,0 +1,147 @@
+use super::LabelPolicy;
+use crate::diagnostic::Diagnostic;
+use nom::error::Error;
+use nom::{Finish, Parser};
+
+fn check<'a, F>(f: F, input: &'a str) -> Vec<(std::ops::Range<usize>, String)>
+where
+    F: FnOnce(&crate::Exposition<&'a str>) -> Vec<Diagnostic>,
+{
+    let (_, exposition) = crate::exposition::<_, Error<_>>
+        .parse(input)
+        .finish()
+        .unwrap();
+    f(&exposition)
+        .into_iter()
+        .map(|diagnostic| {
+            let span = diagnostic.span.unwrap();
+            (span.clone(), input[span].to_owned())
+        })
+        .collect()
+}
+
+#[test]
+fn test_label_policy() {
+    let input = r#"# TYPE a counter
+a_total{job="x",method="GET",user="u"} 1
+b{pod="p",user="u"} 1
+# EOF
+"#;
+    let policy = LabelPolicy {
+        allow: [(
+            "a".to_owned(),
+            ["job", "method"].into_iter().map(str::to_owned).collect(),
+        )]
+        .into(),
+        deny: ["pod".to_owned()].into(),
+    };
+    assert_eq!(
+        check(|exposition| super::label_policy(exposition, &policy), input),
+        [
+            (46..54, r#"user="u""#.to_owned()),
+            (60..67, r#"pod="p""#.to_owned()),
+        ],
+    );
+}
+
+#[test]
+fn test_reserved_suffix() {
+    let input = r#"# TYPE a_count gauge
+a_count 1
+# TYPE b_total counter
+b_total_total 1
+# TYPE c histogram
+c_bucket{le="+Inf"} 1
+c_count 1
+d_bucket 1
+# TYPE e_info info
+e_info_info 1
+# EOF
+"#;
+    assert_eq!(
+        check(super::reserved_suffix, input),
+        [
+            (7..14, "a_count".to_owned()),
+            (38..45, "b_total".to_owned()),
+            (121..129, "d_bucket".to_owned()),
+            (139..145, "e_info".to_owned()),
+        ],
+    );
+}
+
+#[test]
+fn test_help_unit_consistency() {
+    let input = r#"# HELP foo_latency_seconds Request latency.
+# UNIT foo_latency_seconds seconds
+foo_latency_seconds 1
+# HELP foo_latency_milliseconds Request latency in seconds.
+# UNIT foo_latency_milliseconds milliseconds
+foo_latency_milliseconds 1
+# HELP bar_latency_seconds Request latency in seconds, not milliseconds.
+bar_latency_seconds 1
+# HELP bar_size_bytes Size in kilobytes per second.
+bar_size_bytes 1
+# HELP bar_size Size in kilobytes.
+bar_size 1
+# EOF
+"#;
+    assert_eq!(
+        check(super::help_unit_consistency, input),
+        [
+            (193..205, "milliseconds".to_owned()),
+            (133..160, "Request latency in seconds.".to_owned()),
+            (350..379, "Size in kilobytes per second.".to_owned()),
+        ],
+    );
+}
+
+#[test]
+fn test_label_value_length() {
+    let input = r#"a{b="12345",c="1234\n"} 1
+# EOF
+"#;
+    assert_eq!(
+        check(|exposition| super::label_value_length(exposition, 5), input),
+        [],
+    );
+    assert_eq!(
+        check(|exposition| super::label_value_length(exposition, 4), input),
+        [(5..10, "12345".to_owned()), (15..21, r"1234\n".to_owned()),],
+    );
+}
+
+#[test]
+fn test_job_instance() {
+    let input = r#"a{job="x",instance="i"} 1
+b{instance="i",job="x"} 2
+c{job="x"} 3
+# EOF
+"#;
+    assert_eq!(
+        check(super::job_instance, input),
+        [(52..65, "c{job=\"x\"} 3\n".to_owned())],
+    );
+}
+
+#[test]
+fn test_interleaved_families() {
+    let input = r#"# TYPE a counter
+a_total 1
+# TYPE b gauge
+b 2
+a_total 3
+b 4
+c 5
+# TYPE a counter
+a_total 6
+# EOF
+"#;
+    assert_eq!(
+        check(super::interleaved_families, input),
+        [
+            (46..56, "a_total 3\n".to_owned()),
+            (56..60, "b 4\n".to_owned()),
+            (64..81, "# TYPE a counter\n".to_owned()),
+        ],
+    );
+}
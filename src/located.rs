@@ -0,0 +1,123 @@
+use nom::{Compare, CompareResult, Input, Needed, Offset};
+use std::fmt::{self, Display};
+use std::str::{CharIndices, Chars};
+
+// A `&str` input that remembers where it lies in the whole source, so every span captured by the
+// parsers can report its line and column. Parse `Span::new(source)` like a `&str`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Span<'a> {
+    source: &'a str,
+    offset: usize,
+    len: usize,
+}
+
+impl<'a> Span<'a> {
+    pub fn new(source: &'a str) -> Self {
+        Self {
+            source,
+            offset: 0,
+            len: source.len(),
+        }
+    }
+
+    pub fn fragment(&self) -> &'a str {
+        &self.source[self.offset..self.offset + self.len]
+    }
+
+    // The byte offset of the span in the source.
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    // The 1-based line of the start of the span.
+    pub fn line(&self) -> usize {
+        self.source[..self.offset].matches('\n').count() + 1
+    }
+
+    // The 1-based column of the start of the span, in characters.
+    pub fn column(&self) -> usize {
+        let prefix = &self.source[..self.offset];
+        let start = prefix.rfind('\n').map_or(0, |lf| lf + 1);
+        prefix[start..].chars().count() + 1
+    }
+
+    fn slice(&self, start: usize, end: usize) -> Self {
+        Self {
+            source: self.source,
+            offset: self.offset + start,
+            len: end - start,
+        }
+    }
+}
+
+impl AsRef<str> for Span<'_> {
+    fn as_ref(&self) -> &str {
+        self.fragment()
+    }
+}
+
+impl Display for Span<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.fragment())
+    }
+}
+
+impl<'a> Input for Span<'a> {
+    type Item = char;
+    type Iter = Chars<'a>;
+    type IterIndices = CharIndices<'a>;
+
+    fn input_len(&self) -> usize {
+        self.len
+    }
+
+    fn take(&self, index: usize) -> Self {
+        self.slice(0, index)
+    }
+
+    fn take_from(&self, index: usize) -> Self {
+        self.slice(index, self.len)
+    }
+
+    fn take_split(&self, index: usize) -> (Self, Self) {
+        (self.slice(index, self.len), self.slice(0, index))
+    }
+
+    fn position<P>(&self, predicate: P) -> Option<usize>
+    where
+        P: Fn(Self::Item) -> bool,
+    {
+        self.fragment().position(predicate)
+    }
+
+    fn iter_elements(&self) -> Self::Iter {
+        self.fragment().chars()
+    }
+
+    fn iter_indices(&self) -> Self::IterIndices {
+        self.fragment().char_indices()
+    }
+
+    fn slice_index(&self, count: usize) -> Result<usize, Needed> {
+        self.fragment().slice_index(count)
+    }
+}
+
+impl<'b> Compare<&'b str> for Span<'_> {
+    fn compare(&self, t: &'b str) -> CompareResult {
+        self.fragment().compare(t)
+    }
+
+    fn compare_no_case(&self, t: &'b str) -> CompareResult {
+        self.fragment().compare_no_case(t)
+    }
+}
+
+impl Offset for Span<'_> {
+    fn offset(&self, second: &Self) -> usize {
+        second.offset - self.offset
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,36 @@
+use super::Span;
+use nom::error::Error;
+use nom::{Finish, Parser};
+
+#[test]
+fn test_span() {
+    let input = "# TYPE a counter\na_total{b=\"€\",c=\"d\"} 1\n# EOF\n";
+    let (_, exposition) = crate::exposition::<_, Error<_>>
+        .parse(Span::new(input))
+        .finish()
+        .unwrap();
+    let (_, expected) = crate::exposition::<_, Error<_>>
+        .parse(input)
+        .finish()
+        .unwrap();
+    assert_eq!(
+        exposition.clone().map_input(|span| span.fragment()),
+        expected
+    );
+
+    let metricfamily = &exposition.metricset.value.metricfamily[0];
+    assert_eq!((metricfamily.raw.line(), metricfamily.raw.column()), (1, 1));
+    let sample = &metricfamily.metric[0].sample[0];
+    assert_eq!((sample.number.line(), sample.number.column()), (2, 22));
+    assert_eq!(sample.number.offset(), input.find(" 1\n").unwrap() + 1);
+}
+
+#[rstest::rstest]
+#[case("a", 0, (1, 1))]
+#[case("a\nbc", 3, (2, 2))]
+#[case("a\n€b", 5, (2, 2))]
+#[case("a\n\n", 3, (3, 1))]
+fn test_line_column(#[case] input: &str, #[case] offset: usize, #[case] expected: (usize, usize)) {
+    let span = Span::new(input).slice(offset, input.len());
+    assert_eq!((span.line(), span.column()), expected);
+}
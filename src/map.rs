@@ -0,0 +1,538 @@
+use crate::{
+    Comment, EscapedString, EscapedStringFragment, Exemplar, Exposition, Extension,
+    HelpEscapedString, HelpEscapedStringFragment, Label, Labels, Merged, Metric, MetricDescriptor,
+    MetricType, Metricfamily, Metricset, Sample, Shift, Spanned,
+};
+
+// Rebuilds the tree with every input slice converted by `f`, e.g. `&str` -> `Arc<str>`.
+
+fn map_spanned<I, J, T, U, F>(
+    spanned: Spanned<I, T>,
+    f: &mut F,
+    map_value: impl FnOnce(T, &mut F) -> U,
+) -> Spanned<J, U>
+where
+    F: FnMut(I) -> J,
+{
+    Spanned {
+        range: spanned.range,
+        raw: f(spanned.raw),
+        value: map_value(spanned.value, f),
+    }
+}
+
+impl<I> Exposition<I> {
+    pub fn map_input<J, F>(self, mut f: F) -> Exposition<J>
+    where
+        F: FnMut(I) -> J,
+    {
+        Exposition {
+            metricset: map_spanned(self.metricset, &mut f, |value, f| value.map_input(f)),
+            eof: f(self.eof),
+        }
+    }
+}
+
+impl<I> Metricset<I> {
+    pub fn map_input<J, F>(self, mut f: F) -> Metricset<J>
+    where
+        F: FnMut(I) -> J,
+    {
+        Metricset {
+            metricfamily: self
+                .metricfamily
+                .into_iter()
+                .map(|metricfamily| {
+                    map_spanned(metricfamily, &mut f, |value, f| value.map_input(f))
+                })
+                .collect(),
+            extension: self
+                .extension
+                .into_iter()
+                .map(|extension| map_spanned(extension, &mut f, |value, f| value.map_input(f)))
+                .collect(),
+            comment: self
+                .comment
+                .into_iter()
+                .map(|comment| map_spanned(comment, &mut f, |value, f| value.map_input(f)))
+                .collect(),
+            merged: self
+                .merged
+                .into_iter()
+                .map(|merged| map_spanned(merged, &mut f, |value, f| value.map_input(f)))
+                .collect(),
+        }
+    }
+}
+
+impl<I> Extension<I> {
+    pub fn map_input<J, F>(self, mut f: F) -> Extension<J>
+    where
+        F: FnMut(I) -> J,
+    {
+        Extension {
+            keyword: f(self.keyword),
+            body: f(self.body),
+        }
+    }
+}
+
+impl<I> Comment<I> {
+    pub fn map_input<J, F>(self, mut f: F) -> Comment<J>
+    where
+        F: FnMut(I) -> J,
+    {
+        Comment { text: f(self.text) }
+    }
+}
+
+impl<I> Merged<I> {
+    pub fn map_input<J, F>(self, mut f: F) -> Merged<J>
+    where
+        F: FnMut(I) -> J,
+    {
+        Merged {
+            metricname: f(self.metricname),
+        }
+    }
+}
+
+impl<I> Metricfamily<I> {
+    pub fn map_input<J, F>(self, mut f: F) -> Metricfamily<J>
+    where
+        F: FnMut(I) -> J,
+    {
+        Metricfamily {
+            metric_descriptor: self
+                .metric_descriptor
+                .into_iter()
+                .map(|metric_descriptor| {
+                    map_spanned(metric_descriptor, &mut f, |value, f| value.map_input(f))
+                })
+                .collect(),
+            metric: self
+                .metric
+                .into_iter()
+                .map(|metric| map_spanned(metric, &mut f, |value, f| value.map_input(f)))
+                .collect(),
+        }
+    }
+}
+
+impl<I> MetricDescriptor<I> {
+    pub fn map_input<J, F>(self, mut f: F) -> MetricDescriptor<J>
+    where
+        F: FnMut(I) -> J,
+    {
+        match self {
+            Self::Type {
+                metricname,
+                metric_type,
+            } => MetricDescriptor::Type {
+                metricname: f(metricname),
+                metric_type: map_spanned(metric_type, &mut f, |value, _| value),
+            },
+            Self::Help {
+                metricname,
+                escaped_string,
+            } => MetricDescriptor::Help {
+                metricname: f(metricname),
+                escaped_string: map_spanned(escaped_string, &mut f, |value, f| value.map_input(f)),
+            },
+            Self::Unit {
+                metricname,
+                metricname_char,
+            } => MetricDescriptor::Unit {
+                metricname: f(metricname),
+                metricname_char: f(metricname_char),
+            },
+        }
+    }
+}
+
+impl<I> Metric<I> {
+    pub fn map_input<J, F>(self, mut f: F) -> Metric<J>
+    where
+        F: FnMut(I) -> J,
+    {
+        Metric {
+            sample: self
+                .sample
+                .into_iter()
+                .map(|sample| map_spanned(sample, &mut f, |value, f| value.map_input(f)))
+                .collect(),
+        }
+    }
+}
+
+impl<I> Sample<I> {
+    pub fn map_input<J, F>(self, mut f: F) -> Sample<J>
+    where
+        F: FnMut(I) -> J,
+    {
+        Sample {
+            metricname: f(self.metricname),
+            labels: self
+                .labels
+                .map(|labels| map_spanned(labels, &mut f, |value, f| value.map_input(f))),
+            number: f(self.number),
+            timestamp: self.timestamp.map(&mut f),
+            exemplar: self
+                .exemplar
+                .map(|exemplar| map_spanned(exemplar, &mut f, |value, f| value.map_input(f))),
+            normalized_number: self.normalized_number,
+        }
+    }
+}
+
+impl<I> Exemplar<I> {
+    pub fn map_input<J, F>(self, mut f: F) -> Exemplar<J>
+    where
+        F: FnMut(I) -> J,
+    {
+        Exemplar {
+            labels: map_spanned(self.labels, &mut f, |value, f| value.map_input(f)),
+            number: f(self.number),
+            timestamp: self.timestamp.map(&mut f),
+            normalized_number: self.normalized_number,
+        }
+    }
+}
+
+impl<I> Labels<I> {
+    pub fn map_input<J, F>(self, mut f: F) -> Labels<J>
+    where
+        F: FnMut(I) -> J,
+    {
+        Labels {
+            label: self
+                .label
+                .into_iter()
+                .map(|label| map_spanned(label, &mut f, |value, f| value.map_input(f)))
+                .collect(),
+        }
+    }
+}
+
+impl<I> Label<I> {
+    pub fn map_input<J, F>(self, mut f: F) -> Label<J>
+    where
+        F: FnMut(I) -> J,
+    {
+        Label {
+            label_name: f(self.label_name),
+            escaped_string: map_spanned(self.escaped_string, &mut f, |value, f| value.map_input(f)),
+        }
+    }
+}
+
+impl<I> EscapedString<I> {
+    pub fn map_input<J, F>(self, mut f: F) -> EscapedString<J>
+    where
+        F: FnMut(I) -> J,
+    {
+        EscapedString(
+            self.0
+                .into_iter()
+                .map(|fragment| map_spanned(fragment, &mut f, |value, f| value.map_input(f)))
+                .collect(),
+        )
+    }
+}
+
+impl<I> HelpEscapedString<I> {
+    pub fn map_input<J, F>(self, mut f: F) -> HelpEscapedString<J>
+    where
+        F: FnMut(I) -> J,
+    {
+        HelpEscapedString(
+            self.0
+                .into_iter()
+                .map(|fragment| map_spanned(fragment, &mut f, |value, f| value.map_input(f)))
+                .collect(),
+        )
+    }
+}
+
+impl<I> HelpEscapedStringFragment<I> {
+    pub fn map_input<J, F>(self, mut f: F) -> HelpEscapedStringFragment<J>
+    where
+        F: FnMut(I) -> J,
+    {
+        match self {
+            Self::Normal(normal) => HelpEscapedStringFragment::Normal(f(normal)),
+            Self::Lf => HelpEscapedStringFragment::Lf,
+            Self::Bs => HelpEscapedStringFragment::Bs,
+        }
+    }
+}
+
+impl<I> EscapedStringFragment<I> {
+    pub fn map_input<J, F>(self, mut f: F) -> EscapedStringFragment<J>
+    where
+        F: FnMut(I) -> J,
+    {
+        match self {
+            Self::Normal(normal) => EscapedStringFragment::Normal(f(normal)),
+            Self::Lf => EscapedStringFragment::Lf,
+            Self::Dquote => EscapedStringFragment::Dquote,
+            Self::Bs => EscapedStringFragment::Bs,
+        }
+    }
+}
+
+// Detaches the tree from the input, e.g. to keep it after the scrape buffer is dropped.
+
+impl<I> Exposition<I>
+where
+    I: AsRef<str>,
+{
+    pub fn into_owned(self) -> Exposition<String> {
+        self.map_input(|s| s.as_ref().to_owned())
+    }
+}
+
+impl<I> Metricset<I>
+where
+    I: AsRef<str>,
+{
+    pub fn into_owned(self) -> Metricset<String> {
+        self.map_input(|s| s.as_ref().to_owned())
+    }
+}
+
+impl<I> Extension<I>
+where
+    I: AsRef<str>,
+{
+    pub fn into_owned(self) -> Extension<String> {
+        self.map_input(|s| s.as_ref().to_owned())
+    }
+}
+
+impl<I> Comment<I>
+where
+    I: AsRef<str>,
+{
+    pub fn into_owned(self) -> Comment<String> {
+        self.map_input(|s| s.as_ref().to_owned())
+    }
+}
+
+impl<I> Metricfamily<I>
+where
+    I: AsRef<str>,
+{
+    pub fn into_owned(self) -> Metricfamily<String> {
+        self.map_input(|s| s.as_ref().to_owned())
+    }
+}
+
+impl<I> MetricDescriptor<I>
+where
+    I: AsRef<str>,
+{
+    pub fn into_owned(self) -> MetricDescriptor<String> {
+        self.map_input(|s| s.as_ref().to_owned())
+    }
+}
+
+impl<I> Metric<I>
+where
+    I: AsRef<str>,
+{
+    pub fn into_owned(self) -> Metric<String> {
+        self.map_input(|s| s.as_ref().to_owned())
+    }
+}
+
+impl<I> Sample<I>
+where
+    I: AsRef<str>,
+{
+    pub fn into_owned(self) -> Sample<String> {
+        self.map_input(|s| s.as_ref().to_owned())
+    }
+}
+
+impl<I> Exemplar<I>
+where
+    I: AsRef<str>,
+{
+    pub fn into_owned(self) -> Exemplar<String> {
+        self.map_input(|s| s.as_ref().to_owned())
+    }
+}
+
+impl<I> Labels<I>
+where
+    I: AsRef<str>,
+{
+    pub fn into_owned(self) -> Labels<String> {
+        self.map_input(|s| s.as_ref().to_owned())
+    }
+}
+
+impl<I> Label<I>
+where
+    I: AsRef<str>,
+{
+    pub fn into_owned(self) -> Label<String> {
+        self.map_input(|s| s.as_ref().to_owned())
+    }
+}
+
+impl<I> HelpEscapedString<I>
+where
+    I: AsRef<str>,
+{
+    pub fn into_owned(self) -> HelpEscapedString<String> {
+        self.map_input(|s| s.as_ref().to_owned())
+    }
+}
+
+impl<I> EscapedString<I>
+where
+    I: AsRef<str>,
+{
+    pub fn into_owned(self) -> EscapedString<String> {
+        self.map_input(|s| s.as_ref().to_owned())
+    }
+}
+
+// Moves every range by `by`, as `spanned` does when a node is parsed from a suffix of the input.
+
+impl<I, T> Shift for Spanned<I, T>
+where
+    T: Shift,
+{
+    fn shift(&mut self, by: usize) {
+        self.range.start += by;
+        self.range.end += by;
+        self.value.shift(by);
+    }
+}
+
+impl<T> Shift for Vec<T>
+where
+    T: Shift,
+{
+    fn shift(&mut self, by: usize) {
+        self.iter_mut().for_each(|value| value.shift(by));
+    }
+}
+
+impl<T> Shift for Option<T>
+where
+    T: Shift,
+{
+    fn shift(&mut self, by: usize) {
+        if let Some(value) = self {
+            value.shift(by);
+        }
+    }
+}
+
+impl<T, U> Shift for (T, U)
+where
+    T: Shift,
+    U: Shift,
+{
+    fn shift(&mut self, by: usize) {
+        self.0.shift(by);
+        self.1.shift(by);
+    }
+}
+
+impl<I> Shift for Metricset<I> {
+    fn shift(&mut self, by: usize) {
+        self.metricfamily.shift(by);
+        self.extension.shift(by);
+        self.comment.shift(by);
+        self.merged.shift(by);
+    }
+}
+
+impl<I> Shift for Extension<I> {
+    fn shift(&mut self, _: usize) {}
+}
+
+impl<I> Shift for Comment<I> {
+    fn shift(&mut self, _: usize) {}
+}
+
+impl<I> Shift for Merged<I> {
+    fn shift(&mut self, _: usize) {}
+}
+
+impl<I> Shift for Metricfamily<I> {
+    fn shift(&mut self, by: usize) {
+        self.metric_descriptor.shift(by);
+        self.metric.shift(by);
+    }
+}
+
+impl<I> Shift for MetricDescriptor<I> {
+    fn shift(&mut self, by: usize) {
+        match self {
+            Self::Type { metric_type, .. } => metric_type.shift(by),
+            Self::Help { escaped_string, .. } => escaped_string.shift(by),
+            Self::Unit { .. } => {}
+        }
+    }
+}
+
+impl Shift for MetricType {
+    fn shift(&mut self, _: usize) {}
+}
+
+impl<I> Shift for Metric<I> {
+    fn shift(&mut self, by: usize) {
+        self.sample.shift(by);
+    }
+}
+
+impl<I> Shift for Sample<I> {
+    fn shift(&mut self, by: usize) {
+        self.labels.shift(by);
+        self.exemplar.shift(by);
+    }
+}
+
+impl<I> Shift for Exemplar<I> {
+    fn shift(&mut self, by: usize) {
+        self.labels.shift(by);
+    }
+}
+
+impl<I> Shift for Labels<I> {
+    fn shift(&mut self, by: usize) {
+        self.label.shift(by);
+    }
+}
+
+impl<I> Shift for Label<I> {
+    fn shift(&mut self, by: usize) {
+        self.escaped_string.shift(by);
+    }
+}
+
+impl<I> Shift for EscapedString<I> {
+    fn shift(&mut self, by: usize) {
+        self.0.shift(by);
+    }
+}
+
+impl<I> Shift for HelpEscapedString<I> {
+    fn shift(&mut self, by: usize) {
+        self.0.shift(by);
+    }
+}
+
+impl<I> Shift for EscapedStringFragment<I> {
+    fn shift(&mut self, _: usize) {}
+}
+
+impl<I> Shift for HelpEscapedStringFragment<I> {
+    fn shift(&mut self, _: usize) {}
+}
@@ -0,0 +1,165 @@
+use crate::Sample;
+use crate::glob::Glob;
+#[cfg(feature = "matcher")]
+use regex::Regex;
+use std::borrow::Cow;
+#[cfg(feature = "matcher")]
+use std::collections::HashMap;
+use std::convert::Infallible;
+#[cfg(feature = "matcher")]
+use std::sync::{Arc, Mutex, OnceLock};
+
+// Regexes need the `matcher` feature, `MatcherSet::compile_glob` does not.
+
+// https://prometheus.io/docs/prometheus/latest/querying/basics/#time-series-selectors
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Op {
+    Eq,
+    Ne,
+    Re,
+    Nre,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Matcher {
+    // `__name__` refers to the metricname
+    pub label: String,
+    pub op: Op,
+    pub value: String,
+}
+
+// Compiled regexes by pattern. Clones share the same cache.
+#[cfg(feature = "matcher")]
+#[derive(Clone, Debug, Default)]
+pub struct RegexCache(Arc<Mutex<HashMap<String, Regex>>>);
+
+#[cfg(feature = "matcher")]
+impl RegexCache {
+    // Patterns are fully anchored, as in PromQL.
+    pub fn get(&self, pattern: &str) -> Result<Regex, regex::Error> {
+        let mut cache = self.0.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(regex) = cache.get(pattern) {
+            return Ok(regex.clone());
+        }
+        let regex = Regex::new(&format!("^(?:{pattern})$"))?;
+        cache.insert(pattern.to_owned(), regex.clone());
+        Ok(regex)
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.lock().unwrap_or_else(|e| e.into_inner()).len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+#[derive(Clone, Debug)]
+enum Pattern {
+    #[cfg(feature = "matcher")]
+    Regex(Regex),
+    Glob(Glob),
+}
+
+impl Pattern {
+    fn is_match(&self, value: &str) -> bool {
+        match self {
+            #[cfg(feature = "matcher")]
+            Self::Regex(regex) => regex.is_match(value),
+            Self::Glob(glob) => glob.is_match(value),
+        }
+    }
+}
+
+// A matcher's op with the pattern of `=~` and `!~`.
+#[derive(Clone, Debug)]
+enum Test {
+    Eq,
+    Ne,
+    Re(Pattern),
+    Nre(Pattern),
+}
+
+impl Test {
+    fn new<E>(op: Op, pattern: impl FnOnce() -> Result<Pattern, E>) -> Result<Self, E> {
+        Ok(match op {
+            Op::Eq => Self::Eq,
+            Op::Ne => Self::Ne,
+            Op::Re => Self::Re(pattern()?),
+            Op::Nre => Self::Nre(pattern()?),
+        })
+    }
+}
+
+// Matchers compiled once and shared across scrapes; all of them must match.
+#[derive(Clone, Debug)]
+pub struct MatcherSet(Vec<(Matcher, Test)>);
+
+impl MatcherSet {
+    // Uses a process-wide cache, so identical patterns are compiled once.
+    #[cfg(feature = "matcher")]
+    pub fn compile(matchers: &[Matcher]) -> Result<Self, regex::Error> {
+        static CACHE: OnceLock<RegexCache> = OnceLock::new();
+        Self::compile_with(matchers, CACHE.get_or_init(RegexCache::default))
+    }
+
+    #[cfg(feature = "matcher")]
+    pub fn compile_with(matchers: &[Matcher], cache: &RegexCache) -> Result<Self, regex::Error> {
+        matchers
+            .iter()
+            .map(|matcher| {
+                let test = Test::new(matcher.op, || cache.get(&matcher.value).map(Pattern::Regex))?;
+                Ok((matcher.clone(), test))
+            })
+            .collect::<Result<_, _>>()
+            .map(Self)
+    }
+
+    // Matches the values of `=~` and `!~` as `Glob`s instead of regexes, e.g. where patterns come
+    // from untrusted input: `a.*` is then a literal `a.` followed by anything. Every matcher
+    // takes time linear in its pattern and the value.
+    pub fn compile_glob(matchers: &[Matcher]) -> Self {
+        Self(
+            matchers
+                .iter()
+                .map(|matcher| {
+                    let test = Test::new(matcher.op, || {
+                        Ok::<_, Infallible>(Pattern::Glob(Glob::new(&matcher.value)))
+                    });
+                    let Ok(test) = test;
+                    (matcher.clone(), test)
+                })
+                .collect(),
+        )
+    }
+
+    pub fn matches<I>(&self, sample: &Sample<I>) -> bool
+    where
+        I: AsRef<str>,
+    {
+        self.0.iter().all(|(matcher, test)| {
+            // a missing label matches like an empty value
+            let value = if matcher.label == "__name__" {
+                Cow::Borrowed(sample.metricname.as_ref())
+            } else {
+                sample
+                    .labels
+                    .iter()
+                    .flat_map(|labels| &labels.label)
+                    .find(|label| label.label_name.as_ref() == matcher.label)
+                    .map(|label| label.escaped_string.unescape())
+                    .unwrap_or_default()
+            };
+            match test {
+                Test::Eq => value == matcher.value,
+                Test::Ne => value != matcher.value,
+                Test::Re(pattern) => pattern.is_match(&value),
+                Test::Nre(pattern) => !pattern.is_match(&value),
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests;
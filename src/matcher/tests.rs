@@ -0,0 +1,85 @@
+#[cfg(feature = "matcher")]
+use super::RegexCache;
+use super::{Matcher, MatcherSet, Op};
+use nom::error::Error;
+use nom::{Finish, Parser};
+
+fn matcher(label: &str, op: Op, value: &str) -> Matcher {
+    Matcher {
+        label: label.to_owned(),
+        op,
+        value: value.to_owned(),
+    }
+}
+
+#[cfg(feature = "matcher")]
+#[rstest::rstest]
+#[case(vec![matcher("__name__", Op::Eq, "a")], &[1, 2, 3])]
+#[case(vec![matcher("method", Op::Re, "GET|POST")], &[1, 2])]
+#[case(vec![matcher("method", Op::Nre, "GET|POST")], &[3, 4])]
+#[case(vec![matcher("method", Op::Ne, "GET")], &[2, 3, 4])]
+#[case(vec![matcher("method", Op::Eq, "")], &[3, 4])]
+#[case(vec![matcher("path", Op::Re, "/a.*")], &[1])]
+#[case(vec![matcher("path", Op::Re, "a")], &[])]
+#[case(
+    vec![matcher("__name__", Op::Eq, "a"), matcher("method", Op::Re, "P.*")],
+    &[2],
+)]
+fn test_matches(#[case] matchers: Vec<Matcher>, #[case] expected: &[u32]) {
+    let input = r#"a{method="GET",path="/a\"b"} 1
+a{method="POST"} 2
+a 3
+b 4
+# EOF
+"#;
+    let (_, exposition) = crate::exposition::<_, Error<_>>
+        .parse(input)
+        .finish()
+        .unwrap();
+    let matcher_set = MatcherSet::compile(&matchers).unwrap();
+    let matched = exposition.metricset.value.metricfamily[0]
+        .samples()
+        .filter(|sample| matcher_set.matches(sample))
+        .map(|sample| sample.number.parse().unwrap())
+        .collect::<Vec<u32>>();
+    assert_eq!(matched, expected);
+}
+
+#[cfg(feature = "matcher")]
+#[test]
+fn test_regex_cache() {
+    let cache = RegexCache::default();
+    let matchers = [
+        matcher("a", Op::Re, "x.*"),
+        matcher("b", Op::Nre, "x.*"),
+        matcher("c", Op::Re, "y"),
+    ];
+    MatcherSet::compile_with(&matchers, &cache).unwrap();
+    MatcherSet::compile_with(&matchers, &cache.clone()).unwrap();
+    assert_eq!(cache.len(), 2);
+}
+
+#[rstest::rstest]
+#[case(vec![matcher("method", Op::Re, "P*")], &[2])]
+#[case(vec![matcher("method", Op::Nre, "*T")], &[3, 4])]
+#[case(vec![matcher("path", Op::Re, "/a.*")], &[])]
+#[case(vec![matcher("path", Op::Re, "/a*")], &[1])]
+fn test_compile_glob(#[case] matchers: Vec<Matcher>, #[case] expected: &[u32]) {
+    let input = r#"a{method="GET",path="/a\"b"} 1
+a{method="POST"} 2
+a 3
+b 4
+# EOF
+"#;
+    let (_, exposition) = crate::exposition::<_, Error<_>>
+        .parse(input)
+        .finish()
+        .unwrap();
+    let matcher_set = MatcherSet::compile_glob(&matchers);
+    let matched = exposition.metricset.value.metricfamily[0]
+        .samples()
+        .filter(|sample| matcher_set.matches(sample))
+        .map(|sample| sample.number.parse().unwrap())
+        .collect::<Vec<u32>>();
+    assert_eq!(matched, expected);
+}
@@ -0,0 +1,203 @@
+pub use crate::CONTENT_TYPE;
+use crate::diagnostic::{Diagnostic, Severity};
+use crate::validate;
+use nom::error::Error as NomError;
+use nom::{Finish, Parser};
+use std::fmt;
+use std::sync::Arc;
+
+// Validation of what a service serves on its `/metrics` endpoint, for debug or staging builds:
+// the diagnostics of the response are reported and, if rejecting, an invalid response is
+// replaced by a 500 listing them. The adapters are behind the feature of their framework:
+//
+//     warp:   warp::path("metrics").and(warp::path::end()).map(render) wrapped by `warp`
+//     tide:   app.at("/metrics").with(middleware::tide(validator)).get(render)
+//     rocket: a route returning `Metrics`, with the validator as managed state
+
+type Report = Arc<dyn Fn(&[Diagnostic]) + Send + Sync>;
+
+#[derive(Clone)]
+pub struct Validator {
+    enabled: bool,
+    reject: bool,
+    report: Report,
+}
+
+impl fmt::Debug for Validator {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Validator")
+            .field("enabled", &self.enabled)
+            .field("reject", &self.reject)
+            .finish_non_exhaustive()
+    }
+}
+
+// Enabled in debug builds, neither reporting nor rejecting; see `Validator::report` and `stderr`.
+impl Default for Validator {
+    fn default() -> Self {
+        Self {
+            enabled: cfg!(debug_assertions),
+            reject: false,
+            report: Arc::new(|_| {}),
+        }
+    }
+}
+
+impl Validator {
+    pub fn enabled(mut self, enabled: bool) -> Self {
+        self.enabled = enabled;
+        self
+    }
+
+    pub fn reject(mut self, reject: bool) -> Self {
+        self.reject = reject;
+        self
+    }
+
+    pub fn report<F>(mut self, report: F) -> Self
+    where
+        F: Fn(&[Diagnostic]) + Send + Sync + 'static,
+    {
+        self.report = Arc::new(report);
+        self
+    }
+
+    // Validates `body` and reports its diagnostics, if any. They are returned if the body is to
+    // be rejected, i.e. if rejecting and one of them is an error.
+    pub fn check(&self, body: &[u8]) -> Result<(), Vec<Diagnostic>> {
+        if !self.enabled {
+            return Ok(());
+        }
+        let diagnostics = match std::str::from_utf8(body) {
+            Ok(text) => match crate::exposition::<_, NomError<_>>.parse(text).finish() {
+                Ok((_, exposition)) => validate::validate(&exposition),
+                Err(e) => vec![Diagnostic::from_error(&text, &e)],
+            },
+            Err(e) => {
+                let start = e.valid_up_to();
+                vec![Diagnostic {
+                    rule: crate::diagnostic::SYNTAX,
+                    severity: Severity::Error,
+                    message: "invalid exposition (not UTF-8)".to_owned(),
+                    span: Some(start..start + 1),
+                    family: None,
+                    series: None,
+                }]
+            }
+        };
+        if diagnostics.is_empty() {
+            return Ok(());
+        }
+        (self.report)(&diagnostics);
+        if self.reject
+            && diagnostics
+                .iter()
+                .any(|diagnostic| diagnostic.severity == Severity::Error)
+        {
+            Err(diagnostics)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+// A reporter printing the diagnostics to stderr like `rejection`, for `Validator::report`.
+pub fn stderr(diagnostics: &[Diagnostic]) {
+    eprint!("{}", rejection(diagnostics));
+}
+
+// The body replacing a rejected response, a diagnostic per line.
+pub fn rejection(diagnostics: &[Diagnostic]) -> String {
+    diagnostics
+        .iter()
+        .map(|diagnostic| {
+            let span = diagnostic
+                .span
+                .as_ref()
+                .map(|span| format!(" at {}..{}", span.start, span.end))
+                .unwrap_or_default();
+            format!("{}{span}: {}\n", diagnostic.rule, diagnostic.message)
+        })
+        .collect()
+}
+
+// Serves the `String` extracted by `filter` as an exposition.
+#[cfg(feature = "warp")]
+pub fn warp<F>(
+    validator: Validator,
+    filter: F,
+) -> impl ::warp::Filter<Extract = (::warp::reply::Response,), Error = F::Error> + Clone
+where
+    F: ::warp::Filter<Extract = (String,)> + Clone,
+{
+    use ::warp::Reply;
+    use ::warp::http::StatusCode;
+
+    filter.map(move |body: String| match validator.check(body.as_bytes()) {
+        Ok(()) => ::warp::reply::with_header(body, "content-type", CONTENT_TYPE).into_response(),
+        Err(diagnostics) => {
+            ::warp::reply::with_status(rejection(&diagnostics), StatusCode::INTERNAL_SERVER_ERROR)
+                .into_response()
+        }
+    })
+}
+
+// Checks the responses of the routes it is attached to.
+#[cfg(feature = "tide")]
+pub fn tide<State>(validator: Validator) -> impl ::tide::Middleware<State>
+where
+    State: Clone + Send + Sync + 'static,
+{
+    ::tide::utils::After(move |mut response: ::tide::Response| {
+        let validator = validator.clone();
+        async move {
+            let body = response.take_body().into_bytes().await?;
+            match validator.check(&body) {
+                Ok(()) => {
+                    response.set_body(body);
+                    Ok(response)
+                }
+                Err(diagnostics) => {
+                    let mut response =
+                        ::tide::Response::new(::tide::StatusCode::InternalServerError);
+                    response.set_body(rejection(&diagnostics));
+                    Ok(response)
+                }
+            }
+        }
+    })
+}
+
+// An exposition as the response of a route, checked by the `Validator` managed by the
+// application, or the default one.
+#[cfg(feature = "rocket")]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Metrics(pub String);
+
+#[cfg(feature = "rocket")]
+impl<'r> ::rocket::response::Responder<'r, 'static> for Metrics {
+    fn respond_to(self, request: &'r ::rocket::Request<'_>) -> ::rocket::response::Result<'static> {
+        use ::rocket::http::{Header, Status};
+
+        let validator = request
+            .rocket()
+            .state::<Validator>()
+            .cloned()
+            .unwrap_or_default();
+        let (status, content_type, body) = match validator.check(self.0.as_bytes()) {
+            Ok(()) => (Status::Ok, CONTENT_TYPE, self.0),
+            Err(diagnostics) => (
+                Status::InternalServerError,
+                "text/plain; charset=utf-8",
+                rejection(&diagnostics),
+            ),
+        };
+        ::rocket::Response::build_from(body.respond_to(request)?)
+            .status(status)
+            .header(Header::new("Content-Type", content_type))
+            .ok()
+    }
+}
+
+#[cfg(test)]
+mod tests;
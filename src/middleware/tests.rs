@@ -0,0 +1,143 @@
+use super::Validator;
+use crate::diagnostic::Diagnostic;
+use std::sync::{Arc, Mutex};
+
+fn validator(reject: bool) -> (Validator, Arc<Mutex<Vec<Diagnostic>>>) {
+    let reported = Arc::new(Mutex::new(Vec::new()));
+    let validator = Validator::default().enabled(true).reject(reject).report({
+        let reported = reported.clone();
+        move |diagnostics| reported.lock().unwrap().extend_from_slice(diagnostics)
+    });
+    (validator, reported)
+}
+
+#[rstest::rstest]
+#[case(b"# TYPE a counter\na_total 1\n# EOF\n", false, Ok(()), &[])]
+#[case(b"# TYPE a counter\na_total 1\n", false, Ok(()), &["syntax"])]
+#[case(b"# TYPE a counter\na_total 1\n", true, Err(vec!["syntax"]), &["syntax"])]
+#[case(b"# TYPE a counter\na_total -1\n# EOF\n", true, Err(vec!["counter-value"]), &["counter-value"])]
+#[case(b"a \xff\n# EOF\n", true, Err(vec!["syntax"]), &["syntax"])]
+fn test_check(
+    #[case] body: &[u8],
+    #[case] reject: bool,
+    #[case] expected: Result<(), Vec<&str>>,
+    #[case] reports: &[&str],
+) {
+    let (validator, reported) = validator(reject);
+    assert_eq!(
+        validator.check(body).map_err(|diagnostics| diagnostics
+            .iter()
+            .map(|diagnostic| diagnostic.rule)
+            .collect::<Vec<_>>()),
+        expected
+    );
+    assert_eq!(
+        reported
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|diagnostic| diagnostic.rule)
+            .collect::<Vec<_>>(),
+        reports
+    );
+}
+
+#[test]
+fn test_disabled() {
+    let (validator, reported) = validator(true);
+    assert_eq!(validator.enabled(false).check(b"a 1\n"), Ok(()));
+    assert!(reported.lock().unwrap().is_empty());
+}
+
+#[test]
+fn test_rejection() {
+    let (validator, _) = validator(true);
+    let diagnostics = validator
+        .check(b"# TYPE a counter\na_total 1\n")
+        .unwrap_err();
+    assert_eq!(
+        super::rejection(&diagnostics),
+        "syntax at 27..27: invalid exposition (Char)\n"
+    );
+}
+
+#[test]
+fn test_default() {
+    let validator = Validator::default().enabled(true);
+    assert_eq!(validator.check(b"a 1\n"), Ok(()));
+}
+
+#[cfg(feature = "warp")]
+#[rstest::rstest]
+#[case("a 1\n# EOF\n", 200, crate::CONTENT_TYPE)]
+#[case("a 1\n", 500, "text/plain; charset=utf-8")]
+#[tokio::test]
+async fn test_warp(#[case] body: &'static str, #[case] status: u16, #[case] content_type: &str) {
+    use warp::Filter;
+
+    let (validator, _) = validator(true);
+    let filter = super::warp(validator, warp::any().map(move || body.to_owned()));
+    let response = warp::test::request().reply(&filter).await;
+    assert_eq!(response.status(), status);
+    assert_eq!(
+        response
+            .headers()
+            .get("content-type")
+            .map(|value| value.to_str().unwrap()),
+        Some(content_type)
+    );
+}
+
+#[cfg(feature = "tide")]
+#[rstest::rstest]
+#[case("a 1\n# EOF\n", 200, crate::CONTENT_TYPE)]
+#[case("a 1\n", 500, "text/plain; charset=utf-8")]
+#[tokio::test]
+async fn test_tide(#[case] body: &'static str, #[case] status: u16, #[case] content_type: &str) {
+    use tide::http::{Method, Mime, Request, Response, Url};
+
+    let (validator, _) = validator(true);
+    let mut app = tide::new();
+    app.at("/metrics")
+        .with(super::tide(validator))
+        .get(move |_| async move {
+            let mut response = tide::Response::new(200);
+            response.set_body(body);
+            response.set_content_type(crate::CONTENT_TYPE);
+            Ok(response)
+        });
+    let request = Request::new(Method::Get, Url::parse("http://localhost/metrics").unwrap());
+    let response: Response = app.respond(request).await.unwrap();
+    assert_eq!(u16::from(response.status()), status);
+    assert_eq!(
+        response.content_type(),
+        Some(content_type.parse::<Mime>().unwrap())
+    );
+}
+
+#[cfg(feature = "rocket")]
+#[rocket::get("/metrics")]
+fn metrics(body: &rocket::State<String>) -> super::Metrics {
+    super::Metrics(body.inner().clone())
+}
+
+#[cfg(feature = "rocket")]
+#[rstest::rstest]
+#[case("a 1\n# EOF\n", 200, crate::CONTENT_TYPE)]
+#[case("a 1\n", 500, "text/plain; charset=utf-8")]
+fn test_rocket(#[case] body: &'static str, #[case] status: u16, #[case] content_type: &str) {
+    use rocket::local::blocking::Client;
+
+    let (validator, _) = validator(true);
+    let rocket = rocket::build()
+        .mount("/", rocket::routes![metrics])
+        .manage(body.to_owned())
+        .manage(validator);
+    let client = Client::untracked(rocket).unwrap();
+    let response = client.get("/metrics").dispatch();
+    assert_eq!(response.status().code, status);
+    assert_eq!(
+        response.headers().get_one("Content-Type"),
+        Some(content_type)
+    );
+}
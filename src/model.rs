@@ -0,0 +1,942 @@
+use crate::heap::HeapSize;
+use crate::{
+    Exemplar, HelpEscapedString, Label, Metric, MetricDescriptor, MetricType, Metricfamily,
+    Metricset, Sample,
+};
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+// A family with its descriptors resolved.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MetricFamily<I> {
+    pub name: I,
+    pub r#type: MetricType,
+    pub help: Option<HelpEscapedString<I>>,
+    pub unit: Option<I>,
+    pub metrics: Vec<Metric<I>>,
+    // the index of the family in the metricset, 0 if converted on its own
+    pub ordinal: usize,
+    // the index of every sample (in the order of `metrics`) among the samples of the metricset,
+    // or of the family if converted on its own
+    pub sample_ordinals: Vec<usize>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Error {
+    Empty,
+    // descriptors naming different families
+    NameMismatch {
+        name: String,
+        found: String,
+    },
+    // TYPE, HELP or UNIT given more than once
+    DuplicateDescriptor {
+        name: String,
+        descriptor: &'static str,
+    },
+    // the unit is not a suffix of the name
+    UnitMismatch {
+        name: String,
+        unit: String,
+    },
+    // a sample whose name does not belong to the family
+    UnexpectedSample {
+        name: String,
+        metricname: String,
+    },
+    // the family is described with another type than the one reconstructed
+    TypeMismatch {
+        name: String,
+        expected: MetricType,
+        found: MetricType,
+    },
+    // a sample lacking a label its type requires, e.g. `le` of a bucket
+    MissingLabel {
+        metricname: String,
+        label: &'static str,
+    },
+    // a metric point lacking a sample its type requires, e.g. `_total` of a counter
+    MissingSample {
+        name: String,
+        suffix: &'static str,
+    },
+    // a stateset sample without the label named after its family
+    MissingStateLabel {
+        name: String,
+        metricname: String,
+    },
+    // a stateset sample whose value is neither 0 nor 1
+    InvalidState {
+        metricname: String,
+        number: String,
+    },
+    // an info sample whose value is not 1
+    InvalidInfo {
+        metricname: String,
+        number: String,
+    },
+    // an info labelset exposed more than once, whatever the timestamps
+    DuplicateInfo {
+        metricname: String,
+    },
+    // a quantile exposed twice within a summary metric point
+    DuplicateQuantile {
+        metricname: String,
+        quantile: String,
+    },
+    // a quantile exposed by other metric points of the summary but not by this one
+    MissingQuantile {
+        metricname: String,
+        quantile: String,
+    },
+    // a sample value (or `le`) that is not a number
+    InvalidNumber {
+        metricname: String,
+        number: String,
+    },
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Empty => write!(f, "empty family"),
+            Self::NameMismatch { name, found } => {
+                write!(f, "`{found}` is described within `{name}`")
+            }
+            Self::DuplicateDescriptor { name, descriptor } => {
+                write!(f, "`{name}` has more than one {descriptor}")
+            }
+            Self::UnitMismatch { name, unit } => {
+                write!(f, "`{name}` does not end with its unit `{unit}`")
+            }
+            Self::UnexpectedSample { name, metricname } => {
+                write!(f, "`{metricname}` does not belong to `{name}`")
+            }
+            Self::TypeMismatch {
+                name,
+                expected,
+                found,
+            } => write!(f, "`{name}` is a {found:?}, not a {expected:?}"),
+            Self::MissingLabel { metricname, label } => {
+                write!(f, "`{metricname}` has no `{label}` label")
+            }
+            Self::MissingSample { name, suffix } => {
+                write!(f, "`{name}` has a metric point without `{name}{suffix}`")
+            }
+            Self::MissingStateLabel { name, metricname } => {
+                write!(f, "`{metricname}` has no `{name}` label")
+            }
+            Self::InvalidState { metricname, number } => {
+                write!(f, "`{number}` of `{metricname}` is neither 0 nor 1")
+            }
+            Self::InvalidInfo { metricname, number } => {
+                write!(f, "`{number}` of `{metricname}` is not 1")
+            }
+            Self::DuplicateInfo { metricname } => {
+                write!(f, "`{metricname}` exposes a labelset more than once")
+            }
+            Self::DuplicateQuantile {
+                metricname,
+                quantile,
+            } => write!(f, "`{metricname}` has quantile {quantile} more than once"),
+            Self::MissingQuantile {
+                metricname,
+                quantile,
+            } => write!(f, "`{metricname}` lacks quantile {quantile}"),
+            Self::InvalidNumber { metricname, number } => {
+                write!(f, "`{number}` of `{metricname}` is not a number")
+            }
+        }
+    }
+}
+
+impl Error {
+    // See `diagnostic::CODES`.
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::Empty => "OM-SEM-101",
+            Self::NameMismatch { .. } => "OM-SEM-102",
+            Self::DuplicateDescriptor { .. } => "OM-SEM-103",
+            Self::UnitMismatch { .. } => "OM-SEM-104",
+            Self::UnexpectedSample { .. } => "OM-SEM-105",
+            Self::TypeMismatch { .. } => "OM-SEM-106",
+            Self::MissingLabel { .. } => "OM-SEM-107",
+            Self::InvalidNumber { .. } => "OM-SEM-108",
+            Self::MissingSample { .. } => "OM-SEM-109",
+            Self::MissingStateLabel { .. } => "OM-SEM-110",
+            Self::InvalidState { .. } => "OM-SEM-111",
+            Self::InvalidInfo { .. } => "OM-SEM-112",
+            Self::DuplicateInfo { .. } => "OM-SEM-113",
+            Self::DuplicateQuantile { .. } => "OM-SEM-114",
+            Self::MissingQuantile { .. } => "OM-SEM-115",
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl<I> TryFrom<Metricfamily<I>> for MetricFamily<I>
+where
+    I: AsRef<str> + Clone,
+{
+    type Error = Error;
+
+    fn try_from(metricfamily: Metricfamily<I>) -> Result<Self, Self::Error> {
+        let r#type = metricfamily.metric_type();
+        let name = match metricfamily.metric_descriptor.first() {
+            Some(metric_descriptor) => metric_descriptor.metricname().clone(),
+            None => metricfamily
+                .samples()
+                .next()
+                .map(|sample| sample.metricname.clone())
+                .ok_or(Error::Empty)?,
+        };
+
+        let mut seen_type = false;
+        let mut help = None;
+        let mut unit = None;
+        for metric_descriptor in metricfamily.metric_descriptor {
+            let found = metric_descriptor.metricname().as_ref();
+            if found != name.as_ref() {
+                return Err(Error::NameMismatch {
+                    name: name.as_ref().to_owned(),
+                    found: found.to_owned(),
+                });
+            }
+            let (descriptor, duplicate) = match metric_descriptor.value {
+                MetricDescriptor::Type { .. } => ("TYPE", std::mem::replace(&mut seen_type, true)),
+                MetricDescriptor::Help { escaped_string, .. } => {
+                    ("HELP", help.replace(escaped_string.value).is_some())
+                }
+                MetricDescriptor::Unit {
+                    metricname_char, ..
+                } => ("UNIT", unit.replace(metricname_char).is_some()),
+            };
+            if duplicate {
+                return Err(Error::DuplicateDescriptor {
+                    name: name.as_ref().to_owned(),
+                    descriptor,
+                });
+            }
+        }
+        // https://github.com/prometheus/OpenMetrics/blob/main/specification/OpenMetrics.md#unit
+        let unit = unit.filter(|unit: &I| !unit.as_ref().is_empty());
+        if let Some(unit) = &unit {
+            let suffixed = name
+                .as_ref()
+                .strip_suffix(unit.as_ref())
+                .is_some_and(|name| name.ends_with('_'));
+            if !suffixed {
+                return Err(Error::UnitMismatch {
+                    name: name.as_ref().to_owned(),
+                    unit: unit.as_ref().to_owned(),
+                });
+            }
+        }
+
+        for metric in &metricfamily.metric {
+            for sample in &metric.sample {
+                let metricname = sample.metricname.as_ref();
+                let belongs = metricname
+                    .strip_prefix(name.as_ref())
+                    .is_some_and(|suffix| r#type.sample_suffixes().contains(&suffix));
+                if !belongs {
+                    return Err(Error::UnexpectedSample {
+                        name: name.as_ref().to_owned(),
+                        metricname: metricname.to_owned(),
+                    });
+                }
+            }
+        }
+
+        let metrics = metricfamily
+            .metric
+            .into_iter()
+            .map(|metric| metric.value)
+            .collect::<Vec<_>>();
+        let sample_ordinals = (0..metrics.iter().map(|metric| metric.sample.len()).sum()).collect();
+        Ok(Self {
+            name,
+            r#type,
+            help,
+            unit,
+            metrics,
+            ordinal: 0,
+            sample_ordinals,
+        })
+    }
+}
+
+// Converts every family of `metricset`, numbering families and samples in the order of the
+// input, so that the order can be restored with `restore_order` after regrouping or merging.
+pub fn metric_families<I>(metricset: Metricset<I>) -> Result<Vec<MetricFamily<I>>, Error>
+where
+    I: AsRef<str> + Clone,
+{
+    let mut offset = 0;
+    metricset
+        .metricfamily
+        .into_iter()
+        .enumerate()
+        .map(|(ordinal, metricfamily)| {
+            let mut family = MetricFamily::try_from(metricfamily.value)?;
+            family.ordinal = ordinal;
+            for sample_ordinal in &mut family.sample_ordinals {
+                *sample_ordinal += offset;
+            }
+            offset += family.sample_ordinals.len();
+            Ok(family)
+        })
+        .collect()
+}
+
+// The approximate heap size of the families returned by `metric_families`, their slice included
+// as the buffer of the vector; a walk of every family like `MetricFamily::approx_heap_size`.
+pub fn approx_heap_size<I>(families: &[MetricFamily<I>]) -> usize
+where
+    I: HeapSize,
+{
+    size_of_val(families) + families.iter().map(HeapSize::heap_size).sum::<usize>()
+}
+
+impl<I> MetricFamily<I> {
+    // Moves the metrics of `other` into `self`, keeping their ordinals. The family takes the
+    // place of the earlier of both.
+    pub fn merge(&mut self, other: Self) {
+        self.ordinal = self.ordinal.min(other.ordinal);
+        self.metrics.extend(other.metrics);
+        self.sample_ordinals.extend(other.sample_ordinals);
+    }
+
+    // Sorts the samples of every metric, then the metrics by their first sample, by ordinal.
+    pub fn restore_sample_order(&mut self) {
+        let mut ordinals = self.sample_ordinals.iter().copied();
+        let mut metrics = std::mem::take(&mut self.metrics)
+            .into_iter()
+            .map(|metric| {
+                let mut sample = metric
+                    .sample
+                    .into_iter()
+                    .map(|sample| (ordinals.next().unwrap_or(usize::MAX), sample))
+                    .collect::<Vec<_>>();
+                sample.sort_by_key(|(ordinal, _)| *ordinal);
+                sample
+            })
+            .collect::<Vec<_>>();
+        metrics.sort_by_key(|sample| sample.first().map_or(usize::MAX, |(ordinal, _)| *ordinal));
+        self.sample_ordinals = metrics
+            .iter()
+            .flatten()
+            .map(|(ordinal, _)| *ordinal)
+            .collect();
+        self.metrics = metrics
+            .into_iter()
+            .map(|sample| Metric {
+                sample: sample.into_iter().map(|(_, sample)| sample).collect(),
+            })
+            .collect();
+    }
+}
+
+// Sorts `families` and the samples of each of them back into the order of the input.
+pub fn restore_order<I>(families: &mut [MetricFamily<I>]) {
+    families.sort_by_key(|family| family.ordinal);
+    for family in families {
+        family.restore_sample_order();
+    }
+}
+
+// https://github.com/prometheus/OpenMetrics/blob/main/specification/OpenMetrics.md#metricpoint
+//
+// The samples sharing a labelset (without `le`, `quantile` or the stateset's own label, in any
+// order) and a timestamp. `labels` are the ones of the first sample, in its order.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MetricPoint<'a, I, V = Value<'a, I>> {
+    pub labels: Vec<&'a Label<I>>,
+    pub timestamp: Option<&'a I>,
+    pub value: V,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value<'a, I> {
+    Unknown(&'a Sample<I>),
+    Gauge(&'a Sample<I>),
+    Counter {
+        total: Option<&'a Sample<I>>,
+        created: Option<&'a Sample<I>>,
+    },
+    Histogram {
+        buckets: Vec<&'a Sample<I>>,
+        count: Option<&'a Sample<I>>,
+        sum: Option<&'a Sample<I>>,
+        created: Option<&'a Sample<I>>,
+    },
+    Gaugehistogram {
+        buckets: Vec<&'a Sample<I>>,
+        gcount: Option<&'a Sample<I>>,
+        gsum: Option<&'a Sample<I>>,
+    },
+    Stateset(Vec<&'a Sample<I>>),
+    Info(&'a Sample<I>),
+    Summary {
+        quantiles: Vec<&'a Sample<I>>,
+        count: Option<&'a Sample<I>>,
+        sum: Option<&'a Sample<I>>,
+        created: Option<&'a Sample<I>>,
+    },
+}
+
+// Groups the samples of `metricfamily` into metric points of type `r#type`, in the order of
+// their first sample. Samples whose name does not belong to the family are skipped.
+pub fn group_metric_points<I>(
+    metricfamily: &Metricfamily<I>,
+    r#type: MetricType,
+) -> Vec<MetricPoint<'_, I>>
+where
+    I: AsRef<str>,
+{
+    group_points(
+        metricfamily,
+        r#type,
+        |sample| Value::new(r#type, sample),
+        Value::push,
+    )
+}
+
+// Groups like `group_metric_points`, with values that `new` creates from the first sample of a
+// point and `push` fills with every sample of it and its suffix.
+fn group_points<'a, I, V>(
+    metricfamily: &'a Metricfamily<I>,
+    r#type: MetricType,
+    new: impl Fn(&'a Sample<I>) -> V,
+    push: impl Fn(&mut V, &str, &'a Sample<I>),
+) -> Vec<MetricPoint<'a, I, V>>
+where
+    I: AsRef<str>,
+{
+    let name = match metricfamily.metric_descriptor.first() {
+        Some(metric_descriptor) => metric_descriptor.metricname().as_ref(),
+        None => match metricfamily.samples().next() {
+            Some(sample) => sample.metricname.as_ref(),
+            None => return Vec::new(),
+        },
+    };
+    let magic = match r#type {
+        MetricType::Histogram | MetricType::Gaugehistogram => Some("le"),
+        MetricType::Summary => Some("quantile"),
+        MetricType::Stateset => Some(name),
+        _ => None,
+    };
+
+    let mut points = Vec::<MetricPoint<I, V>>::new();
+    let mut positions = HashMap::new();
+    for sample in metricfamily.samples() {
+        let Some(suffix) = sample
+            .metricname
+            .as_ref()
+            .strip_prefix(name)
+            .filter(|suffix| r#type.sample_suffixes().contains(suffix))
+        else {
+            continue;
+        };
+        let labels = sample
+            .labels
+            .iter()
+            .flat_map(|labels| &labels.label)
+            .map(|label| &label.value)
+            .filter(|label| Some(label.label_name.as_ref()) != magic)
+            .collect::<Vec<_>>();
+        let timestamp = sample.timestamp.as_ref();
+        let key = (labelset(&labels), timestamp.map(AsRef::as_ref));
+        let position = *positions.entry(key).or_insert_with(|| {
+            points.push(MetricPoint {
+                labels,
+                timestamp,
+                value: new(sample),
+            });
+            points.len() - 1
+        });
+        push(&mut points[position].value, suffix, sample);
+    }
+    points
+}
+
+// The labels as `(name, escaped value)` sorted by name, equal for labelsets in any order.
+fn labelset<'a, I>(labels: &[&'a Label<I>]) -> Vec<(&'a str, &'a str)>
+where
+    I: AsRef<str>,
+{
+    let mut labelset = labels
+        .iter()
+        .map(|label| (label.label_name.as_ref(), label.escaped_string.raw.as_ref()))
+        .collect::<Vec<_>>();
+    labelset.sort_unstable();
+    labelset
+}
+
+impl<'a, I> Value<'a, I> {
+    fn new(r#type: MetricType, sample: &'a Sample<I>) -> Self {
+        match r#type {
+            MetricType::Counter => Self::Counter {
+                total: None,
+                created: None,
+            },
+            MetricType::Gauge => Self::Gauge(sample),
+            MetricType::Histogram => Self::Histogram {
+                buckets: Vec::new(),
+                count: None,
+                sum: None,
+                created: None,
+            },
+            MetricType::Gaugehistogram => Self::Gaugehistogram {
+                buckets: Vec::new(),
+                gcount: None,
+                gsum: None,
+            },
+            MetricType::Stateset => Self::Stateset(Vec::new()),
+            MetricType::Info => Self::Info(sample),
+            MetricType::Summary => Self::Summary {
+                quantiles: Vec::new(),
+                count: None,
+                sum: None,
+                created: None,
+            },
+            MetricType::Unknown => Self::Unknown(sample),
+        }
+    }
+
+    fn push(&mut self, suffix: &str, sample: &'a Sample<I>) {
+        match (self, suffix) {
+            (Self::Counter { total, .. }, "_total") => *total = Some(sample),
+            (
+                Self::Counter { created, .. }
+                | Self::Histogram { created, .. }
+                | Self::Summary { created, .. },
+                "_created",
+            ) => *created = Some(sample),
+            (Self::Histogram { buckets, .. } | Self::Gaugehistogram { buckets, .. }, "_bucket") => {
+                buckets.push(sample)
+            }
+            (Self::Histogram { count, .. } | Self::Summary { count, .. }, "_count") => {
+                *count = Some(sample)
+            }
+            (Self::Histogram { sum, .. } | Self::Summary { sum, .. }, "_sum") => {
+                *sum = Some(sample)
+            }
+            (Self::Gaugehistogram { gcount, .. }, "_gcount") => *gcount = Some(sample),
+            (Self::Gaugehistogram { gsum, .. }, "_gsum") => *gsum = Some(sample),
+            (Self::Stateset(states), _) => states.push(sample),
+            (Self::Summary { quantiles, .. }, "") => quantiles.push(sample),
+            // the first sample of a single-sample point is already stored
+            _ => {}
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Counter<'a, I> {
+    pub labels: Vec<&'a Label<I>>,
+    pub timestamp: Option<&'a I>,
+    pub total: f64,
+    pub created: Option<f64>,
+    // of the `_total` sample
+    pub exemplar: Option<&'a Exemplar<I>>,
+}
+
+// Reassembles the metric points of a counter family.
+pub fn counters<I>(metricfamily: &Metricfamily<I>) -> Result<Vec<Counter<'_, I>>, Error>
+where
+    I: AsRef<str>,
+{
+    let mut counters = Vec::new();
+    let points = group_typed(
+        metricfamily,
+        MetricType::Counter,
+        |_| (None, None),
+        |(total, created), suffix, sample| match suffix {
+            "_total" => *total = Some(sample),
+            "_created" => *created = Some(sample),
+            _ => {}
+        },
+    )?;
+    for point in points {
+        let (total, created) = point.value;
+        let Some(total) = total else {
+            return Err(Error::MissingSample {
+                name: name(metricfamily).to_owned(),
+                suffix: "_total",
+            });
+        };
+        counters.push(Counter {
+            labels: point.labels,
+            timestamp: point.timestamp,
+            total: number(total)?,
+            created: created.map(number).transpose()?,
+            exemplar: total.exemplar.as_ref().map(|exemplar| &exemplar.value),
+        });
+    }
+    Ok(counters)
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Histogram<'a, I> {
+    pub labels: Vec<&'a Label<I>>,
+    pub timestamp: Option<&'a I>,
+    // `(le, cumulative count)` in the order of exposition
+    pub buckets: Vec<(f64, f64)>,
+    pub count: Option<f64>,
+    pub sum: Option<f64>,
+    pub created: Option<f64>,
+    // by the `le` of their bucket
+    pub exemplars: Vec<(f64, &'a Exemplar<I>)>,
+}
+
+// Reassembles the metric points of a histogram family.
+pub fn histograms<I>(metricfamily: &Metricfamily<I>) -> Result<Vec<Histogram<'_, I>>, Error>
+where
+    I: AsRef<str>,
+{
+    let mut histograms = Vec::new();
+    let points = group_typed(
+        metricfamily,
+        MetricType::Histogram,
+        |_| (Vec::new(), None, None, None),
+        |(buckets, count, sum, created), suffix, sample| match suffix {
+            "_bucket" => buckets.push(sample),
+            "_count" => *count = Some(sample),
+            "_sum" => *sum = Some(sample),
+            "_created" => *created = Some(sample),
+            _ => {}
+        },
+    )?;
+    for point in points {
+        let (bucket_samples, count, sum, created) = point.value;
+        let (buckets, exemplars) = buckets(&bucket_samples)?;
+        histograms.push(Histogram {
+            labels: point.labels,
+            timestamp: point.timestamp,
+            buckets,
+            count: count.map(number).transpose()?,
+            sum: sum.map(number).transpose()?,
+            created: created.map(number).transpose()?,
+            exemplars,
+        });
+    }
+    Ok(histograms)
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct GaugeHistogram<'a, I> {
+    pub labels: Vec<&'a Label<I>>,
+    pub timestamp: Option<&'a I>,
+    // `(le, cumulative count)` in the order of exposition
+    pub buckets: Vec<(f64, f64)>,
+    pub gcount: Option<f64>,
+    pub gsum: Option<f64>,
+    // by the `le` of their bucket
+    pub exemplars: Vec<(f64, &'a Exemplar<I>)>,
+}
+
+// Reassembles the metric points of a gaugehistogram family.
+pub fn gauge_histograms<I>(
+    metricfamily: &Metricfamily<I>,
+) -> Result<Vec<GaugeHistogram<'_, I>>, Error>
+where
+    I: AsRef<str>,
+{
+    let mut gauge_histograms = Vec::new();
+    let points = group_typed(
+        metricfamily,
+        MetricType::Gaugehistogram,
+        |_| (Vec::new(), None, None),
+        |(buckets, gcount, gsum), suffix, sample| match suffix {
+            "_bucket" => buckets.push(sample),
+            "_gcount" => *gcount = Some(sample),
+            "_gsum" => *gsum = Some(sample),
+            _ => {}
+        },
+    )?;
+    for point in points {
+        let (bucket_samples, gcount, gsum) = point.value;
+        let (buckets, exemplars) = buckets(&bucket_samples)?;
+        gauge_histograms.push(GaugeHistogram {
+            labels: point.labels,
+            timestamp: point.timestamp,
+            buckets,
+            gcount: gcount.map(number).transpose()?,
+            gsum: gsum.map(number).transpose()?,
+            exemplars,
+        });
+    }
+    Ok(gauge_histograms)
+}
+
+// `(le, cumulative count)` of each bucket sample, and their exemplars by `le`.
+type Buckets<'a, I> = (Vec<(f64, f64)>, Vec<(f64, &'a Exemplar<I>)>);
+
+fn buckets<'a, I>(samples: &[&'a Sample<I>]) -> Result<Buckets<'a, I>, Error>
+where
+    I: AsRef<str>,
+{
+    let mut buckets = Vec::with_capacity(samples.len());
+    let mut exemplars = Vec::new();
+    for sample in samples {
+        let le = label(sample, "le")?;
+        let le = parse(sample, le)?;
+        buckets.push((le, number(sample)?));
+        if let Some(exemplar) = &sample.exemplar {
+            exemplars.push((le, &exemplar.value));
+        }
+    }
+    Ok((buckets, exemplars))
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Stateset<'a, I> {
+    pub labels: Vec<&'a Label<I>>,
+    pub timestamp: Option<&'a I>,
+    // by the unescaped value of the label named after the family
+    pub states: Vec<(Cow<'a, str>, bool)>,
+}
+
+// Reassembles the metric points of a stateset family.
+pub fn statesets<I>(metricfamily: &Metricfamily<I>) -> Result<Vec<Stateset<'_, I>>, Error>
+where
+    I: AsRef<str>,
+{
+    let name = name(metricfamily);
+    let mut statesets = Vec::new();
+    let points = group_typed(
+        metricfamily,
+        MetricType::Stateset,
+        |_| Vec::new(),
+        |samples, _, sample| samples.push(sample),
+    )?;
+    for point in points {
+        let samples = point.value;
+        let mut states = Vec::with_capacity(samples.len());
+        for sample in samples {
+            let state = sample
+                .labels
+                .iter()
+                .flat_map(|labels| &labels.label)
+                .find(|label| label.label_name.as_ref() == name)
+                .map(|label| label.escaped_string.unescape())
+                .ok_or_else(|| Error::MissingStateLabel {
+                    name: name.to_owned(),
+                    metricname: sample.metricname.as_ref().to_owned(),
+                })?;
+            let enabled = match number(sample)? {
+                0. => false,
+                1. => true,
+                _ => {
+                    return Err(Error::InvalidState {
+                        metricname: sample.metricname.as_ref().to_owned(),
+                        number: sample.number.as_ref().to_owned(),
+                    });
+                }
+            };
+            states.push((state, enabled));
+        }
+        statesets.push(Stateset {
+            labels: point.labels,
+            timestamp: point.timestamp,
+            states,
+        });
+    }
+    Ok(statesets)
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Info<'a, I> {
+    pub labels: Vec<&'a Label<I>>,
+    pub timestamp: Option<&'a I>,
+}
+
+// Extracts the labelsets of an info family. Each one has to be exposed once with the value 1.
+pub fn infos<I>(metricfamily: &Metricfamily<I>) -> Result<Vec<Info<'_, I>>, Error>
+where
+    I: AsRef<str>,
+{
+    check_type(metricfamily, MetricType::Info)?;
+    let name = name(metricfamily);
+    let mut infos = Vec::<Info<I>>::new();
+    let mut labelsets = HashSet::new();
+    for sample in metricfamily.samples() {
+        let metricname = sample.metricname.as_ref();
+        if metricname.strip_prefix(name) != Some("_info") {
+            continue;
+        }
+        if number(sample)? != 1. {
+            return Err(Error::InvalidInfo {
+                metricname: metricname.to_owned(),
+                number: sample.number.as_ref().to_owned(),
+            });
+        }
+        let labels = sample
+            .labels
+            .iter()
+            .flat_map(|labels| &labels.label)
+            .map(|label| &label.value)
+            .collect::<Vec<_>>();
+        if !labelsets.insert(labelset(&labels)) {
+            return Err(Error::DuplicateInfo {
+                metricname: metricname.to_owned(),
+            });
+        }
+        infos.push(Info {
+            labels,
+            timestamp: sample.timestamp.as_ref(),
+        });
+    }
+    Ok(infos)
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Summary<'a, I> {
+    pub labels: Vec<&'a Label<I>>,
+    pub timestamp: Option<&'a I>,
+    // `(quantile, value)` in the order of exposition
+    pub quantiles: Vec<(f64, f64)>,
+    pub count: Option<f64>,
+    pub sum: Option<f64>,
+    pub created: Option<f64>,
+}
+
+// Reassembles the metric points of a summary family. Every point has to expose the same set of
+// quantiles, each once.
+pub fn summaries<I>(metricfamily: &Metricfamily<I>) -> Result<Vec<Summary<'_, I>>, Error>
+where
+    I: AsRef<str>,
+{
+    let mut summaries = Vec::new();
+    // the sample exposing each quantile first, to report missing ones
+    let mut exposed = Vec::<(f64, &Sample<I>)>::new();
+    let points = group_typed(
+        metricfamily,
+        MetricType::Summary,
+        |_| (Vec::new(), None, None, None),
+        |(quantiles, count, sum, created), suffix, sample| match suffix {
+            "" => quantiles.push(sample),
+            "_count" => *count = Some(sample),
+            "_sum" => *sum = Some(sample),
+            "_created" => *created = Some(sample),
+            _ => {}
+        },
+    )?;
+    for point in points {
+        let (quantile_samples, count, sum, created) = point.value;
+        let mut quantiles = Vec::<(f64, f64)>::with_capacity(quantile_samples.len());
+        for sample in quantile_samples {
+            let quantile = label(sample, "quantile")?;
+            let parsed = parse(sample, quantile)?;
+            if quantiles.iter().any(|(q, _)| *q == parsed) {
+                return Err(Error::DuplicateQuantile {
+                    metricname: sample.metricname.as_ref().to_owned(),
+                    quantile: quantile.to_owned(),
+                });
+            }
+            quantiles.push((parsed, number(sample)?));
+            if !exposed.iter().any(|(q, _)| *q == parsed) {
+                exposed.push((parsed, sample));
+            }
+        }
+        summaries.push(Summary {
+            labels: point.labels,
+            timestamp: point.timestamp,
+            quantiles,
+            count: count.map(number).transpose()?,
+            sum: sum.map(number).transpose()?,
+            created: created.map(number).transpose()?,
+        });
+    }
+    for summary in &summaries {
+        for (quantile, sample) in &exposed {
+            if !summary.quantiles.iter().any(|(q, _)| q == quantile) {
+                return Err(Error::MissingQuantile {
+                    metricname: sample.metricname.as_ref().to_owned(),
+                    quantile: label(sample, "quantile")?.to_owned(),
+                });
+            }
+        }
+    }
+    Ok(summaries)
+}
+
+fn group_typed<'a, I, V>(
+    metricfamily: &'a Metricfamily<I>,
+    expected: MetricType,
+    new: impl Fn(&'a Sample<I>) -> V,
+    push: impl Fn(&mut V, &str, &'a Sample<I>),
+) -> Result<Vec<MetricPoint<'a, I, V>>, Error>
+where
+    I: AsRef<str>,
+{
+    check_type(metricfamily, expected)?;
+    Ok(group_points(metricfamily, expected, new, push))
+}
+
+fn check_type<I>(metricfamily: &Metricfamily<I>, expected: MetricType) -> Result<(), Error>
+where
+    I: AsRef<str>,
+{
+    let found = metricfamily.metric_type();
+    if found != expected {
+        return Err(Error::TypeMismatch {
+            name: name(metricfamily).to_owned(),
+            expected,
+            found,
+        });
+    }
+    Ok(())
+}
+
+fn name<I>(metricfamily: &Metricfamily<I>) -> &str
+where
+    I: AsRef<str>,
+{
+    match metricfamily.metric_descriptor.first() {
+        Some(metric_descriptor) => metric_descriptor.metricname().as_ref(),
+        None => metricfamily
+            .samples()
+            .next()
+            .map_or("", |sample| sample.metricname.as_ref()),
+    }
+}
+
+fn label<'a, I>(sample: &'a Sample<I>, label_name: &'static str) -> Result<&'a str, Error>
+where
+    I: AsRef<str>,
+{
+    sample
+        .labels
+        .iter()
+        .flat_map(|labels| &labels.label)
+        .find(|label| label.label_name.as_ref() == label_name)
+        .map(|label| label.escaped_string.raw.as_ref())
+        .ok_or_else(|| Error::MissingLabel {
+            metricname: sample.metricname.as_ref().to_owned(),
+            label: label_name,
+        })
+}
+
+fn number<I>(sample: &Sample<I>) -> Result<f64, Error>
+where
+    I: AsRef<str>,
+{
+    parse(sample, sample.number.as_ref())
+}
+
+fn parse<I>(sample: &Sample<I>, number: &str) -> Result<f64, Error>
+where
+    I: AsRef<str>,
+{
+    crate::parse_number(number).ok_or_else(|| Error::InvalidNumber {
+        metricname: sample.metricname.as_ref().to_owned(),
+        number: number.to_owned(),
+    })
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,425 @@
+use super::{Error, MetricFamily, Value};
+use crate::MetricType;
+use nom::error::Error as NomError;
+use nom::{Finish, Parser};
+
+fn check(input: &str) -> Result<MetricFamily<&str>, Error> {
+    let (_, metricfamily) = crate::metricfamily::<_, NomError<_>>
+        .parse(input)
+        .finish()
+        .unwrap();
+    MetricFamily::try_from(metricfamily)
+}
+
+#[test]
+fn test_try_from() {
+    let family = check(
+        r#"# TYPE a_seconds histogram
+# HELP a_seconds Help.
+# UNIT a_seconds seconds
+a_seconds_bucket{le="+Inf"} 1
+a_seconds_count 1
+a_seconds_sum 1
+"#,
+    )
+    .unwrap();
+    assert_eq!(family.name, "a_seconds");
+    assert_eq!(family.r#type, MetricType::Histogram);
+    assert_eq!(family.help.unwrap().unescape(), "Help.");
+    assert_eq!(family.unit, Some("seconds"));
+    assert_eq!(family.metrics[0].sample.len(), 3);
+
+    let family = check("b 1\n").unwrap();
+    assert_eq!(family.name, "b");
+    assert_eq!(family.r#type, MetricType::Unknown);
+}
+
+#[rstest::rstest]
+#[case(
+    "# TYPE a counter\n# HELP b Help.\n",
+    Error::NameMismatch { name: "a".to_owned(), found: "b".to_owned() },
+)]
+#[case(
+    "# TYPE a counter\n# TYPE a gauge\n",
+    Error::DuplicateDescriptor { name: "a".to_owned(), descriptor: "TYPE" },
+)]
+#[case(
+    "# TYPE a gauge\n# UNIT a seconds\n",
+    Error::UnitMismatch { name: "a".to_owned(), unit: "seconds".to_owned() },
+)]
+#[case(
+    "# TYPE a counter\na 1\n",
+    Error::UnexpectedSample { name: "a".to_owned(), metricname: "a".to_owned() },
+)]
+fn test_try_from_error(#[case] input: &str, #[case] expected: Error) {
+    assert!(crate::diagnostic::describe(expected.code()).is_some());
+    assert_eq!(check(input), Err(expected));
+}
+
+#[test]
+fn test_restore_order() {
+    let input = r#"# TYPE a counter
+a_total 1
+# TYPE b gauge
+b 2
+# TYPE a counter
+a_total{c="d"} 3
+a_total{c="e"} 4
+# EOF
+"#;
+    let (_, exposition) = crate::exposition::<_, NomError<_>>
+        .parse(input)
+        .finish()
+        .unwrap();
+    let mut families = super::metric_families(exposition.metricset.value).unwrap();
+    assert_eq!(
+        families
+            .iter()
+            .map(|family| (family.ordinal, family.sample_ordinals.clone()))
+            .collect::<Vec<_>>(),
+        [(0, vec![0]), (1, vec![1]), (2, vec![2, 3])],
+    );
+
+    // regrouped the other way around
+    let mut a = families.pop().unwrap();
+    a.metrics.reverse();
+    for metric in &mut a.metrics {
+        metric.sample.reverse();
+    }
+    a.sample_ordinals.reverse();
+    a.merge(families.remove(0));
+    families.insert(0, a);
+    families.reverse();
+
+    super::restore_order(&mut families);
+    let samples = families
+        .iter()
+        .map(|family| {
+            let samples = family
+                .metrics
+                .iter()
+                .flat_map(|metric| &metric.sample)
+                .map(|sample| sample.raw)
+                .collect::<Vec<_>>();
+            (family.ordinal, samples, family.sample_ordinals.clone())
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        samples,
+        [
+            (
+                0,
+                vec![
+                    "a_total 1\n",
+                    "a_total{c=\"d\"} 3\n",
+                    "a_total{c=\"e\"} 4\n"
+                ],
+                vec![0, 2, 3],
+            ),
+            (1, vec!["b 2\n"], vec![1]),
+        ],
+    );
+}
+
+#[test]
+fn test_group_metric_points() {
+    let input = r#"# TYPE a histogram
+a_bucket{job="x",le="1"} 1
+a_bucket{job="x",le="+Inf"} 2
+a_count{job="x"} 2
+a_sum{job="x"} 3
+a_bucket{job="y",le="+Inf"} 1
+a_count{job="y"} 1
+a_sum{job="y"} 1
+a_created{job="x"} 0
+"#;
+    let (_, metricfamily) = crate::metricfamily::<_, NomError<_>>
+        .parse(input)
+        .finish()
+        .unwrap();
+    let points = super::group_metric_points(&metricfamily, MetricType::Histogram);
+    assert_eq!(points.len(), 2);
+    let labels = points
+        .iter()
+        .map(|point| {
+            point
+                .labels
+                .iter()
+                .map(|label| label.escaped_string.raw)
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(labels, [["x"], ["y"]]);
+    let Value::Histogram {
+        buckets,
+        count,
+        sum,
+        created,
+    } = &points[0].value
+    else {
+        panic!();
+    };
+    assert_eq!(buckets.len(), 2);
+    assert_eq!(count.unwrap().number, "2");
+    assert_eq!(sum.unwrap().number, "3");
+    assert_eq!(created.unwrap().number, "0");
+}
+
+#[test]
+fn test_group_metric_points_stateset() {
+    let input = r#"# TYPE a stateset
+a{job="x",env="p",a="on"} 1
+a{env="p",a="off",job="x"} 0
+a{job="y",env="p",a="on"} 0
+"#;
+    let (_, metricfamily) = crate::metricfamily::<_, NomError<_>>
+        .parse(input)
+        .finish()
+        .unwrap();
+    let points = super::group_metric_points(&metricfamily, MetricType::Stateset);
+    let states = points
+        .iter()
+        .map(|point| match &point.value {
+            Value::Stateset(states) => states.len(),
+            _ => panic!(),
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(states, [2, 1]);
+}
+
+#[test]
+fn test_histograms() {
+    let input = r#"# TYPE a histogram
+a_bucket{job="x",le="0.5"} 1 # {trace_id="t"} 0.25
+a_bucket{job="x",le="+Inf"} 2
+a_count{job="x"} 2
+a_sum{job="x"} 1.5
+a_created{job="x"} 10
+a_bucket{job="y",le="+Inf"} 0
+"#;
+    let (_, metricfamily) = crate::metricfamily::<_, NomError<_>>
+        .parse(input)
+        .finish()
+        .unwrap();
+    let histograms = super::histograms(&metricfamily).unwrap();
+    assert_eq!(histograms.len(), 2);
+    assert_eq!(histograms[0].buckets, [(0.5, 1.), (f64::INFINITY, 2.)]);
+    assert_eq!(histograms[0].count, Some(2.));
+    assert_eq!(histograms[0].sum, Some(1.5));
+    assert_eq!(histograms[0].created, Some(10.));
+    assert_eq!(histograms[0].exemplars.len(), 1);
+    assert_eq!(histograms[0].exemplars[0].0, 0.5);
+    assert_eq!(histograms[0].exemplars[0].1.number, "0.25");
+    assert_eq!(histograms[1].buckets, [(f64::INFINITY, 0.)]);
+    assert_eq!(histograms[1].count, None);
+}
+
+#[rstest::rstest]
+#[case(
+    "# TYPE a gauge\na 1\n",
+    Error::TypeMismatch { name: "a".to_owned(), expected: MetricType::Histogram, found: MetricType::Gauge },
+)]
+#[case(
+    "# TYPE a histogram\na_bucket 1\n",
+    Error::MissingLabel { metricname: "a_bucket".to_owned(), label: "le" },
+)]
+#[case(
+    "# TYPE a histogram\na_bucket{le=\"x\"} 1\n",
+    Error::InvalidNumber { metricname: "a_bucket".to_owned(), number: "x".to_owned() },
+)]
+fn test_histograms_error(#[case] input: &str, #[case] expected: Error) {
+    let (_, metricfamily) = crate::metricfamily::<_, NomError<_>>
+        .parse(input)
+        .finish()
+        .unwrap();
+    assert_eq!(super::histograms(&metricfamily), Err(expected));
+}
+
+#[test]
+fn test_summaries() {
+    let input = r#"# TYPE a summary
+a{job="x",quantile="0.5"} 1
+a{job="x",quantile="0.9"} 2
+a_count{job="x"} 4
+a_sum{job="x"} 5
+a{job="y",quantile="0.9"} 3
+a{job="y",quantile="0.5"} 0
+"#;
+    let (_, metricfamily) = crate::metricfamily::<_, NomError<_>>
+        .parse(input)
+        .finish()
+        .unwrap();
+    let summaries = super::summaries(&metricfamily).unwrap();
+    assert_eq!(summaries.len(), 2);
+    assert_eq!(summaries[0].quantiles, [(0.5, 1.), (0.9, 2.)]);
+    assert_eq!(summaries[0].count, Some(4.));
+    assert_eq!(summaries[0].sum, Some(5.));
+    assert_eq!(summaries[0].created, None);
+    assert_eq!(summaries[1].quantiles, [(0.9, 3.), (0.5, 0.)]);
+}
+
+#[rstest::rstest]
+#[case(
+    "# TYPE a summary\na{quantile=\"0.5\"} 1\na{quantile=\"0.50\"} 1\n",
+    Error::DuplicateQuantile { metricname: "a".to_owned(), quantile: "0.50".to_owned() },
+)]
+#[case(
+    "# TYPE a summary\na{job=\"x\",quantile=\"0.5\"} 1\na{job=\"y\",quantile=\"0.9\"} 1\n",
+    Error::MissingQuantile { metricname: "a".to_owned(), quantile: "0.9".to_owned() },
+)]
+#[case(
+    "# TYPE a summary\na_count 1\na 1\n",
+    Error::MissingLabel { metricname: "a".to_owned(), label: "quantile" },
+)]
+fn test_summaries_error(#[case] input: &str, #[case] expected: Error) {
+    let (_, metricfamily) = crate::metricfamily::<_, NomError<_>>
+        .parse(input)
+        .finish()
+        .unwrap();
+    assert_eq!(super::summaries(&metricfamily), Err(expected));
+}
+
+#[test]
+fn test_counters() {
+    let input = r#"# TYPE a counter
+a_total{job="x"} 1 # {trace_id="t"} 1
+a_created{job="x"} 10
+a_total{job="y"} 2
+"#;
+    let (_, metricfamily) = crate::metricfamily::<_, NomError<_>>
+        .parse(input)
+        .finish()
+        .unwrap();
+    let counters = super::counters(&metricfamily).unwrap();
+    assert_eq!(counters.len(), 2);
+    assert_eq!(counters[0].total, 1.);
+    assert_eq!(counters[0].created, Some(10.));
+    assert_eq!(counters[0].exemplar.unwrap().number, "1");
+    assert_eq!(counters[1].total, 2.);
+    assert_eq!(counters[1].created, None);
+    assert_eq!(counters[1].exemplar, None);
+
+    let (_, metricfamily) = crate::metricfamily::<_, NomError<_>>
+        .parse("# TYPE a counter\na_created 10\n")
+        .finish()
+        .unwrap();
+    assert_eq!(
+        super::counters(&metricfamily),
+        Err(Error::MissingSample {
+            name: "a".to_owned(),
+            suffix: "_total"
+        }),
+    );
+}
+
+#[test]
+fn test_gauge_histograms() {
+    let input = r#"# TYPE a gaugehistogram
+a_bucket{le="1"} 1
+a_bucket{le="+Inf"} 3 # {trace_id="t"} 2
+a_gcount 3
+a_gsum 4
+"#;
+    let (_, metricfamily) = crate::metricfamily::<_, NomError<_>>
+        .parse(input)
+        .finish()
+        .unwrap();
+    let gauge_histograms = super::gauge_histograms(&metricfamily).unwrap();
+    assert_eq!(gauge_histograms.len(), 1);
+    assert_eq!(gauge_histograms[0].buckets, [(1., 1.), (f64::INFINITY, 3.)]);
+    assert_eq!(gauge_histograms[0].gcount, Some(3.));
+    assert_eq!(gauge_histograms[0].gsum, Some(4.));
+    assert_eq!(gauge_histograms[0].exemplars[0].0, f64::INFINITY);
+
+    // not a histogram
+    assert!(super::histograms(&metricfamily).is_err());
+}
+
+#[test]
+fn test_statesets() {
+    let input = r#"# TYPE a stateset
+a{job="x",a="on"} 1
+a{job="x",a="off"} 0.0
+a{job="x",a="a\"b"} 0
+"#;
+    let (_, metricfamily) = crate::metricfamily::<_, NomError<_>>
+        .parse(input)
+        .finish()
+        .unwrap();
+    let statesets = super::statesets(&metricfamily).unwrap();
+    assert_eq!(statesets.len(), 1);
+    assert_eq!(statesets[0].labels[0].label_name, "job");
+    let states = statesets[0]
+        .states
+        .iter()
+        .map(|(state, enabled)| (state.as_ref(), *enabled))
+        .collect::<Vec<_>>();
+    assert_eq!(states, [("on", true), ("off", false), ("a\"b", false)]);
+}
+
+#[rstest::rstest]
+#[case(
+    "# TYPE a stateset\na{state=\"on\"} 1\n",
+    Error::MissingStateLabel { name: "a".to_owned(), metricname: "a".to_owned() },
+)]
+#[case(
+    "# TYPE a stateset\na{a=\"on\"} 2\n",
+    Error::InvalidState { metricname: "a".to_owned(), number: "2".to_owned() },
+)]
+fn test_statesets_error(#[case] input: &str, #[case] expected: Error) {
+    let (_, metricfamily) = crate::metricfamily::<_, NomError<_>>
+        .parse(input)
+        .finish()
+        .unwrap();
+    assert_eq!(super::statesets(&metricfamily), Err(expected));
+}
+
+#[test]
+fn test_infos() {
+    let input = r#"# TYPE a info
+a_info{version="1"} 1
+a_info{version="2",os="linux"} 1.0
+"#;
+    let (_, metricfamily) = crate::metricfamily::<_, NomError<_>>
+        .parse(input)
+        .finish()
+        .unwrap();
+    let infos = super::infos(&metricfamily).unwrap();
+    let labels = infos
+        .iter()
+        .map(|info| {
+            info.labels
+                .iter()
+                .map(|label| (label.label_name, label.escaped_string.raw))
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        labels,
+        [
+            vec![("version", "1")],
+            vec![("version", "2"), ("os", "linux")],
+        ],
+    );
+}
+
+#[rstest::rstest]
+#[case(
+    "# TYPE a info\na_info{version=\"1\"} 2\n",
+    Error::InvalidInfo { metricname: "a_info".to_owned(), number: "2".to_owned() },
+)]
+#[case(
+    "# TYPE a info\na_info{version=\"1\"} 1 0\na_info{version=\"1\"} 1 1\n",
+    Error::DuplicateInfo { metricname: "a_info".to_owned() },
+)]
+#[case(
+    "# TYPE a info\na_info{os=\"linux\",version=\"1\"} 1\na_info{version=\"1\",os=\"linux\"} 1\n",
+    Error::DuplicateInfo { metricname: "a_info".to_owned() },
+)]
+fn test_infos_error(#[case] input: &str, #[case] expected: Error) {
+    let (_, metricfamily) = crate::metricfamily::<_, NomError<_>>
+        .parse(input)
+        .finish()
+        .unwrap();
+    assert_eq!(super::infos(&metricfamily), Err(expected));
+}
@@ -0,0 +1,804 @@
+use crate::fold;
+use crate::grammar::{self, Rule};
+use crate::{
+    Comment, Exposition, Extension, Merged, Metric, MetricDescriptor, Metricfamily, Metricset,
+    Sample, Spanned,
+};
+use nom::branch::alt;
+use nom::bytes::complete::{tag, take_while, take_while1};
+use nom::character::complete::char;
+use nom::combinator::{opt, recognize, verify};
+use nom::error::{ContextError, ParseError, context};
+use nom::multi::{fold_many0, fold_many1, many0, many1};
+use nom::sequence::preceded;
+use nom::{AsChar, Compare, IResult, Input, Offset, Parser};
+use std::borrow::Cow;
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+
+// Deviations from the strict grammar. `ParseOptions::default()` behaves like `exposition`.
+#[derive(Clone, Default)]
+pub struct ParseOptions {
+    merge_repeated_families: bool,
+    extension: Vec<(&'static str, Accepts)>,
+    sample_filter: Option<SampleFilter>,
+    normalize_numbers: bool,
+    comments: Comments,
+    missing_eof: bool,
+}
+
+// What to do with the `#` lines that are neither a descriptor, `# EOF` nor a registered
+// extension, e.g. `# Generated by ...` of an exporter.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum Comments {
+    // fail the parse, as in the strict grammar
+    #[default]
+    Reject,
+    // accept and drop them
+    Skip,
+    // accept them into `Metricset::comment`
+    Keep,
+}
+
+// Whether the body of an extension line is well-formed.
+pub type Accepts = fn(&str) -> bool;
+
+// Whether to keep a sample, given its metricname and its labels with unescaped values.
+pub type SampleFilter = Arc<dyn Fn(&str, SampleLabels<'_, '_>) -> bool + Send + Sync>;
+
+// The labels of a sample given to a `SampleFilter`, each value unescaped as it is iterated.
+pub struct SampleLabels<'a, 'b>(&'a mut dyn Iterator<Item = (&'b str, Cow<'b, str>)>);
+
+impl<'b> Iterator for SampleLabels<'_, 'b> {
+    type Item = (&'b str, Cow<'b, str>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next()
+    }
+}
+
+// `EscapedString::unescape` of an escaped string as written.
+fn unescape(escaped_string: &str) -> Cow<'_, str> {
+    if !escaped_string.contains(crate::BS) {
+        return Cow::Borrowed(escaped_string);
+    }
+    let mut unescaped = String::with_capacity(escaped_string.len());
+    let mut chars = escaped_string.chars();
+    while let Some(c) = chars.next() {
+        if c != crate::BS {
+            unescaped.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => unescaped.push(crate::LF),
+            Some(c @ (crate::DQUOTE | crate::BS)) => unescaped.push(c),
+            // kept as written, as by `crate::escaped_string`
+            Some(c) => {
+                unescaped.push(crate::BS);
+                unescaped.push(c);
+            }
+            None => unescaped.push(crate::BS),
+        }
+    }
+    Cow::Owned(unescaped)
+}
+
+impl fmt::Debug for ParseOptions {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("ParseOptions")
+            .field("merge_repeated_families", &self.merge_repeated_families)
+            .field("extension", &self.extension)
+            .field("sample_filter", &self.sample_filter.as_ref().map(|_| ".."))
+            .field("normalize_numbers", &self.normalize_numbers)
+            .field("comments", &self.comments)
+            .field("missing_eof", &self.missing_eof)
+            .finish()
+    }
+}
+
+impl ParseOptions {
+    // Prometheus federation repeats descriptors per block, and with them the families.
+    pub fn federation() -> Self {
+        Self::default().merge_repeated_families(true)
+    }
+
+    // Merges families described under the same name into the first one. Identical descriptors
+    // are kept once, samples are concatenated. The raw input of a merged family is the one of
+    // its first occurrence, the later ones are kept in `Metricset::merged`. A family described
+    // again with another TYPE, HELP or UNIT fails with the context `conflicting_descriptor` at
+    // that descriptor.
+    pub fn merge_repeated_families(mut self, merge_repeated_families: bool) -> Self {
+        self.merge_repeated_families = merge_repeated_families;
+        self
+    }
+
+    // Captures `# <keyword> <body>` lines into `Metricset::extension` when `body` accepts the
+    // rest of the line, e.g. `# SCOPE ...`. Without any registered keyword, such lines are
+    // rejected as in the strict grammar. `TYPE`, `HELP`, `UNIT` and `EOF` cannot be overridden.
+    pub fn extension(mut self, keyword: &'static str, body: Accepts) -> Self {
+        self.extension.push((keyword, body));
+        self
+    }
+
+    // Drops the samples for which `sample_filter` does not hold, e.g. to apply an allowlist. It is
+    // called once the metricname and labels of a sample are scanned, before any node is built,
+    // with label values unescaped as they are iterated. Dropped samples are still validated but
+    // build no node, not even of their labels or exemplar, and are reported by no event. Metrics
+    // (and undescribed families) left without samples are dropped as well.
+    pub fn sample_filter<F>(mut self, sample_filter: F) -> Self
+    where
+        F: Fn(&str, SampleLabels<'_, '_>) -> bool + Send + Sync + 'static,
+    {
+        self.sample_filter = Some(Arc::new(sample_filter));
+        self
+    }
+
+    // Sets `normalized_number` of every sample and exemplar, keeping `number` as written, so that
+    // values can be compared or hashed as text. See `crate::normalize_number`.
+    pub fn normalize_numbers(mut self, normalize_numbers: bool) -> Self {
+        self.normalize_numbers = normalize_numbers;
+        self
+    }
+
+    // Accepts other `#` lines, see `Comments`. Unlike an extension line, a comment may appear
+    // within a family, between its descriptors and samples, without ending it.
+    pub fn comments(mut self, comments: Comments) -> Self {
+        self.comments = comments;
+        self
+    }
+
+    // Accepts an input that ends after a complete line without `# EOF`, e.g. a truncated or
+    // legacy response: `Exposition::eof` is then empty, see `lint::missing_eof`. A line that
+    // does not parse still fails.
+    pub fn missing_eof(mut self, missing_eof: bool) -> Self {
+        self.missing_eof = missing_eof;
+        self
+    }
+
+    // The dialect accepted by `exposition`, see `grammar::rules`.
+    pub fn rules(&self) -> Vec<Rule> {
+        let mut rules = grammar::rules();
+        for rule in &mut rules {
+            match rule.name.as_ref() {
+                "exposition" if self.missing_eof => {
+                    rule.comments
+                        .push("`HASH SP eof [ LF ]` may be missing at the end of the input".into());
+                }
+                "metricfamily" if self.comments != Comments::Reject => {
+                    rule.comments.push(
+                        "a `comment` may also precede any `metric-descriptor` or `sample`".into(),
+                    );
+                }
+                "metricset" => {
+                    let mut entries = vec!["metricfamily"];
+                    if !self.extension.is_empty() {
+                        entries.push("extension");
+                    }
+                    if self.comments != Comments::Reject {
+                        entries.push("comment");
+                    }
+                    if entries.len() > 1 {
+                        rule.alternatives = vec![format!("*({})", entries.join(" / ")).into()];
+                    }
+                    if self.merge_repeated_families {
+                        rule.comments.push(
+                            "families described under the same name are merged into the first one"
+                                .into(),
+                        );
+                    }
+                }
+                _ => {}
+            }
+        }
+        if !self.extension.is_empty() {
+            let mut keywords = Vec::<&str>::new();
+            for (keyword, _) in &self.extension {
+                if !keywords.contains(keyword) {
+                    keywords.push(keyword);
+                }
+            }
+            let keywords = keywords
+                .iter()
+                .map(|keyword| format!("%s\"{keyword}\""))
+                .collect::<Vec<_>>();
+            let position = rules
+                .iter()
+                .position(|rule| rule.name == "metricfamily")
+                .map_or(rules.len(), |position| position + 1);
+            rules.splice(
+                position..position,
+                [
+                    Rule {
+                        name: "extension".into(),
+                        alternatives: vec![
+                            "HASH SP extension-keyword [SP *extension-char] LF".into(),
+                        ],
+                        comments: vec![
+                            "the registered parser of the keyword accepts the body".into(),
+                        ],
+                    },
+                    Rule {
+                        name: "extension-keyword".into(),
+                        alternatives: vec![keywords.join(" / ").into()],
+                        comments: Vec::new(),
+                    },
+                    Rule {
+                        name: "extension-char".into(),
+                        alternatives: vec!["%x00-09 / %x0B-10FFFF".into()],
+                        comments: Vec::new(),
+                    },
+                ],
+            );
+        }
+        if self.comments != Comments::Reject {
+            let position = rules
+                .iter()
+                .rposition(|rule| rule.name == "metricfamily" || rule.name.starts_with("extension"))
+                .map_or(rules.len(), |position| position + 1);
+            rules.splice(
+                position..position,
+                [
+                    Rule {
+                        name: "comment".into(),
+                        alternatives: vec!["HASH *comment-char LF".into()],
+                        comments: vec![
+                            "neither a descriptor, `# EOF` nor an extension line".into(),
+                        ],
+                    },
+                    Rule {
+                        name: "comment-char".into(),
+                        alternatives: vec!["%x00-09 / %x0B-10FFFF".into()],
+                        comments: Vec::new(),
+                    },
+                ],
+            );
+        }
+        rules
+    }
+
+    pub fn grammar(&self) -> String {
+        grammar::to_abnf(&self.rules())
+    }
+
+    pub fn exposition<I, E>(&self, input: I) -> IResult<I, Exposition<I>, E>
+    where
+        I: AsRef<str> + Compare<&'static str> + Input + Offset,
+        I::Item: AsChar,
+        E: ContextError<I> + ParseError<I>,
+    {
+        if !self.extension.is_empty()
+            || self.comments != Comments::Reject
+            || self.missing_eof
+            || self.sample_filter.is_some()
+            || self.normalize_numbers
+        {
+            return self.exposition_with(input, |_| {});
+        }
+        let (input, mut exposition) = crate::exposition(input)?;
+        self.finish(&mut exposition)?;
+        Ok((input, exposition))
+    }
+
+    // Like `exposition`, calling `on_event` for every line as soon as it is parsed. The events
+    // of lines before a syntax error are delivered even though the parse fails.
+    pub fn exposition_with<I, E, F>(
+        &self,
+        input: I,
+        mut on_event: F,
+    ) -> IResult<I, Exposition<I>, E>
+    where
+        I: AsRef<str> + Compare<&'static str> + Input + Offset,
+        I::Item: AsChar,
+        E: ContextError<I> + ParseError<I>,
+        F: FnMut(Event<'_, I>),
+    {
+        self.exposition_until(input, |event| {
+            on_event(event);
+            false
+        })
+    }
+
+    // Like `exposition_with`, stopping after the first line for which `abort_if` holds, e.g. once
+    // the wanted metric was found or a sample budget is exceeded. The metricset parsed so far is
+    // returned without `# EOF` (and `Event::Eof`), along with the input following the line.
+    pub fn exposition_until<I, E, F>(
+        &self,
+        input: I,
+        mut abort_if: F,
+    ) -> IResult<I, Exposition<I>, E>
+    where
+        I: AsRef<str> + Compare<&'static str> + Input + Offset,
+        I::Item: AsChar,
+        E: ContextError<I> + ParseError<I>,
+        F: FnMut(Event<'_, I>) -> bool,
+    {
+        let events = Events {
+            abort_if: RefCell::new(&mut abort_if),
+            aborted: Cell::new(false),
+            sample_filter: self.sample_filter.as_ref(),
+            labels: RefCell::new(Vec::new()),
+            normalize_numbers: self.normalize_numbers,
+        };
+        let mut exposition = context("exposition", |input: I| {
+            let (input, metricset) =
+                crate::spanned(input.clone(), |input| self.metricset(input, &events))
+                    .parse(input)?;
+            if events.aborted.get() {
+                let eof = input.take(0);
+                return Ok((input, Exposition { metricset, eof }));
+            }
+            if self.missing_eof && input.input_len() == 0 {
+                let eof = input.take(0);
+                return Ok((input, Exposition { metricset, eof }));
+            }
+            let (input, eof) = recognize((
+                char(crate::HASH),
+                char(crate::SP),
+                tag(crate::EOF),
+                opt(char(crate::LF)),
+            ))
+            .parse(input)?;
+            (events.abort_if.borrow_mut())(Event::Eof);
+            Ok((input, Exposition { metricset, eof }))
+        });
+        let (input, mut exposition) = exposition.parse(input)?;
+        self.finish(&mut exposition)?;
+        Ok((input, exposition))
+    }
+
+    fn finish<I, E>(&self, exposition: &mut Exposition<I>) -> Result<(), nom::Err<E>>
+    where
+        I: AsRef<str> + Clone,
+        E: ContextError<I> + ParseError<I>,
+    {
+        if self.merge_repeated_families {
+            let metricset = &mut exposition.metricset.value;
+            let conflicts =
+                merge_repeated_families(&mut metricset.metricfamily, &mut metricset.merged);
+            if let Some(conflict) = conflicts.into_iter().next() {
+                let input = conflict.raw;
+                return Err(nom::Err::Failure(E::add_context(
+                    input.clone(),
+                    "conflicting_descriptor",
+                    E::from_error_kind(input, nom::error::ErrorKind::Verify),
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    fn metricset<I, E>(&self, input: I, events: &Events<I>) -> IResult<I, Metricset<I>, E>
+    where
+        I: AsRef<str> + Compare<&'static str> + Input + Offset,
+        I::Item: AsChar,
+        E: ContextError<I> + ParseError<I>,
+    {
+        let origin = input.clone();
+        context(
+            "metricset",
+            fold_many0(
+                alt((
+                    crate::spanned(origin.clone(), |input| metricfamily(input, self, events)).map(
+                        |Spanned { range, raw, value }| {
+                            let (metricfamily, comment) = value;
+                            Entry::Metricfamily(
+                                Spanned {
+                                    range,
+                                    raw,
+                                    value: metricfamily,
+                                },
+                                comment,
+                            )
+                        },
+                    ),
+                    |suffix: I| {
+                        events.check(&suffix)?;
+                        let (suffix, extension) =
+                            crate::spanned(origin.clone(), |input| self.extension_line(input))
+                                .parse(suffix)?;
+                        events.emit(Event::Extension(&extension));
+                        Ok((suffix, Entry::Extension(extension)))
+                    },
+                )),
+                || Metricset {
+                    metricfamily: Vec::new(),
+                    extension: Vec::new(),
+                    comment: Vec::new(),
+                    merged: Vec::new(),
+                },
+                |mut metricset, entry| {
+                    match entry {
+                        Entry::Metricfamily(metricfamily, comment) => {
+                            if !metricfamily.metric_descriptor.is_empty()
+                                || !metricfamily.metric.is_empty()
+                            {
+                                metricset.metricfamily.push(metricfamily);
+                            }
+                            metricset.comment.extend(comment);
+                        }
+                        Entry::Extension(extension) => metricset.extension.push(extension),
+                    }
+                    metricset
+                },
+            ),
+        )
+        .parse(input)
+    }
+
+    fn extension_line<I, E>(&self, input: I) -> IResult<I, Extension<I>, E>
+    where
+        I: AsRef<str> + Compare<&'static str> + Input + Offset,
+        I::Item: AsChar,
+        E: ContextError<I> + ParseError<I>,
+    {
+        let accepts = |keyword: &I, body: &str| {
+            ![crate::TYPE, crate::HELP, crate::UNIT, crate::EOF].contains(&keyword.as_ref())
+                && self
+                    .extension
+                    .iter()
+                    .any(|(registered, accepts)| *registered == keyword.as_ref() && accepts(body))
+        };
+        let (input, (_, _, keyword, body, _)) = verify(
+            (
+                char(crate::HASH),
+                char(crate::SP),
+                take_while1(|c: I::Item| {
+                    let c = c.as_char();
+                    c != crate::SP && c != crate::LF
+                }),
+                opt(preceded(
+                    char(crate::SP),
+                    take_while(|c: I::Item| c.as_char() != crate::LF),
+                )),
+                char(crate::LF),
+            ),
+            |(_, _, keyword, body, _): &(_, _, I, Option<I>, _)| {
+                accepts(keyword, body.as_ref().map_or("", AsRef::as_ref))
+            },
+        )
+        .parse(input)?;
+        let body = body.unwrap_or_else(|| keyword.take_from(keyword.input_len()));
+        Ok((input, Extension { keyword, body }))
+    }
+
+    fn comment_line<I, E>(&self, input: I) -> IResult<I, Comment<I>, E>
+    where
+        I: AsRef<str> + Compare<&'static str> + Input + Offset,
+        I::Item: AsChar,
+        E: ContextError<I> + ParseError<I>,
+    {
+        // a malformed descriptor or a rejected extension line still fails
+        let accepts = |text: &I| {
+            let keyword = text
+                .as_ref()
+                .strip_prefix(crate::SP)
+                .and_then(|text| text.split(crate::SP).next());
+            self.comments != Comments::Reject
+                && keyword.is_none_or(|keyword| {
+                    ![crate::TYPE, crate::HELP, crate::UNIT, crate::EOF].contains(&keyword)
+                        && !self
+                            .extension
+                            .iter()
+                            .any(|(registered, _)| *registered == keyword)
+                })
+        };
+        let (input, (_, text, _)) = verify(
+            (
+                char(crate::HASH),
+                take_while(|c: I::Item| c.as_char() != crate::LF),
+                char(crate::LF),
+            ),
+            |(_, text, _): &(_, I, _)| accepts(text),
+        )
+        .parse(input)?;
+        Ok((input, Comment { text }))
+    }
+}
+
+// A line of the exposition, with its raw input.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Event<'a, I> {
+    MetricDescriptor(&'a Spanned<I, MetricDescriptor<I>>),
+    Sample(&'a Spanned<I, Sample<I>>),
+    Extension(&'a Spanned<I, Extension<I>>),
+    Comment(&'a Spanned<I, Comment<I>>),
+    Eof,
+}
+
+type AbortIf<'a, I> = dyn FnMut(Event<'_, I>) -> bool + 'a;
+
+struct Events<'a, I> {
+    abort_if: RefCell<&'a mut AbortIf<'a, I>>,
+    aborted: Cell<bool>,
+    sample_filter: Option<&'a SampleFilter>,
+    // the labels of the last sample scanned for `sample_filter`, reused across samples
+    labels: RefCell<Vec<(I, I)>>,
+    normalize_numbers: bool,
+}
+
+impl<I> Events<'_, I>
+where
+    I: AsRef<str>,
+{
+    fn emit(&self, event: Event<'_, I>) {
+        if (self.abort_if.borrow_mut())(event) {
+            self.aborted.set(true);
+        }
+    }
+
+    // Scans the metricname and labels of the sample at `input` for `sample_filter`, without
+    // building their nodes, and returns the input after them and whether to keep the sample.
+    // `None` without a filter or if they do not parse, which building them then reports.
+    fn scan(&self, input: I) -> Option<(I, bool)>
+    where
+        I: Clone + Input + Offset,
+        I::Item: AsChar,
+    {
+        let sample_filter = self.sample_filter?;
+        let (rest, metricname) = crate::metricname::<_, ()>(input).ok()?;
+        let mut scanned = self.labels.borrow_mut();
+        scanned.clear();
+        let rest = match rest.iter_elements().next() {
+            Some(c) if c.as_char() == '{' => {
+                fold::labels::<_, (), _>(rest, |label_name, escaped_string| {
+                    scanned.push((label_name, escaped_string))
+                })
+                .ok()?
+                .0
+            }
+            _ => rest,
+        };
+        let mut labels = scanned.iter().map(|(label_name, escaped_string)| {
+            (label_name.as_ref(), unescape(escaped_string.as_ref()))
+        });
+        let keeps = sample_filter(metricname.as_ref(), SampleLabels(&mut labels));
+        Some((rest, keeps))
+    }
+
+    fn normalize(&self, sample: &mut Sample<I>) {
+        if !self.normalize_numbers {
+            return;
+        }
+        sample.normalized_number = crate::normalize_number(sample.number.as_ref());
+        if let Some(exemplar) = &mut sample.exemplar {
+            exemplar.normalized_number = crate::normalize_number(exemplar.number.as_ref());
+        }
+    }
+
+    // Fails the next line once aborted, ending every repetition of the grammar.
+    fn check<E>(&self, input: &I) -> Result<(), nom::Err<E>>
+    where
+        I: Clone,
+        E: ParseError<I>,
+    {
+        if self.aborted.get() {
+            return Err(nom::Err::Error(E::from_error_kind(
+                input.clone(),
+                nom::error::ErrorKind::Fail,
+            )));
+        }
+        Ok(())
+    }
+}
+
+// `crate::metricfamily`, reporting its lines. The grammar never backtracks over a parsed line,
+// so every event belongs to the result. Comment lines accepted by `options` may precede any line
+// of the family, and are returned apart if kept.
+fn metricfamily<I, E>(
+    input: I,
+    options: &ParseOptions,
+    events: &Events<I>,
+) -> IResult<I, (Metricfamily<I>, CommentLines<I>), E>
+where
+    I: AsRef<str> + Compare<&'static str> + Input + Offset,
+    I::Item: AsChar,
+    E: ContextError<I> + ParseError<I>,
+{
+    let origin = input.clone();
+    let comments = RefCell::new(Vec::new());
+    let comment = |suffix: I| -> IResult<I, (), E> {
+        events.check(&suffix)?;
+        let (suffix, comment) =
+            crate::spanned(origin.clone(), |input| options.comment_line(input)).parse(suffix)?;
+        events.emit(Event::Comment(&comment));
+        if options.comments == Comments::Keep {
+            comments.borrow_mut().push(comment);
+        }
+        Ok((suffix, ()))
+    };
+    let metric_descriptor = |suffix: I| {
+        events.check(&suffix)?;
+        let (suffix, metric_descriptor) =
+            crate::spanned(origin.clone(), crate::metric_descriptor).parse(suffix)?;
+        events.emit(Event::MetricDescriptor(&metric_descriptor));
+        Ok((suffix, Some(metric_descriptor)))
+    };
+    let metric = |input: I| {
+        let origin = input.clone();
+        context(
+            "metric",
+            fold_many1(
+                alt((comment.map(|()| None), |suffix: I| {
+                    events.check(&suffix)?;
+                    let (suffix, Spanned { range, raw, value }) =
+                        crate::spanned(origin.clone(), sample(events)).parse(suffix)?;
+                    let Some(value) = value else {
+                        return Ok((suffix, None));
+                    };
+                    let mut sample = Spanned { range, raw, value };
+                    events.normalize(&mut sample.value);
+                    events.emit(Event::Sample(&sample));
+                    Ok((suffix, Some(sample)))
+                })),
+                Vec::new,
+                |mut samples, sample| {
+                    samples.extend(sample);
+                    samples
+                },
+            ),
+        )
+        .map(|sample| Metric { sample })
+        .parse(input)
+    };
+    let (input, metricfamily) = context(
+        "metricfamily",
+        alt((
+            (
+                many1(alt((comment.map(|()| None), metric_descriptor))),
+                many0(crate::spanned(origin.clone(), metric)),
+            ),
+            (
+                many0(alt((comment.map(|()| None), metric_descriptor))),
+                many1(crate::spanned(origin.clone(), metric)),
+            ),
+        )),
+    )
+    .map(|(metric_descriptor, mut metric)| {
+        metric.retain(|metric| !metric.sample.is_empty());
+        Metricfamily {
+            metric_descriptor: metric_descriptor.into_iter().flatten().collect(),
+            metric,
+        }
+    })
+    .parse(input)?;
+    Ok((input, (metricfamily, comments.into_inner())))
+}
+
+// `crate::sample`, or only its validation if `Events::scan` drops it.
+fn sample<'a, I, E>(events: &'a Events<I>) -> impl Parser<I, Output = Option<Sample<I>>, Error = E>
+where
+    I: AsRef<str> + Compare<&'static str> + Input + Offset,
+    I::Item: AsChar,
+    E: ContextError<I> + ParseError<I> + 'a,
+{
+    context("sample", move |input: I| {
+        if let Some((rest, false)) = events.scan(input.clone())
+            && let Ok((rest, ())) = fold::sample_value::<_, ()>(rest)
+        {
+            return Ok((rest, None));
+        }
+        let (rest, (metricname, labels)) = crate::sample_series.parse(input.clone())?;
+        let (rest, (number, timestamp, exemplar)) = crate::sample_value(input).parse(rest)?;
+        let sample = Sample {
+            metricname,
+            labels,
+            number,
+            timestamp,
+            exemplar,
+            normalized_number: None,
+        };
+        Ok((rest, Some(sample)))
+    })
+}
+
+enum Entry<I> {
+    Metricfamily(Spanned<I, Metricfamily<I>>, CommentLines<I>),
+    Extension(Spanned<I, Extension<I>>),
+}
+
+type CommentLines<I> = Vec<Spanned<I, Comment<I>>>;
+
+// Pushes every later occurrence to `merged`. Returns the descriptors dropped for conflicting with
+// the ones of the first occurrence, in the order of the input.
+pub(crate) fn merge_repeated_families<I>(
+    metricfamily: &mut Vec<Spanned<I, Metricfamily<I>>>,
+    merged_blocks: &mut Vec<Spanned<I, Merged<I>>>,
+) -> Vec<Spanned<I, MetricDescriptor<I>>>
+where
+    I: AsRef<str> + Clone,
+{
+    let mut merged = Vec::<Spanned<I, Metricfamily<I>>>::with_capacity(metricfamily.len());
+    let mut index = HashMap::<Option<String>, usize>::new();
+    let mut conflicts = Vec::new();
+    for family in metricfamily.drain(..) {
+        let name = name(&family).map(str::to_owned);
+        match index.get(&name).and_then(|index| merged.get_mut(*index)) {
+            Some(merged) => {
+                if let Some(metricname) = metricname(&family) {
+                    merged_blocks.push(Spanned {
+                        range: family.range.clone(),
+                        raw: family.raw.clone(),
+                        value: Merged {
+                            metricname: metricname.clone(),
+                        },
+                    });
+                }
+                let family = family.value;
+                for metric_descriptor in family.metric_descriptor {
+                    let kept = merged.metric_descriptor.iter().find(|merged| {
+                        std::mem::discriminant(&merged.value)
+                            == std::mem::discriminant(&metric_descriptor.value)
+                    });
+                    match kept {
+                        Some(kept) if same_descriptor(kept, &metric_descriptor) => {}
+                        Some(_) => conflicts.push(metric_descriptor),
+                        None => merged.metric_descriptor.push(metric_descriptor),
+                    }
+                }
+                merged.metric.extend(family.metric);
+            }
+            None => {
+                index.insert(name, merged.len());
+                merged.push(family);
+            }
+        }
+    }
+    *metricfamily = merged;
+    conflicts
+}
+
+fn same_descriptor<I>(a: &MetricDescriptor<I>, b: &MetricDescriptor<I>) -> bool
+where
+    I: AsRef<str>,
+{
+    match (a, b) {
+        (
+            MetricDescriptor::Type { metric_type: a, .. },
+            MetricDescriptor::Type { metric_type: b, .. },
+        ) => a.value == b.value,
+        (
+            MetricDescriptor::Help {
+                escaped_string: a, ..
+            },
+            MetricDescriptor::Help {
+                escaped_string: b, ..
+            },
+        ) => a.raw.as_ref() == b.raw.as_ref(),
+        (
+            MetricDescriptor::Unit {
+                metricname_char: a, ..
+            },
+            MetricDescriptor::Unit {
+                metricname_char: b, ..
+            },
+        ) => a.as_ref() == b.as_ref(),
+        _ => false,
+    }
+}
+
+pub(crate) fn name<I>(metricfamily: &Metricfamily<I>) -> Option<&str>
+where
+    I: AsRef<str>,
+{
+    metricname(metricfamily).map(AsRef::as_ref)
+}
+
+fn metricname<I>(metricfamily: &Metricfamily<I>) -> Option<&I> {
+    match metricfamily.metric_descriptor.first() {
+        Some(metric_descriptor) => Some(metric_descriptor.metricname()),
+        None => metricfamily
+            .samples()
+            .next()
+            .map(|sample| &sample.metricname),
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,412 @@
+use super::{Comments, Event, ParseOptions};
+use crate::Spanned;
+use crate::error::{Kind, OpenMetricsError};
+use nom::error::Error;
+use nom::{Finish, Parser};
+use std::sync::{Arc, Mutex};
+
+#[rstest::rstest]
+#[case("# TYPE a counter\na_total 1\n# TYPE a gauge\na 2\n# EOF\n", Some(27))]
+#[case("# HELP a x\na 1\n# HELP a y\na 2\n# EOF\n", Some(15))]
+#[case(
+    "# UNIT a_s s\na_s 1\n# HELP a_s x\n# UNIT a_s s\na_s 2\n# EOF\n",
+    None
+)]
+fn test_federation_conflict(#[case] input: &str, #[case] offset: Option<usize>) {
+    let result = ParseOptions::federation()
+        .exposition::<_, OpenMetricsError<_>>(input)
+        .finish();
+    let error = result.err().map(|error| {
+        assert_eq!(error.kind, Kind::ConflictingDescriptor);
+        error.offset(&input)
+    });
+    assert_eq!(error, offset);
+}
+
+#[test]
+fn test_federation() {
+    let input = r#"# TYPE a counter
+a_total{instance="1"} 1
+# TYPE b gauge
+b{instance="1"} 2
+# TYPE a counter
+# HELP a Help.
+a_total{instance="2"} 3
+# TYPE b gauge
+b{instance="2"} 4
+# EOF
+"#;
+    let (_, exposition) = ParseOptions::federation()
+        .exposition::<_, Error<_>>(input)
+        .finish()
+        .unwrap();
+    let metricfamily = exposition
+        .metricset
+        .metricfamily
+        .iter()
+        .map(|metricfamily| {
+            (
+                metricfamily
+                    .metric_descriptor
+                    .iter()
+                    .map(|metric_descriptor| metric_descriptor.raw)
+                    .collect::<Vec<_>>(),
+                metricfamily
+                    .samples()
+                    .map(|sample| sample.raw)
+                    .collect::<Vec<_>>(),
+            )
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        metricfamily,
+        [
+            (
+                vec!["# TYPE a counter\n", "# HELP a Help.\n"],
+                vec!["a_total{instance=\"1\"} 1\n", "a_total{instance=\"2\"} 3\n"],
+            ),
+            (
+                vec!["# TYPE b gauge\n"],
+                vec!["b{instance=\"1\"} 2\n", "b{instance=\"2\"} 4\n"],
+            ),
+        ],
+    );
+
+    let (_, exposition) = ParseOptions::default()
+        .exposition::<_, Error<_>>(input)
+        .finish()
+        .unwrap();
+    assert_eq!(exposition.metricset.value.metricfamily.len(), 4);
+}
+
+#[rstest::rstest]
+#[case("# SCOPE a\n# TYPE b gauge\nb 1\n# EOF\n", Some(vec![("SCOPE", "a")]))]
+#[case("# TYPE b gauge\n# SCOPE a\nb 1\n# EOF\n", Some(vec![("SCOPE", "a")]))]
+#[case("# SCOPE\n# SCOPE a b\n# EOF\n", Some(vec![("SCOPE", ""), ("SCOPE", "a b")]))]
+#[case("# SCOPE A\n# EOF\n", None)]
+#[case("# VENDOR a\n# EOF\n", None)]
+fn test_extension(#[case] input: &str, #[case] expected: Option<Vec<(&str, &str)>>) {
+    let options =
+        ParseOptions::default().extension("SCOPE", |body| !body.contains(char::is_uppercase));
+    let extension =
+        options
+            .exposition::<_, Error<_>>(input)
+            .finish()
+            .ok()
+            .map(|(_, exposition)| {
+                exposition
+                    .metricset
+                    .value
+                    .extension
+                    .into_iter()
+                    .map(|extension| (extension.keyword, extension.body))
+                    .collect::<Vec<_>>()
+            });
+    assert_eq!(extension, expected);
+
+    // the strict grammar rejects any unknown keyword
+    assert!(
+        ParseOptions::default()
+            .exposition::<_, Error<_>>(input)
+            .finish()
+            .is_err()
+    );
+}
+
+#[rstest::rstest]
+#[case(
+    "# Generated by x.\n# TYPE a gauge\na 1\n#\n#EOF\nb 2\n# EOF\n",
+    Some(vec![" Generated by x.", "", "EOF"]),
+)]
+#[case("# TYPE a foo\n# EOF\n", None)]
+#[case("# TYPE a gauge\n# TYPE\n# EOF\n", None)]
+#[case("# SCOPE A\n# EOF\n", None)]
+#[case("# EOF\n# x\n", Some(Vec::new()))]
+fn test_comments(#[case] input: &str, #[case] expected: Option<Vec<&str>>) {
+    let options =
+        ParseOptions::default().extension("SCOPE", |body| !body.contains(char::is_uppercase));
+    let comment = options
+        .clone()
+        .comments(Comments::Keep)
+        .exposition::<_, Error<_>>(input)
+        .finish()
+        .ok()
+        .map(|(_, exposition)| {
+            exposition
+                .metricset
+                .value
+                .comment
+                .into_iter()
+                .map(|comment| comment.value.text)
+                .collect::<Vec<_>>()
+        });
+    assert_eq!(comment, expected);
+
+    let skipped = options
+        .clone()
+        .comments(Comments::Skip)
+        .exposition::<_, Error<_>>(input)
+        .finish();
+    assert_eq!(skipped.is_ok(), expected.is_some());
+    if let Ok((_, exposition)) = skipped {
+        assert!(exposition.metricset.comment.is_empty());
+    }
+    if expected.is_some_and(|comment| !comment.is_empty()) {
+        assert!(options.exposition::<_, Error<_>>(input).finish().is_err());
+    }
+}
+
+#[rstest::rstest]
+#[case(
+    "# TYPE a counter\n# x\na_total 1\n# EOF\n",
+    "# TYPE a counter\na_total 1\n# EOF\n"
+)]
+#[case(
+    "# TYPE a counter\n# x\n# HELP a y\n#\na_total 1\n# x\na_total 2\n# EOF\n",
+    "# TYPE a counter\n# HELP a y\na_total 1\na_total 2\n# EOF\n"
+)]
+#[case("a 1\n# x\nb 2\n# EOF\n", "a 1\nb 2\n# EOF\n")]
+fn test_comments_within_family(#[case] input: &str, #[case] uncommented: &str) {
+    let expected = crate::exposition::<_, Error<_>>(uncommented)
+        .finish()
+        .unwrap()
+        .1;
+    for comments in [Comments::Skip, Comments::Keep] {
+        let (_, exposition) = ParseOptions::default()
+            .comments(comments)
+            .exposition::<_, Error<_>>(input)
+            .finish()
+            .unwrap();
+        assert_eq!(
+            exposition.metricset.metricfamily.len(),
+            expected.metricset.metricfamily.len()
+        );
+        assert_eq!(exposition.to_string(), expected.to_string());
+        if comments == Comments::Keep {
+            for comment in &exposition.metricset.comment {
+                assert_eq!(&input[comment.range.clone()], comment.raw);
+            }
+        }
+    }
+}
+
+#[test]
+fn test_exposition_with() {
+    let input = r#"# TYPE a counter
+a_total 1
+# SCOPE x
+b 2
+# EOF
+"#;
+    let mut events = Vec::new();
+    let (_, exposition) = ParseOptions::default()
+        .extension("SCOPE", |_| true)
+        .exposition_with::<_, Error<_>, _>(input, |event| {
+            events.push(match event {
+                Event::MetricDescriptor(Spanned { raw, .. })
+                | Event::Sample(Spanned { raw, .. })
+                | Event::Extension(Spanned { raw, .. })
+                | Event::Comment(Spanned { raw, .. }) => *raw,
+                Event::Eof => "EOF",
+            })
+        })
+        .finish()
+        .unwrap();
+    assert_eq!(
+        events,
+        [
+            "# TYPE a counter\n",
+            "a_total 1\n",
+            "# SCOPE x\n",
+            "b 2\n",
+            "EOF"
+        ],
+    );
+    assert_eq!(
+        exposition,
+        ParseOptions::default()
+            .extension("SCOPE", |_| true)
+            .exposition::<_, Error<_>>(input)
+            .finish()
+            .unwrap()
+            .1,
+    );
+
+    let mut events = 0;
+    assert!(
+        ParseOptions::default()
+            .exposition_with::<_, Error<_>, _>("a 1\nb 2\nc\n# EOF\n", |_| events += 1)
+            .finish()
+            .is_err()
+    );
+    assert_eq!(events, 2);
+}
+
+#[rstest::rstest]
+#[case(
+    "# TYPE a gauge\na 1\n# TYPE b gauge\nb 2\n# EOF\n",
+    "b 2\n",
+    "# EOF\n"
+)]
+#[case(
+    "# TYPE a gauge\na 1\n# TYPE b gauge\nb 3\n# EOF\n",
+    "b 3\n",
+    "# EOF\n"
+)]
+#[case("b 2\nb 3\nc 4\n# EOF\n", "b 2\n", "b 3\nc 4\n# EOF\n")]
+fn test_abort_if(#[case] input: &str, #[case] last: &str, #[case] rest: &str) {
+    let mut events = Vec::new();
+    let (input, exposition) = ParseOptions::default()
+        .normalize_numbers(true)
+        .exposition_until::<_, Error<_>, _>(input, |event| match event {
+            Event::Sample(sample) => {
+                events.push(sample.raw);
+                assert!(sample.normalized_number.is_some());
+                sample.metricname == "b"
+            }
+            _ => false,
+        })
+        .finish()
+        .unwrap();
+    assert_eq!(input, rest);
+    assert_eq!(events.last(), Some(&last));
+    let metricfamily = exposition.metricset.value.metricfamily.last().unwrap();
+    assert_eq!(metricfamily.samples().last().unwrap().raw, last);
+}
+
+#[rstest::rstest]
+#[case("# TYPE a gauge\na 1\n", Some(""))]
+#[case("a 1\n# EOF\n", Some("# EOF\n"))]
+#[case("", Some(""))]
+#[case("a 1", None)]
+#[case("a 1\nb\n", None)]
+fn test_missing_eof(#[case] input: &str, #[case] eof: Option<&str>) {
+    let exposition = ParseOptions::default()
+        .missing_eof(true)
+        .exposition::<_, Error<_>>(input)
+        .finish()
+        .ok()
+        .map(|(_, exposition)| exposition);
+    assert_eq!(exposition.as_ref().map(|exposition| exposition.eof), eof);
+    if let Some(exposition) = exposition {
+        let diagnostics = crate::lint::missing_eof(&exposition);
+        assert_eq!(diagnostics.len(), usize::from(exposition.eof.is_empty()));
+        if let [diagnostic] = &diagnostics[..] {
+            assert_eq!(diagnostic.span, Some(input.len()..input.len()));
+        }
+    }
+}
+
+#[test]
+fn test_sample_filter() {
+    let input = r#"# TYPE a gauge
+a{job="x"} 1
+a{job="y\n"} 2
+b 3
+c{job="y\n"} 4
+# EOF
+"#;
+    let mut events = Vec::new();
+    let (_, exposition) = ParseOptions::default()
+        .sample_filter(|metricname, mut labels| {
+            metricname == "c" || labels.any(|(name, value)| name == "job" && value == "x")
+        })
+        .exposition_with::<_, Error<_>, _>(input, |event| {
+            if let Event::Sample(Spanned { raw, .. }) = event {
+                events.push(*raw);
+            }
+        })
+        .finish()
+        .unwrap();
+    assert_eq!(events, ["a{job=\"x\"} 1\n", "c{job=\"y\\n\"} 4\n"]);
+    let samples = exposition
+        .metricset
+        .metricfamily
+        .iter()
+        .flat_map(|metricfamily| metricfamily.samples())
+        .map(|sample| sample.raw)
+        .collect::<Vec<_>>();
+    assert_eq!(samples, events);
+
+    // dropped samples are still validated
+    assert!(
+        ParseOptions::default()
+            .sample_filter(|_, _| false)
+            .exposition::<_, Error<_>>("a 1\nb\n# EOF\n")
+            .finish()
+            .is_err()
+    );
+}
+
+#[rstest::rstest]
+#[case("a{x=\"1\"} 1 # {t=} 1\n# EOF\n")]
+#[case("a{x=\"1\",} 1\n# EOF\n")]
+#[case("a{x=\"1\"} 1 # {t=\"1\"}\n# EOF\n")]
+fn test_sample_filter_error(#[case] input: &str) {
+    let dropped = ParseOptions::default()
+        .sample_filter(|_, _| false)
+        .exposition::<_, OpenMetricsError<_>>(input)
+        .finish()
+        .unwrap_err();
+    let strict = crate::exposition::<_, OpenMetricsError<_>>
+        .parse(input)
+        .finish()
+        .unwrap_err();
+    assert_eq!(dropped, strict);
+}
+
+#[test]
+fn test_sample_filter_unescape() {
+    let input = r#"a{x="a\"b\\c\nd\q",y=""} 1 # {t="1"} 1
+# EOF
+"#;
+    let labels = Arc::new(Mutex::new(Vec::new()));
+    let recorded = labels.clone();
+    let (_, exposition) = ParseOptions::default()
+        .sample_filter(move |_, sample_labels| {
+            let sample_labels =
+                sample_labels.map(|(name, value)| (name.to_owned(), value.into_owned()));
+            recorded.lock().unwrap().extend(sample_labels);
+            false
+        })
+        .exposition::<_, Error<_>>(input)
+        .finish()
+        .unwrap();
+    assert!(exposition.metricset.metricfamily.is_empty());
+    assert_eq!(
+        *labels.lock().unwrap(),
+        [
+            ("x".to_owned(), "a\"b\\c\nd\\q".to_owned()),
+            ("y".to_owned(), String::new()),
+        ],
+    );
+}
+
+#[test]
+fn test_normalize_numbers() {
+    let input = "a 0042 # {} 1.0E3\nb 1\n# EOF\n";
+    let (_, exposition) = ParseOptions::default()
+        .normalize_numbers(true)
+        .exposition::<_, Error<_>>(input)
+        .finish()
+        .unwrap();
+    let metricfamily = &exposition.metricset.value.metricfamily[0];
+    let samples = metricfamily.samples().collect::<Vec<_>>();
+    let sample = samples[0];
+    assert_eq!(sample.number, "0042");
+    assert_eq!(sample.normalized_number.as_deref(), Some("42"));
+    let exemplar = sample.exemplar.as_ref().unwrap();
+    assert_eq!(exemplar.normalized_number.as_deref(), Some("1000"));
+    assert_eq!(samples[1].normalized_number.as_deref(), Some("1"));
+
+    let (_, exposition) = ParseOptions::default()
+        .exposition::<_, Error<_>>(input)
+        .finish()
+        .unwrap();
+    let metricfamily = &exposition.metricset.value.metricfamily[0];
+    assert!(
+        metricfamily
+            .samples()
+            .all(|sample| sample.normalized_number.is_none())
+    );
+}
@@ -0,0 +1,333 @@
+use crate::diagnostic::Diagnostic;
+use crate::lint::{self, LabelPolicy};
+use crate::options::{Comments, ParseOptions};
+use crate::transform::{self, LabelConflict};
+use crate::{Exposition, Metricset, validate};
+use nom::error::Error as NomError;
+use nom::{Finish, Parser};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::sync::Arc;
+
+// A metric-processing pipeline as configuration: the input is parsed, validated, transformed
+// step by step and written in the output format. Any serde format can describe it, e.g. YAML
+// through `serde_yaml::from_str`:
+//
+//     parse: { merge_repeated_families: true }
+//     validations: [{ rule: validate }, { rule: label_value_length, max: 128 }]
+//     transforms: [{ transform: prefix, prefix: "node_" }]
+//     output: { format: openmetrics }
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Pipeline {
+    pub parse: Parse,
+    pub validations: Vec<Validation>,
+    pub transforms: Vec<Stage>,
+    pub output: Output,
+}
+
+// See `options::ParseOptions`.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Parse {
+    pub merge_repeated_families: bool,
+    pub normalize_numbers: bool,
+    pub missing_eof: bool,
+    pub comments: Comments,
+}
+
+// A check of `validate` or `lint`, run on the parsed input.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "rule", rename_all = "snake_case", deny_unknown_fields)]
+pub enum Validation {
+    // every check of `validate::validate`
+    Validate,
+    MetadataOrder,
+    FamilyPrefix,
+    NameClash,
+    ExemplarLength,
+    CounterValue,
+    ExemplarPlacement,
+    LabelPolicy(LabelPolicy),
+    ReservedSuffix,
+    HelpUnitConsistency,
+    LabelValueLength { max: usize },
+    JobInstance,
+    InterleavedFamilies,
+    MissingEof,
+}
+
+// A transform of `transform`, applied to the output of the previous one.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "transform", rename_all = "snake_case", deny_unknown_fields)]
+pub enum Stage {
+    Prefix {
+        prefix: String,
+    },
+    // by `transform::CONVERSIONS`
+    ConvertUnits,
+    CounterToGauge {
+        name: String,
+    },
+    HistogramToCounters {
+        name: String,
+    },
+    SummaryToGauge {
+        name: String,
+        quantile: f64,
+    },
+    FillBuckets {
+        name: String,
+        schedule: Vec<f64>,
+    },
+    Rebucket {
+        name: String,
+        bounds: Vec<f64>,
+    },
+    TruncateLabelValues {
+        max: usize,
+    },
+    // labels are set in the order of their names
+    SetTargetLabels {
+        labels: BTreeMap<String, String>,
+        #[serde(default)]
+        conflict: LabelConflict,
+    },
+    // a `Transform` of the `Registry`, created from `config`
+    Plugin {
+        name: String,
+        #[serde(default)]
+        config: serde_json::Value,
+    },
+}
+
+// A custom stage, e.g. of a downstream crate, plugged into pipelines by `Registry::register`.
+pub trait Transform: Send + Sync {
+    fn apply(&self, metricset: &Metricset<&str>) -> Result<Transformed, transform::Error>;
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Transformed {
+    pub metricset: Metricset<String>,
+    // added to `Run::diagnostics`
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+type Factory =
+    Arc<dyn Fn(serde_json::Value) -> serde_json::Result<Box<dyn Transform>> + Send + Sync>;
+
+// The transforms `Stage::Plugin` refers to, by name.
+#[derive(Clone, Default)]
+pub struct Registry(HashMap<String, Factory>);
+
+impl fmt::Debug for Registry {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_set().entries(self.0.keys()).finish()
+    }
+}
+
+impl Registry {
+    // Registers `T` as `name`, created by deserializing the `config` of the stage.
+    pub fn register<T>(mut self, name: impl Into<String>) -> Self
+    where
+        T: Transform + DeserializeOwned + 'static,
+    {
+        self.0.insert(
+            name.into(),
+            Arc::new(|config| Ok(Box::new(serde_json::from_value::<T>(config)?))),
+        );
+        self
+    }
+
+    fn create(&self, name: &str, config: &serde_json::Value) -> Result<Box<dyn Transform>, Error> {
+        let factory = self
+            .0
+            .get(name)
+            .ok_or_else(|| Error::UnknownPlugin(name.to_owned()))?;
+        factory(config.clone()).map_err(Error::Config)
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "format", rename_all = "snake_case", deny_unknown_fields)]
+pub enum Output {
+    // the transformed exposition
+    #[default]
+    Openmetrics,
+    // the diagnostics, see `diagnostic::to_json_lines`
+    JsonLines,
+    // the diagnostics, see `sarif::to_sarif`
+    Sarif {
+        uri: String,
+    },
+}
+
+// The result of `Pipeline::run`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Run {
+    pub output: String,
+    // of the validations, in their order
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+#[derive(Debug)]
+pub enum Error {
+    // the input, or the output of a transform, does not parse
+    Parse(Diagnostic),
+    Transform(transform::Error),
+    // a `Stage::Plugin` that is not registered
+    UnknownPlugin(String),
+    // the config of a `Stage::Plugin` that does not deserialize
+    Config(serde_json::Error),
+    Output(serde_json::Error),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Parse(diagnostic) => write!(f, "{}", diagnostic.message),
+            Self::Transform(e) => write!(f, "{e}"),
+            Self::UnknownPlugin(name) => write!(f, "unknown transform `{name}`"),
+            Self::Config(e) | Self::Output(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl Pipeline {
+    pub fn from_json(json: &str) -> serde_json::Result<Self> {
+        serde_json::from_str(json)
+    }
+
+    // Runs without plugins, see `run_with`.
+    pub fn run(&self, input: &str) -> Result<Run, Error> {
+        self.run_with(input, &Registry::default())
+    }
+
+    pub fn run_with(&self, input: &str, registry: &Registry) -> Result<Run, Error> {
+        let (_, exposition) = self
+            .parse
+            .options()
+            .exposition::<_, NomError<_>>(input)
+            .finish()
+            .map_err(|e| Error::Parse(Diagnostic::from_error(&input, &e)))?;
+        let mut diagnostics = self
+            .validations
+            .iter()
+            .flat_map(|validation| validation.run(&exposition))
+            .collect::<Vec<_>>();
+
+        let mut text = None::<String>;
+        for stage in &self.transforms {
+            let output = match &text {
+                Some(text) => stage.apply(&parse(text)?, registry, &mut diagnostics)?,
+                None => stage.apply(&exposition, registry, &mut diagnostics)?,
+            };
+            text = Some(output);
+        }
+
+        let output = match &self.output {
+            Output::Openmetrics => text.unwrap_or_else(|| exposition.to_string()),
+            Output::JsonLines => {
+                crate::diagnostic::to_json_lines(&diagnostics).map_err(Error::Output)?
+            }
+            Output::Sarif { uri } => {
+                crate::sarif::to_sarif(&diagnostics, uri, input).map_err(Error::Output)?
+            }
+        };
+        Ok(Run {
+            output,
+            diagnostics,
+        })
+    }
+}
+
+impl Parse {
+    pub fn options(&self) -> ParseOptions {
+        ParseOptions::default()
+            .merge_repeated_families(self.merge_repeated_families)
+            .normalize_numbers(self.normalize_numbers)
+            .missing_eof(self.missing_eof)
+            .comments(self.comments)
+    }
+}
+
+impl Validation {
+    pub fn run(&self, exposition: &Exposition<&str>) -> Vec<Diagnostic> {
+        match self {
+            Self::Validate => validate::validate(exposition),
+            Self::MetadataOrder => validate::metadata_order(exposition),
+            Self::FamilyPrefix => validate::family_prefix(exposition),
+            Self::NameClash => validate::name_clash(exposition),
+            Self::ExemplarLength => validate::exemplar_length(exposition),
+            Self::CounterValue => validate::counter_value(exposition),
+            Self::ExemplarPlacement => validate::exemplar_placement(exposition),
+            Self::LabelPolicy(policy) => lint::label_policy(exposition, policy),
+            Self::ReservedSuffix => lint::reserved_suffix(exposition),
+            Self::HelpUnitConsistency => lint::help_unit_consistency(exposition),
+            Self::LabelValueLength { max } => lint::label_value_length(exposition, *max),
+            Self::JobInstance => lint::job_instance(exposition),
+            Self::InterleavedFamilies => lint::interleaved_families(exposition),
+            Self::MissingEof => lint::missing_eof(exposition),
+        }
+    }
+}
+
+impl Stage {
+    // Adds the diagnostics of a plugin to `diagnostics`.
+    pub fn apply(
+        &self,
+        exposition: &Exposition<&str>,
+        registry: &Registry,
+        diagnostics: &mut Vec<Diagnostic>,
+    ) -> Result<String, Error> {
+        let output = match self {
+            Self::Prefix { prefix } => transform::prefix(exposition, prefix),
+            Self::ConvertUnits => transform::convert_units(exposition, transform::CONVERSIONS),
+            Self::CounterToGauge { name } => transform::counter_to_gauge(exposition, name),
+            Self::HistogramToCounters { name } => {
+                transform::histogram_to_counters(exposition, name)
+            }
+            Self::SummaryToGauge { name, quantile } => {
+                transform::summary_to_gauge(exposition, name, *quantile)
+            }
+            Self::FillBuckets { name, schedule } => {
+                transform::fill_buckets(exposition, name, schedule)
+            }
+            Self::Rebucket { name, bounds } => transform::rebucket(exposition, name, bounds),
+            Self::TruncateLabelValues { max } => transform::truncate_label_values(exposition, *max),
+            Self::SetTargetLabels { labels, conflict } => {
+                let labels = labels
+                    .iter()
+                    .map(|(name, value)| (name.as_str(), value.as_str()))
+                    .collect::<Vec<_>>();
+                transform::set_target_labels(exposition, &labels, *conflict)
+            }
+            Self::Plugin { name, config } => {
+                let transformed = registry
+                    .create(name, config)?
+                    .apply(&exposition.metricset)
+                    .map_err(Error::Transform)?;
+                let text = format!("{}# EOF\n", transformed.metricset);
+                parse(&text)?;
+                diagnostics.extend(transformed.diagnostics);
+                return Ok(text);
+            }
+        };
+        output.map_err(Error::Transform)
+    }
+}
+
+fn parse(text: &str) -> Result<Exposition<&str>, Error> {
+    let (_, exposition) = crate::exposition::<_, NomError<_>>
+        .parse(text)
+        .finish()
+        .map_err(|e| Error::Parse(Diagnostic::from_error(&text, &e)))?;
+    Ok(exposition)
+}
+
+#[cfg(test)]
+mod tests;
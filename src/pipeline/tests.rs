@@ -0,0 +1,173 @@
+use super::{Error, Output, Parse, Pipeline, Registry, Stage, Transform, Transformed, Validation};
+use crate::Metricset;
+use crate::diagnostic::{Diagnostic, Severity};
+use crate::lint::LabelPolicy;
+use crate::options::Comments;
+use crate::transform::{self, LabelConflict};
+
+const INPUT: &str = r#"# TYPE a counter
+a_total{path="/0123456789"} 1
+# TYPE b_milliseconds gauge
+# UNIT b_milliseconds milliseconds
+b_milliseconds 9
+# EOF
+"#;
+
+#[test]
+fn test_from_json() {
+    let pipeline = Pipeline::from_json(
+        r#"{
+            "parse": {"merge_repeated_families": true, "comments": "skip"},
+            "validations": [
+                {"rule": "validate"},
+                {"rule": "label_value_length", "max": 8},
+                {"rule": "label_policy", "allow": {}, "deny": ["path"]}
+            ],
+            "transforms": [
+                {"transform": "convert_units"},
+                {"transform": "set_target_labels", "labels": {"job": "j"}}
+            ],
+            "output": {"format": "sarif", "uri": "metrics.txt"}
+        }"#,
+    )
+    .unwrap();
+    assert_eq!(
+        pipeline,
+        Pipeline {
+            parse: Parse {
+                merge_repeated_families: true,
+                comments: Comments::Skip,
+                ..Parse::default()
+            },
+            validations: vec![
+                Validation::Validate,
+                Validation::LabelValueLength { max: 8 },
+                Validation::LabelPolicy(LabelPolicy {
+                    allow: Default::default(),
+                    deny: ["path".to_owned()].into(),
+                }),
+            ],
+            transforms: vec![
+                Stage::ConvertUnits,
+                Stage::SetTargetLabels {
+                    labels: [("job".to_owned(), "j".to_owned())].into(),
+                    conflict: LabelConflict::Export,
+                },
+            ],
+            output: Output::Sarif {
+                uri: "metrics.txt".to_owned(),
+            },
+        }
+    );
+    assert_eq!(Pipeline::from_json("{}").unwrap(), Pipeline::default());
+    assert!(Pipeline::from_json(r#"{"transforms": [{"transform": "unknown"}]}"#).is_err());
+    assert!(Pipeline::from_json(r#"{"parse": {"strict": true}}"#).is_err());
+}
+
+#[test]
+fn test_run() {
+    let pipeline = Pipeline {
+        validations: vec![Validation::LabelValueLength { max: 8 }],
+        transforms: vec![
+            Stage::ConvertUnits,
+            Stage::Prefix {
+                prefix: "node_".to_owned(),
+            },
+        ],
+        ..Pipeline::default()
+    };
+    let run = pipeline.run(INPUT).unwrap();
+    assert_eq!(
+        run.output,
+        r#"# TYPE node_a counter
+node_a_total{path="/0123456789"} 1
+# TYPE node_b_seconds gauge
+# UNIT node_b_seconds seconds
+node_b_seconds 0.009
+# EOF
+"#
+    );
+    assert_eq!(run.diagnostics.len(), 1);
+
+    let pipeline = Pipeline {
+        output: Output::JsonLines,
+        ..pipeline
+    };
+    let run = pipeline.run(INPUT).unwrap();
+    assert_eq!(run.output.lines().count(), 1);
+
+    assert_eq!(Pipeline::default().run(INPUT).unwrap().output, INPUT);
+}
+
+#[rstest::rstest]
+#[case("a 1\n", Stage::ConvertUnits)]
+#[case(INPUT, Stage::CounterToGauge { name: "c".to_owned() })]
+fn test_run_error(#[case] input: &str, #[case] stage: Stage) {
+    let pipeline = Pipeline {
+        transforms: vec![stage],
+        ..Pipeline::default()
+    };
+    let error = pipeline.run(input).unwrap_err();
+    match (input, error) {
+        ("a 1\n", Error::Parse(_)) | (INPUT, Error::Transform(_)) => {}
+        (_, error) => panic!("{error}"),
+    }
+}
+
+// Drops the family `name`.
+#[derive(serde::Deserialize)]
+struct DropFamily {
+    name: String,
+}
+
+impl Transform for DropFamily {
+    fn apply(&self, metricset: &Metricset<&str>) -> Result<Transformed, transform::Error> {
+        let mut metricset = metricset.clone().into_owned();
+        metricset
+            .metricfamily
+            .retain(|metricfamily| crate::options::name(metricfamily) != Some(&self.name));
+        Ok(Transformed {
+            metricset,
+            diagnostics: vec![Diagnostic {
+                rule: "drop",
+                severity: Severity::Info,
+                message: format!("dropped `{}`", self.name),
+                span: None,
+                family: Some(self.name.clone()),
+                series: None,
+            }],
+        })
+    }
+}
+
+#[test]
+fn test_plugin() {
+    let pipeline = Pipeline::from_json(
+        r#"{"transforms": [
+            {"transform": "plugin", "name": "drop", "config": {"name": "a"}},
+            {"transform": "prefix", "prefix": "node_"}
+        ]}"#,
+    )
+    .unwrap();
+    let registry = Registry::default().register::<DropFamily>("drop");
+    let run = pipeline.run_with(INPUT, &registry).unwrap();
+    assert_eq!(
+        run.output,
+        r#"# TYPE node_b_milliseconds gauge
+# UNIT node_b_milliseconds milliseconds
+node_b_milliseconds 9
+# EOF
+"#
+    );
+    assert_eq!(run.diagnostics.len(), 1);
+    assert_eq!(format!("{registry:?}"), r#"{"drop"}"#);
+
+    assert!(matches!(pipeline.run(INPUT), Err(Error::UnknownPlugin(_))));
+    let pipeline =
+        Pipeline::from_json(r#"{"transforms": [{"transform": "plugin", "name": "drop"}]}"#)
+            .unwrap();
+    assert!(matches!(
+        pipeline.run_with(INPUT, &registry),
+        Err(Error::Config(_))
+    ));
+}
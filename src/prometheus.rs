@@ -0,0 +1,287 @@
+use crate::{COMMA, EOF, HASH, HELP, LF, SP, TYPE};
+use crate::{
+    Exposition, Labels, Metric, MetricDescriptor, MetricType, Metricfamily, Metricset, Sample,
+    Timestamp,
+};
+use nom::branch::alt;
+use nom::bytes::complete::{tag, take_while, take_while1};
+use nom::character::complete::{char, digit1};
+use nom::combinator::{eof, not, opt, recognize};
+use nom::error::{ContextError, ParseError, context};
+use nom::multi::{many0, many1, separated_list0};
+use nom::sequence::{preceded, terminated};
+use nom::{AsChar, Compare, IResult, Input, Offset, Parser};
+
+// https://prometheus.io/docs/instrumenting/exposition_formats/#text-based-format
+//
+// The Prometheus text format 0.0.4, parsed into the nodes of the OpenMetrics grammar:
+// - `# EOF` is optional, `Exposition::eof` is empty without it;
+// - `untyped` is `MetricType::Unknown`, the `metric_type` node keeping `untyped` as its raw input;
+// - `#` lines other than TYPE and HELP, and blank lines, are comments, part of the raw input of
+//   the node before them (or of the metricset at its start) but of no node of their own;
+// - tokens of a sample are separated by spaces or tabs, and a label set may end with a comma;
+// - a timestamp is an integer in milliseconds, see `timestamp`. Exemplars and UNIT are not part
+//   of the format.
+//
+// Counters are exposed without the `_total` suffix, so the families do not convert to
+// `model::MetricFamily` as they are.
+pub fn exposition<I, E>(input: I) -> IResult<I, Exposition<I>, E>
+where
+    I: Compare<&'static str> + Input + Offset,
+    I::Item: AsChar,
+    E: ContextError<I> + ParseError<I>,
+{
+    context(
+        "exposition",
+        (
+            crate::spanned(input.clone(), metricset),
+            alt((
+                recognize((char(HASH), char(SP), tag(EOF), opt(char(LF)))),
+                eof,
+            )),
+        ),
+    )
+    .map(|(metricset, eof)| Exposition { metricset, eof })
+    .parse(input)
+}
+
+pub fn metricset<I, E>(input: I) -> IResult<I, Metricset<I>, E>
+where
+    I: Compare<&'static str> + Input + Offset,
+    I::Item: AsChar,
+    E: ContextError<I> + ParseError<I>,
+{
+    context(
+        "metricset",
+        preceded(comments, many0(crate::spanned(input.clone(), metricfamily))),
+    )
+    .map(|metricfamily| Metricset {
+        metricfamily,
+        extension: Vec::new(),
+        comment: Vec::new(),
+        merged: Vec::new(),
+    })
+    .parse(input)
+}
+
+pub fn metricfamily<I, E>(input: I) -> IResult<I, Metricfamily<I>, E>
+where
+    I: Compare<&'static str> + Input + Offset,
+    I::Item: AsChar,
+    E: ContextError<I> + ParseError<I>,
+{
+    let metric_descriptor =
+        || terminated(crate::spanned(input.clone(), metric_descriptor), comments);
+    context(
+        "metricfamily",
+        alt((
+            (
+                many1(metric_descriptor()),
+                many0(crate::spanned(input.clone(), metric)),
+            ),
+            (
+                many0(metric_descriptor()),
+                many1(crate::spanned(input.clone(), metric)),
+            ),
+        )),
+    )
+    .map(|(metric_descriptor, metric)| Metricfamily {
+        metric_descriptor,
+        metric,
+    })
+    .parse(input)
+}
+
+pub fn metric_descriptor<I, E>(input: I) -> IResult<I, MetricDescriptor<I>, E>
+where
+    I: Compare<&'static str> + Input + Offset,
+    I::Item: AsChar,
+    E: ContextError<I> + ParseError<I>,
+{
+    context(
+        "metric_descriptor",
+        alt((
+            (
+                char(HASH),
+                char(SP),
+                tag(TYPE),
+                char(SP),
+                crate::metricname,
+                blank1,
+                crate::spanned(input.clone(), metric_type),
+                blank0,
+                char(LF),
+            )
+                .map(|(_, _, _, _, metricname, _, metric_type, _, _)| {
+                    MetricDescriptor::Type {
+                        metricname,
+                        metric_type,
+                    }
+                }),
+            (
+                char(HASH),
+                char(SP),
+                tag(HELP),
+                char(SP),
+                crate::metricname,
+                opt(char(SP)),
+                crate::spanned(input.clone(), crate::help_escaped_string),
+                char(LF),
+            )
+                .map(|(_, _, _, _, metricname, _, escaped_string, _)| {
+                    MetricDescriptor::Help {
+                        metricname,
+                        escaped_string,
+                    }
+                }),
+        )),
+    )
+    .parse(input)
+}
+
+// `crate::metric_type` or `untyped`.
+pub fn metric_type<I, E>(input: I) -> IResult<I, MetricType, E>
+where
+    I: Compare<&'static str> + Input + Offset,
+    I::Item: AsChar,
+    E: ContextError<I> + ParseError<I>,
+{
+    alt((
+        tag(UNTYPED).map(|_| MetricType::Unknown),
+        crate::metric_type,
+    ))
+    .parse(input)
+}
+
+pub fn metric<I, E>(input: I) -> IResult<I, Metric<I>, E>
+where
+    I: Compare<&'static str> + Input + Offset,
+    I::Item: AsChar,
+    E: ContextError<I> + ParseError<I>,
+{
+    context(
+        "metric",
+        many1(terminated(crate::spanned(input.clone(), sample), comments)),
+    )
+    .map(|sample| Metric { sample })
+    .parse(input)
+}
+
+pub fn sample<I, E>(input: I) -> IResult<I, Sample<I>, E>
+where
+    I: Compare<&'static str> + Input + Offset,
+    I::Item: AsChar,
+    E: ContextError<I> + ParseError<I>,
+{
+    context(
+        "sample",
+        (
+            crate::metricname,
+            opt(crate::spanned(input.clone(), labels)),
+            blank1,
+            crate::number,
+            opt(preceded(blank1, timestamp_millis)),
+            blank0,
+            char(LF),
+        ),
+    )
+    .map(|(metricname, labels, _, number, timestamp, _, _)| Sample {
+        metricname,
+        labels,
+        number,
+        timestamp,
+        exemplar: None,
+        normalized_number: None,
+    })
+    .parse(input)
+}
+
+// `crate::labels`, with an optional comma before `}`.
+pub fn labels<I, E>(input: I) -> IResult<I, Labels<I>, E>
+where
+    I: Input + Offset,
+    I::Item: AsChar,
+    E: ContextError<I> + ParseError<I>,
+{
+    context(
+        "labels",
+        (
+            char('{'),
+            separated_list0(char(COMMA), crate::spanned(input.clone(), crate::label)),
+            opt(char(COMMA)),
+            char('}'),
+        ),
+    )
+    .map(|(_, label, _, _)| Labels { label })
+    .parse(input)
+}
+
+fn timestamp_millis<I, E>(input: I) -> IResult<I, I, E>
+where
+    I: Input + Offset,
+    I::Item: AsChar,
+    E: ContextError<I> + ParseError<I>,
+{
+    context("timestamp", recognize((opt(char('-')), digit1))).parse(input)
+}
+
+// The value of a timestamp in milliseconds, as parsed by `sample`.
+pub fn timestamp(s: &str) -> Option<Timestamp> {
+    let millis = s.parse::<i64>().ok()?;
+    Some(Timestamp {
+        seconds: millis.div_euclid(1000),
+        nanos: millis.rem_euclid(1000) as u32 * 1_000_000,
+    })
+}
+
+// Comment and blank lines.
+fn comments<I, E>(input: I) -> IResult<I, (), E>
+where
+    I: Compare<&'static str> + Input + Offset,
+    I::Item: AsChar,
+    E: ContextError<I> + ParseError<I>,
+{
+    many0(alt((
+        (
+            not(alt((
+                tag("# TYPE "),
+                tag("# HELP "),
+                recognize((tag("# EOF"), alt((recognize(char(LF)), eof)))),
+            ))),
+            char(HASH),
+            take_while(|c: I::Item| c.as_char() != LF),
+            char(LF),
+        )
+            .map(|_| ()),
+        (blank0, char(LF)).map(|_| ()),
+    )))
+    .map(|_| ())
+    .parse(input)
+}
+
+fn blank0<I, E>(input: I) -> IResult<I, I, E>
+where
+    I: Input,
+    I::Item: AsChar,
+    E: ParseError<I>,
+{
+    take_while(|c: I::Item| is_blank(c.as_char())).parse(input)
+}
+
+fn blank1<I, E>(input: I) -> IResult<I, I, E>
+where
+    I: Input,
+    I::Item: AsChar,
+    E: ParseError<I>,
+{
+    take_while1(|c: I::Item| is_blank(c.as_char())).parse(input)
+}
+
+fn is_blank(c: char) -> bool {
+    c == SP || c == '\t'
+}
+
+const UNTYPED: &str = "untyped";
+
+#[cfg(test)]
+mod tests;
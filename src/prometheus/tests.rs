@@ -0,0 +1,99 @@
+use crate::{MetricDescriptor, MetricType, Timestamp};
+use nom::error::Error;
+use nom::{Finish, Parser};
+
+#[test]
+fn test_exposition() {
+    let input = "# A comment.
+# HELP http_requests The total number of HTTP requests.
+# TYPE http_requests counter
+http_requests{method=\"post\",code=\"200\",} 1027 1395066363000
+http_requests{method=\"post\",code=\"400\"}    3 1395066363000
+
+# Another comment.
+# TYPE a untyped
+a\t-Inf
+b 1
+";
+    let (rest, exposition) = super::exposition::<_, Error<_>>
+        .parse(input)
+        .finish()
+        .unwrap();
+    assert_eq!(rest, "");
+    assert_eq!(exposition.eof, "");
+    assert_eq!(exposition.reconstruct(), input);
+
+    let families = exposition
+        .metricset
+        .metricfamily
+        .iter()
+        .map(|metricfamily| {
+            let descriptors = metricfamily
+                .metric_descriptor
+                .iter()
+                .map(|metric_descriptor| match &metric_descriptor.value {
+                    MetricDescriptor::Type { metric_type, .. } => metric_type.raw,
+                    MetricDescriptor::Help { escaped_string, .. } => escaped_string.raw,
+                    MetricDescriptor::Unit { .. } => unreachable!(),
+                })
+                .collect::<Vec<_>>();
+            let samples = metricfamily
+                .samples()
+                .map(|sample| (sample.metricname, sample.number, sample.timestamp))
+                .collect::<Vec<_>>();
+            (metricfamily.metric_type(), descriptors, samples)
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        families,
+        [
+            (
+                MetricType::Counter,
+                vec!["The total number of HTTP requests.", "counter"],
+                vec![
+                    ("http_requests", "1027", Some("1395066363000")),
+                    ("http_requests", "3", Some("1395066363000")),
+                ],
+            ),
+            (
+                MetricType::Unknown,
+                vec!["untyped"],
+                vec![("a", "-Inf", None), ("b", "1", None)],
+            ),
+        ],
+    );
+    let labels = &exposition.metricset.metricfamily[0].metric[0].sample[0].labels;
+    assert_eq!(labels.as_ref().unwrap().label.len(), 2);
+}
+
+#[test]
+fn test_exposition_eof() {
+    let input = "a 1\n# EOF\n";
+    let (rest, exposition) = super::exposition::<_, Error<_>>
+        .parse(input)
+        .finish()
+        .unwrap();
+    assert_eq!((rest, exposition.eof), ("", "# EOF\n"));
+}
+
+#[rstest::rstest]
+#[case("# TYPE a foo\n")]
+#[case("a 1 2.5\n")]
+#[case("a 1 # {} 1\n")]
+#[case("a 1")]
+fn test_exposition_error(#[case] input: &str) {
+    assert!(
+        super::exposition::<_, Error<_>>
+            .parse(input)
+            .finish()
+            .is_err()
+    );
+}
+
+#[rstest::rstest]
+#[case("1395066363000", Timestamp { seconds: 1395066363, nanos: 0 })]
+#[case("1500", Timestamp { seconds: 1, nanos: 500_000_000 })]
+#[case("-1500", Timestamp { seconds: -2, nanos: 500_000_000 })]
+fn test_timestamp(#[case] s: &str, #[case] expected: Timestamp) {
+    assert_eq!(super::timestamp(s), Some(expected));
+}
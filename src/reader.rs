@@ -0,0 +1,251 @@
+use crate::cancel::{CancellationToken, Cancelled};
+use crate::clock::{Clock, SystemClock};
+use crate::diagnostic::{Diagnostic, Severity};
+use crate::{Metricfamily, Shift, Spanned};
+use nom::combinator::all_consuming;
+use nom::{Finish, Parser};
+use std::fmt;
+use std::io::{self, BufRead};
+use std::time::{Duration, Instant};
+
+#[derive(Debug)]
+pub enum Error {
+    Io(io::Error),
+    // the span refers to the whole input read so far
+    Invalid(Diagnostic),
+    // see `Families::cancel_on`
+    Cancelled,
+    // see `Families::timeout`; the span is empty, where the reading stopped
+    Timeout(Diagnostic),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "{e}"),
+            Self::Invalid(diagnostic) => write!(f, "{}", diagnostic.message),
+            Self::Cancelled => write!(f, "{Cancelled}"),
+            Self::Timeout(diagnostic) => write!(f, "{}", diagnostic.message),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+// The families of the exposition read from `reader`, each with its span in the input. Lines are
+// buffered until their family ends, i.e. when a descriptor follows a sample or at `# EOF`, and
+// the family is then parsed by `crate::metricfamily`. Ends after `# EOF` without reading further,
+// or after the first error; a missing `# EOF` is an `io::ErrorKind::UnexpectedEof`.
+pub fn parse_reader<R>(reader: R) -> Families<R>
+where
+    R: BufRead,
+{
+    Families {
+        reader,
+        lines: Lines::default(),
+        line: String::new(),
+        cancellation: None,
+        deadline: None,
+    }
+}
+
+// `parse_reader` for a tokio reader. A read is neither cancelled nor timed out by the stream;
+// wrap it in `tokio::time::timeout` for that.
+#[cfg(feature = "tokio")]
+pub fn parse_async<R>(
+    reader: R,
+) -> impl futures_util::Stream<Item = Result<Spanned<String, Metricfamily<String>>, Error>>
+where
+    R: tokio::io::AsyncBufRead + Unpin,
+{
+    use tokio::io::AsyncBufReadExt;
+
+    let state = (reader, Lines::default(), String::new());
+    futures_util::stream::unfold(state, |(mut reader, mut lines, mut line)| async move {
+        while !lines.done {
+            line.clear();
+            let read = reader.read_line(&mut line).await;
+            if let Some(family) = lines.next(read, &line) {
+                return Some((family, (reader, lines, line)));
+            }
+        }
+        None
+    })
+}
+
+pub struct Families<R> {
+    reader: R,
+    lines: Lines,
+    line: String,
+    cancellation: Option<CancellationToken>,
+    deadline: Option<Deadline>,
+}
+
+// The lines of the family being read.
+#[derive(Default)]
+struct Lines {
+    // of `buffer` in the input
+    offset: usize,
+    buffer: String,
+    // whether `buffer` has a sample line
+    samples: bool,
+    done: bool,
+}
+
+struct Deadline {
+    at: Instant,
+    timeout: Duration,
+    clock: Box<dyn Clock + Send>,
+}
+
+impl<R> Families<R> {
+    // Ends with `Error::Cancelled` before reading the next line once `token` is cancelled. A
+    // blocking read is not interrupted; a reader with a timeout returns in time.
+    pub fn cancel_on(mut self, token: CancellationToken) -> Self {
+        self.cancellation = Some(token);
+        self
+    }
+
+    // Ends with `Error::Timeout` before reading the next line once `timeout` has passed since
+    // this call, like `cancel_on`. The families read until then are complete; the lines of the
+    // family being read are dropped.
+    pub fn timeout(self, timeout: Duration) -> Self {
+        self.timeout_with(timeout, SystemClock)
+    }
+
+    pub fn timeout_with<C>(mut self, timeout: Duration, clock: C) -> Self
+    where
+        C: Clock + Send + 'static,
+    {
+        self.deadline = Some(Deadline {
+            at: clock.instant() + timeout,
+            timeout,
+            clock: Box::new(clock),
+        });
+        self
+    }
+
+    fn timed_out(&self) -> Option<Error> {
+        let deadline = self.deadline.as_ref()?;
+        if deadline.clock.instant() < deadline.at {
+            return None;
+        }
+        let offset = self.lines.offset + self.lines.buffer.len();
+        Some(Error::Timeout(Diagnostic {
+            rule: crate::scrape::SCRAPE_TIMEOUT,
+            severity: Severity::Error,
+            message: format!("the scrape timed out after {:?}", deadline.timeout),
+            span: Some(offset..offset),
+            family: None,
+            series: None,
+        }))
+    }
+}
+
+impl Lines {
+    // Takes the result of reading the next line into `line`, returning the family it ends or the
+    // error, if any.
+    fn next(
+        &mut self,
+        read: io::Result<usize>,
+        line: &str,
+    ) -> Option<Result<Spanned<String, Metricfamily<String>>, Error>> {
+        match read {
+            Ok(0) => {
+                self.done = true;
+                return Some(Err(Error::Io(io::Error::new(
+                    io::ErrorKind::UnexpectedEof,
+                    "missing `# EOF`",
+                ))));
+            }
+            Ok(_) => {}
+            Err(e) => {
+                self.done = true;
+                return Some(Err(e.into()));
+            }
+        }
+        if line.trim_end_matches('\n') == "# EOF" {
+            self.done = true;
+            if self.buffer.is_empty() {
+                return None;
+            }
+            return Some(self.flush());
+        }
+        let descriptor = line.starts_with('#');
+        let family = (descriptor && self.samples).then(|| self.flush());
+        self.buffer.push_str(line);
+        self.samples |= !descriptor;
+        if let Some(family) = &family {
+            self.done = family.is_err();
+        }
+        family
+    }
+
+    fn flush(&mut self) -> Result<Spanned<String, Metricfamily<String>>, Error> {
+        let buffer = std::mem::take(&mut self.buffer);
+        let offset = self.offset;
+        self.offset += buffer.len();
+        self.samples = false;
+        let input = buffer.as_str();
+        match all_consuming(crate::metricfamily::<_, nom::error::Error<_>>)
+            .parse(input)
+            .finish()
+        {
+            Ok((_, metricfamily)) => {
+                let mut metricfamily = metricfamily.into_owned();
+                metricfamily.shift(offset);
+                Ok(Spanned {
+                    range: offset..offset + buffer.len(),
+                    raw: buffer.clone(),
+                    value: metricfamily,
+                })
+            }
+            Err(e) => {
+                let mut diagnostic = Diagnostic::from_error(&input, &e);
+                diagnostic.span = diagnostic
+                    .span
+                    .map(|span| offset + span.start..offset + span.end);
+                Err(Error::Invalid(diagnostic))
+            }
+        }
+    }
+}
+
+impl<R> Iterator for Families<R>
+where
+    R: BufRead,
+{
+    type Item = Result<Spanned<String, Metricfamily<String>>, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while !self.lines.done {
+            if self
+                .cancellation
+                .as_ref()
+                .is_some_and(CancellationToken::is_cancelled)
+            {
+                self.lines.done = true;
+                return Some(Err(Error::Cancelled));
+            }
+            if let Some(e) = self.timed_out() {
+                self.lines.done = true;
+                return Some(Err(e));
+            }
+            self.line.clear();
+            let read = self.reader.read_line(&mut self.line);
+            if let Some(family) = self.lines.next(read, &self.line) {
+                return Some(family);
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests;
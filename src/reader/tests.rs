@@ -0,0 +1,125 @@
+use super::Error;
+use nom::error::Error as NomError;
+use nom::{Finish, Parser};
+
+#[rstest::rstest]
+#[case("# EOF\n")]
+#[case("a 1\n# EOF")]
+#[case(
+    r#"# TYPE a counter
+# HELP a Help.
+a_total 1
+a_created 0
+# TYPE b gauge
+b 2
+c 3
+# TYPE d gauge
+# EOF
+"#
+)]
+fn test_parse_reader(#[case] input: &str) {
+    let (_, exposition) = crate::exposition::<_, NomError<_>>
+        .parse(input)
+        .finish()
+        .unwrap();
+    let families = super::parse_reader(input.as_bytes())
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    assert_eq!(
+        families,
+        exposition.into_owned().metricset.value.metricfamily,
+    );
+}
+
+#[test]
+fn test_parse_reader_stops_at_eof() {
+    let mut reader = "a 1\n# EOF\nb 2\n".as_bytes();
+    assert_eq!(super::parse_reader(&mut reader).count(), 1);
+    assert_eq!(reader, b"b 2\n");
+}
+
+#[rstest::rstest]
+#[case("a 1\n# TYPE b gauge\nb\n# EOF\n", 1, Some(19..20))]
+#[case("a 1\n", 0, None)]
+fn test_parse_reader_error(
+    #[case] input: &str,
+    #[case] families: usize,
+    #[case] span: Option<std::ops::Range<usize>>,
+) {
+    let results = super::parse_reader(input.as_bytes()).collect::<Vec<_>>();
+    assert_eq!(results.len(), families + 1);
+    match &results[families] {
+        Err(Error::Invalid(diagnostic)) => assert_eq!(diagnostic.span, span),
+        Err(Error::Io(e)) => {
+            assert_eq!(e.kind(), std::io::ErrorKind::UnexpectedEof);
+            assert_eq!(span, None);
+        }
+        Err(Error::Cancelled | Error::Timeout(_)) | Ok(_) => panic!("{input:?}"),
+    }
+}
+
+#[test]
+fn test_parse_reader_cancel_on() {
+    let token = crate::cancel::CancellationToken::new();
+    let mut families = super::parse_reader(
+        "a 1
+# TYPE b gauge
+b 2
+# EOF
+"
+        .as_bytes(),
+    )
+    .cancel_on(token.clone());
+    assert!(families.next().unwrap().is_ok());
+    token.cancel();
+    assert!(matches!(families.next(), Some(Err(Error::Cancelled))));
+    assert!(families.next().is_none());
+}
+
+#[test]
+fn test_parse_reader_timeout() {
+    let clock = crate::clock::MockClock::new(std::time::UNIX_EPOCH);
+    let mut families = super::parse_reader("a 1\n# TYPE b gauge\nb 2\n# EOF\n".as_bytes())
+        .timeout_with(std::time::Duration::from_secs(1), clock.clone());
+    assert!(families.next().unwrap().is_ok());
+    clock.advance(std::time::Duration::from_secs(1));
+    match families.next() {
+        Some(Err(Error::Timeout(diagnostic))) => assert_eq!(diagnostic.span, Some(19..19)),
+        _ => panic!(),
+    }
+    assert!(families.next().is_none());
+}
+
+#[cfg(feature = "tokio")]
+#[rstest::rstest]
+#[case("a 1\n# TYPE b gauge\nb 2\n# EOF\nc 3\n")]
+#[case("a 1\n# TYPE b gauge\nb\n# EOF\n")]
+#[case("a 1\n")]
+fn test_parse_async(#[case] input: &str) {
+    use futures_util::StreamExt;
+    use std::future::Future;
+    use std::task::{Context, Poll, Waker};
+
+    // reads of a slice complete without waiting
+    let mut collect = std::pin::pin!(super::parse_async(input.as_bytes()).collect::<Vec<_>>());
+    let Poll::Ready(families) = collect
+        .as_mut()
+        .poll(&mut Context::from_waker(Waker::noop()))
+    else {
+        panic!("{input:?}");
+    };
+    let expected = super::parse_reader(input.as_bytes()).collect::<Vec<_>>();
+    assert_eq!(families.len(), expected.len());
+    for (family, expected) in families.iter().zip(&expected) {
+        match (family, expected) {
+            (Ok(family), Ok(expected)) => assert_eq!(family, expected),
+            (Err(super::Error::Invalid(e)), Err(super::Error::Invalid(expected))) => {
+                assert_eq!(e, expected)
+            }
+            (Err(super::Error::Io(e)), Err(super::Error::Io(expected))) => {
+                assert_eq!(e.kind(), expected.kind())
+            }
+            _ => panic!("{input:?}"),
+        }
+    }
+}
@@ -0,0 +1,302 @@
+use crate::model::{self, MetricFamily};
+use crate::{Exposition, Label, MetricType, Metricfamily, Sample, Spanned, Timestamp};
+use ::prometheus::core::{Collector, Desc};
+use ::prometheus::{Registry, proto};
+use std::collections::{BTreeSet, HashMap};
+use std::fmt;
+
+// https://docs.rs/prometheus/0.13
+//
+// The families of a parsed exposition as a `prometheus::core::Collector`, e.g. to re-expose
+// upstream metrics through the `/metrics` endpoint of a service. The values are taken once by
+// `new` and collected as they were parsed. A family without HELP is described by its name. The
+// optional `_count` and `_sum` of a histogram or summary are 0 when missing, as in the protos
+// (the count of a histogram being its `+Inf` bucket, if any).
+#[derive(Clone, Debug)]
+pub struct Snapshot {
+    descs: Vec<Desc>,
+    families: Vec<proto::MetricFamily>,
+}
+
+#[derive(Debug)]
+pub enum Error {
+    Prometheus(::prometheus::Error),
+    Model(model::Error),
+    // e.g. a gaugehistogram, which the client has no type for
+    Unsupported(String),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Prometheus(e) => write!(f, "{e}"),
+            Self::Model(e) => write!(f, "{e}"),
+            Self::Unsupported(message) => write!(f, "{message}"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl Snapshot {
+    pub fn new<I>(exposition: &Exposition<I>) -> Result<Self, Error>
+    where
+        I: AsRef<str> + Clone,
+    {
+        let mut descs = Vec::new();
+        let mut families = Vec::new();
+        for metricfamily in &exposition.metricset.metricfamily {
+            for family in convert(metricfamily)? {
+                let labels = family
+                    .get_metric()
+                    .iter()
+                    .flat_map(|metric| metric.get_label().iter())
+                    .map(|label| label.get_name().to_owned())
+                    .collect::<BTreeSet<_>>();
+                descs.push(
+                    Desc::new(
+                        family.get_name().to_owned(),
+                        family.get_help().to_owned(),
+                        labels.into_iter().collect(),
+                        HashMap::new(),
+                    )
+                    .map_err(Error::Prometheus)?,
+                );
+                families.push(family);
+            }
+        }
+        Ok(Self { descs, families })
+    }
+}
+
+impl Collector for Snapshot {
+    fn desc(&self) -> Vec<&Desc> {
+        self.descs.iter().collect()
+    }
+
+    fn collect(&self) -> Vec<proto::MetricFamily> {
+        self.families.clone()
+    }
+}
+
+// Registers the families of `exposition` into `registry` as one `Snapshot`.
+pub fn register<I>(registry: &Registry, exposition: &Exposition<I>) -> Result<(), Error>
+where
+    I: AsRef<str> + Clone,
+{
+    let snapshot = Snapshot::new(exposition)?;
+    registry
+        .register(Box::new(snapshot))
+        .map_err(Error::Prometheus)
+}
+
+// A stateset becomes a gauge with a sample per state and an info a gauge named `<name>_info`,
+// as Prometheus ingests them.
+fn convert<I>(metricfamily: &Spanned<I, Metricfamily<I>>) -> Result<Vec<proto::MetricFamily>, Error>
+where
+    I: AsRef<str> + Clone,
+{
+    let family = MetricFamily::try_from(metricfamily.value.clone()).map_err(Error::Model)?;
+    let name = family.name.as_ref();
+    let help = family
+        .help
+        .as_ref()
+        .map_or_else(|| name.to_owned(), |help| help.unescape().into_owned());
+    let metricfamily = &metricfamily.value;
+
+    let mut metrics = Vec::new();
+    let (name, r#type) = match family.r#type {
+        MetricType::Counter => {
+            for counter in model::counters(metricfamily).map_err(Error::Model)? {
+                let mut value = proto::Counter::default();
+                value.set_value(counter.total);
+                let mut metric = metric(&counter.labels, counter.timestamp)?;
+                metric.set_counter(value);
+                metrics.push(metric);
+            }
+            (format!("{name}_total"), proto::MetricType::COUNTER)
+        }
+        MetricType::Gauge | MetricType::Unknown => {
+            for sample in metricfamily.samples().map(|sample| &sample.value) {
+                let mut metric = metric(&labels(sample), sample.timestamp.as_ref())?;
+                if family.r#type == MetricType::Gauge {
+                    let mut value = proto::Gauge::default();
+                    value.set_value(number(sample)?);
+                    metric.set_gauge(value);
+                } else {
+                    let mut value = proto::Untyped::default();
+                    value.set_value(number(sample)?);
+                    metric.set_untyped(value);
+                }
+                metrics.push(metric);
+            }
+            let r#type = match family.r#type {
+                MetricType::Gauge => proto::MetricType::GAUGE,
+                _ => proto::MetricType::UNTYPED,
+            };
+            (name.to_owned(), r#type)
+        }
+        MetricType::Histogram => {
+            for histogram in model::histograms(metricfamily).map_err(Error::Model)? {
+                let mut value = proto::Histogram::default();
+                let mut buckets = Vec::new();
+                let mut total = None;
+                for (le, count) in &histogram.buckets {
+                    let count = count_of(name, *count)?;
+                    if *le == f64::INFINITY {
+                        total = Some(count);
+                        continue;
+                    }
+                    let mut bucket = proto::Bucket::default();
+                    bucket.set_upper_bound(*le);
+                    bucket.set_cumulative_count(count);
+                    buckets.push(bucket);
+                }
+                let count = match histogram.count {
+                    Some(count) => count_of(name, count)?,
+                    None => total.unwrap_or_default(),
+                };
+                value.set_bucket(buckets.into());
+                value.set_sample_count(count);
+                value.set_sample_sum(histogram.sum.unwrap_or_default());
+                let mut metric = metric(&histogram.labels, histogram.timestamp)?;
+                metric.set_histogram(value);
+                metrics.push(metric);
+            }
+            (name.to_owned(), proto::MetricType::HISTOGRAM)
+        }
+        MetricType::Summary => {
+            for summary in model::summaries(metricfamily).map_err(Error::Model)? {
+                let mut value = proto::Summary::default();
+                let quantiles = summary
+                    .quantiles
+                    .iter()
+                    .map(|(q, v)| {
+                        let mut quantile = proto::Quantile::default();
+                        quantile.set_quantile(*q);
+                        quantile.set_value(*v);
+                        quantile
+                    })
+                    .collect::<Vec<_>>();
+                value.set_quantile(quantiles.into());
+                let count = summary
+                    .count
+                    .map(|count| count_of(name, count))
+                    .transpose()?;
+                value.set_sample_count(count.unwrap_or_default());
+                value.set_sample_sum(summary.sum.unwrap_or_default());
+                let mut metric = metric(&summary.labels, summary.timestamp)?;
+                metric.set_summary(value);
+                metrics.push(metric);
+            }
+            (name.to_owned(), proto::MetricType::SUMMARY)
+        }
+        MetricType::Stateset => {
+            for stateset in model::statesets(metricfamily).map_err(Error::Model)? {
+                for (state, enabled) in &stateset.states {
+                    let mut metric = metric(&stateset.labels, stateset.timestamp)?;
+                    let mut labels = metric.get_label().to_vec();
+                    labels.push(label_pair(name, state));
+                    metric.set_label(labels.into());
+                    let mut value = proto::Gauge::default();
+                    value.set_value(f64::from(u8::from(*enabled)));
+                    metric.set_gauge(value);
+                    metrics.push(metric);
+                }
+            }
+            (name.to_owned(), proto::MetricType::GAUGE)
+        }
+        MetricType::Info => {
+            for info in model::infos(metricfamily).map_err(Error::Model)? {
+                let mut metric = metric(&info.labels, info.timestamp)?;
+                let mut value = proto::Gauge::default();
+                value.set_value(1.);
+                metric.set_gauge(value);
+                metrics.push(metric);
+            }
+            (format!("{name}_info"), proto::MetricType::GAUGE)
+        }
+        MetricType::Gaugehistogram => {
+            return Err(Error::Unsupported(format!(
+                "`{name}` is a gaugehistogram, which the client has no type for"
+            )));
+        }
+    };
+    if metrics.is_empty() {
+        return Ok(Vec::new());
+    }
+    let mut family = proto::MetricFamily::default();
+    family.set_name(name);
+    family.set_help(help);
+    family.set_field_type(r#type);
+    family.set_metric(metrics.into());
+    Ok(vec![family])
+}
+
+fn metric<I>(labels: &[&Label<I>], timestamp: Option<&I>) -> Result<proto::Metric, Error>
+where
+    I: AsRef<str>,
+{
+    let mut metric = proto::Metric::default();
+    let labels = labels
+        .iter()
+        .map(|label| label_pair(label.label_name.as_ref(), &label.escaped_string.unescape()))
+        .collect::<Vec<_>>();
+    metric.set_label(labels.into());
+    if let Some(timestamp) = timestamp {
+        let timestamp = timestamp.as_ref();
+        let milliseconds = Timestamp::parse(timestamp)
+            .and_then(|parsed| {
+                parsed
+                    .seconds
+                    .checked_mul(1000)?
+                    .checked_add(i64::from(parsed.nanos / 1_000_000))
+            })
+            .ok_or_else(|| Error::Unsupported(format!("timestamp `{timestamp}` out of range")))?;
+        metric.set_timestamp_ms(milliseconds);
+    }
+    Ok(metric)
+}
+
+fn labels<I>(sample: &Sample<I>) -> Vec<&Label<I>> {
+    sample
+        .labels
+        .iter()
+        .flat_map(|labels| &labels.label)
+        .map(|label| &label.value)
+        .collect()
+}
+
+fn label_pair(name: &str, value: &str) -> proto::LabelPair {
+    let mut label = proto::LabelPair::default();
+    label.set_name(name.to_owned());
+    label.set_value(value.to_owned());
+    label
+}
+
+fn number<I>(sample: &Sample<I>) -> Result<f64, Error>
+where
+    I: AsRef<str>,
+{
+    let number = sample.number.as_ref();
+    crate::parse_number(number).ok_or_else(|| {
+        Error::Model(model::Error::InvalidNumber {
+            metricname: sample.metricname.as_ref().to_owned(),
+            number: number.to_owned(),
+        })
+    })
+}
+
+// The client counts observations as integers.
+fn count_of(name: &str, count: f64) -> Result<u64, Error> {
+    if count >= 0. && count.fract() == 0. && count <= u64::MAX as f64 {
+        Ok(count as u64)
+    } else {
+        Err(Error::Unsupported(format!(
+            "`{name}` has a count of {count}, which the client cannot represent"
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests;
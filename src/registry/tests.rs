@@ -0,0 +1,117 @@
+use super::{Error, Snapshot};
+use ::prometheus::core::Collector;
+use ::prometheus::proto::MetricType;
+use nom::error::Error as NomError;
+use nom::{Finish, Parser};
+
+fn snapshot(input: &str) -> Result<Snapshot, Error> {
+    let (_, exposition) = crate::exposition::<_, NomError<_>>
+        .parse(input)
+        .finish()
+        .unwrap();
+    Snapshot::new(&exposition)
+}
+
+#[test]
+fn test_snapshot() {
+    let input = r#"# TYPE a counter
+# HELP a Help.
+a_total{job="x"} 1 123.5
+# TYPE b histogram
+b_bucket{le="1"} 2
+b_bucket{le="+Inf"} 3
+b_count 3
+b_sum 4.5
+# TYPE c stateset
+c{c="on"} 1
+c{c="off"} 0
+c{c="a\"b"} 0
+# TYPE d info
+d_info{version="1"} 1
+# TYPE e unknown
+e 7
+# EOF
+"#;
+    let snapshot = snapshot(input).unwrap();
+    let families = snapshot.collect();
+    assert_eq!(snapshot.desc().len(), families.len());
+    assert_eq!(
+        families
+            .iter()
+            .map(|family| (
+                family.get_name(),
+                family.get_help(),
+                family.get_field_type()
+            ))
+            .collect::<Vec<_>>(),
+        [
+            ("a_total", "Help.", MetricType::COUNTER),
+            ("b", "b", MetricType::HISTOGRAM),
+            ("c", "c", MetricType::GAUGE),
+            ("d_info", "d", MetricType::GAUGE),
+            ("e", "e", MetricType::UNTYPED),
+        ]
+    );
+
+    let counter = &families[0].get_metric()[0];
+    assert_eq!(counter.get_counter().get_value(), 1.);
+    assert_eq!(counter.get_timestamp_ms(), 123_500);
+    assert_eq!(counter.get_label()[0].get_name(), "job");
+
+    let histogram = families[1].get_metric()[0].get_histogram();
+    assert_eq!(histogram.get_bucket().len(), 1);
+    assert_eq!(histogram.get_bucket()[0].get_cumulative_count(), 2);
+    assert_eq!(histogram.get_sample_count(), 3);
+    assert_eq!(histogram.get_sample_sum(), 4.5);
+
+    assert_eq!(
+        families[2]
+            .get_metric()
+            .iter()
+            .map(|metric| (
+                metric.get_label()[0].get_value(),
+                metric.get_gauge().get_value()
+            ))
+            .collect::<Vec<_>>(),
+        [("on", 1.), ("off", 0.), ("a\"b", 0.)]
+    );
+}
+
+#[test]
+fn test_snapshot_without_count_and_sum() {
+    let input = r#"# TYPE a histogram
+a_bucket{le="+Inf"} 2
+# TYPE b summary
+b{quantile="0.5"} 1
+# EOF
+"#;
+    let families = snapshot(input).unwrap().collect();
+    let histogram = families[0].get_metric()[0].get_histogram();
+    assert_eq!(
+        (histogram.get_sample_count(), histogram.get_sample_sum()),
+        (2, 0.)
+    );
+    let summary = families[1].get_metric()[0].get_summary();
+    assert_eq!(
+        (summary.get_sample_count(), summary.get_sample_sum()),
+        (0, 0.)
+    );
+}
+
+#[test]
+fn test_register() {
+    let (_, exposition) = crate::exposition::<_, NomError<_>>
+        .parse("# TYPE a gauge\na 1\n# EOF\n")
+        .finish()
+        .unwrap();
+    let registry = ::prometheus::Registry::new();
+    super::register(&registry, &exposition).unwrap();
+    assert_eq!(registry.gather().len(), 1);
+}
+
+#[rstest::rstest]
+#[case("# TYPE a gaugehistogram\na_bucket{le=\"+Inf\"} 1\na_gcount 1\na_gsum 1\n# EOF\n")]
+#[case("# TYPE a histogram\na_bucket{le=\"+Inf\"} 1.5\na_sum 1\n# EOF\n")]
+fn test_unsupported(#[case] input: &str) {
+    assert!(matches!(snapshot(input), Err(Error::Unsupported(_))));
+}
@@ -0,0 +1,121 @@
+use crate::{MetricType, Metricset};
+
+// A line that `regroup` moved, by its index among all descriptor and sample lines.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Move {
+    pub family: String,
+    pub line: String,
+    pub from: usize,
+    pub to: usize,
+}
+
+// Reorders the lines of `metricset` so that every family appears once, its descriptors precede
+// its samples and the samples of each series (labels other than `le` and `quantile`) are
+// contiguous. Families and series keep the order of their first appearance. Returns the
+// regrouped lines, to be parsed again with `# EOF` appended so that every node has a raw input
+// and range of its own, and the lines moved. Comment and extension lines are left out.
+pub fn regroup<I>(metricset: &Metricset<I>) -> (String, Vec<Move>)
+where
+    I: AsRef<str>,
+{
+    // family name -> type, as described anywhere
+    let mut described = Vec::<(&str, MetricType)>::new();
+    for metricfamily in &metricset.metricfamily {
+        if let Some(metric_descriptor) = metricfamily.metric_descriptor.first() {
+            let name = metric_descriptor.metricname().as_ref();
+            if !described.iter().any(|(described, _)| *described == name) {
+                described.push((name, metricfamily.metric_type()));
+            }
+        }
+    }
+    let family_of = |metricname: &str| {
+        described
+            .iter()
+            .filter(|(name, metric_type)| {
+                metricname
+                    .strip_prefix(name)
+                    .is_some_and(|suffix| metric_type.sample_suffixes().contains(&suffix))
+            })
+            .max_by_key(|(name, _)| name.len())
+            .map_or(metricname, |(name, _)| name)
+            .to_owned()
+    };
+
+    let mut families = Vec::<Family<I>>::new();
+    let mut index = 0;
+    for metricfamily in &metricset.metricfamily {
+        for metric_descriptor in &metricfamily.metric_descriptor {
+            let name = metric_descriptor.metricname().as_ref().to_owned();
+            let family = Family::find(&mut families, name);
+            family
+                .metric_descriptor
+                .push((index, &metric_descriptor.raw));
+            index += 1;
+        }
+        for sample in metricfamily.samples() {
+            let name = family_of(sample.metricname.as_ref());
+            let family = Family::find(&mut families, name);
+            let series = sample
+                .labels
+                .iter()
+                .flat_map(|labels| &labels.label)
+                .filter(|label| !["le", "quantile"].contains(&label.label_name.as_ref()))
+                .map(|label| label.raw.as_ref())
+                .collect::<Vec<_>>();
+            match family.series.iter_mut().find(|(key, _)| *key == series) {
+                Some((_, samples)) => samples.push((index, &sample.raw)),
+                None => family.series.push((series, vec![(index, &sample.raw)])),
+            }
+            index += 1;
+        }
+    }
+
+    let mut text = String::new();
+    let mut moves = Vec::new();
+    let mut to = 0;
+    for family in families {
+        let lines = family.series.into_iter().flat_map(|(_, samples)| samples);
+        for (from, raw) in family.metric_descriptor.into_iter().chain(lines) {
+            if from != to {
+                moves.push(Move {
+                    family: family.name.clone(),
+                    line: raw.as_ref().to_owned(),
+                    from,
+                    to,
+                });
+            }
+            text.push_str(raw.as_ref());
+            to += 1;
+        }
+    }
+    (text, moves)
+}
+
+// labels identifying the series -> sample lines, by line index
+type Series<'a, I> = (Vec<&'a str>, Vec<(usize, &'a I)>);
+
+struct Family<'a, I> {
+    name: String,
+    metric_descriptor: Vec<(usize, &'a I)>,
+    series: Vec<Series<'a, I>>,
+}
+
+impl<I> Family<'_, I> {
+    fn find(families: &mut Vec<Self>, name: String) -> &mut Self {
+        let position = match families.iter().position(|family| family.name == name) {
+            Some(position) => position,
+            None => {
+                families.push(Self {
+                    name,
+                    metric_descriptor: Vec::new(),
+                    series: Vec::new(),
+                });
+                families.len() - 1
+            }
+        };
+        &mut families[position]
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,93 @@
+use super::Move;
+use nom::error::Error;
+use nom::{Finish, Parser};
+
+#[test]
+fn test_regroup() {
+    let input = r#"a_total{job="x"} 1
+# TYPE a counter
+a_created{job="x"} 0
+b 2
+a_total{job="y"} 3
+# TYPE b gauge
+# EOF
+"#;
+    let (_, exposition) = crate::exposition::<_, Error<_>>
+        .parse(input)
+        .finish()
+        .unwrap();
+    let (text, moves) = super::regroup(&exposition.metricset);
+    assert_eq!(
+        text,
+        r#"# TYPE a counter
+a_total{job="x"} 1
+a_created{job="x"} 0
+a_total{job="y"} 3
+# TYPE b gauge
+b 2
+"#,
+    );
+    assert_eq!(
+        moves,
+        [
+            Move {
+                family: "a".to_owned(),
+                line: "# TYPE a counter\n".to_owned(),
+                from: 1,
+                to: 0,
+            },
+            Move {
+                family: "a".to_owned(),
+                line: "a_total{job=\"x\"} 1\n".to_owned(),
+                from: 0,
+                to: 1,
+            },
+            Move {
+                family: "a".to_owned(),
+                line: "a_total{job=\"y\"} 3\n".to_owned(),
+                from: 4,
+                to: 3,
+            },
+            Move {
+                family: "b".to_owned(),
+                line: "# TYPE b gauge\n".to_owned(),
+                from: 5,
+                to: 4,
+            },
+            Move {
+                family: "b".to_owned(),
+                line: "b 2\n".to_owned(),
+                from: 3,
+                to: 5,
+            },
+        ],
+    );
+}
+
+#[test]
+fn test_regroup_spans() {
+    let input = "# TYPE a gauge\na 1\n# TYPE b gauge\nb 2\na 3\n# EOF\n";
+    let (_, exposition) = crate::exposition::<_, Error<_>>
+        .parse(input)
+        .finish()
+        .unwrap();
+    let (text, _) = super::regroup(&exposition.metricset);
+    let text = text + "# EOF\n";
+    let (_, exposition) = crate::exposition::<_, Error<_>>
+        .parse(text.as_str())
+        .finish()
+        .unwrap();
+    let families = exposition
+        .metricset
+        .metricfamily
+        .iter()
+        .map(|metricfamily| {
+            assert_eq!(&text[metricfamily.range.clone()], metricfamily.raw);
+            metricfamily.raw
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        families,
+        ["# TYPE a gauge\na 1\na 3\n", "# TYPE b gauge\nb 2\n"]
+    );
+}
@@ -0,0 +1,131 @@
+use crate::diagnostic::Diagnostic;
+use crate::lint;
+use nom::Finish;
+use nom::error::Error;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+const BODY: &str = "om";
+const OUTCOME: &str = "outcome";
+
+// What parsing and linting a scrape body yields. The text form is what gets recorded, so that a
+// replay can be compared without a format of its own.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Outcome {
+    pub families: usize,
+    pub samples: usize,
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+impl Outcome {
+    pub fn new(body: &str) -> Self {
+        match crate::exposition::<_, Error<_>>(body).finish() {
+            Ok((_, exposition)) => {
+                let metricset = &exposition.metricset.value;
+                let mut diagnostics = lint::reserved_suffix(&exposition);
+                diagnostics.extend(lint::help_unit_consistency(&exposition));
+                Self {
+                    families: metricset.metricfamily.len(),
+                    samples: metricset
+                        .metricfamily
+                        .iter()
+                        .map(|metricfamily| metricfamily.samples().count())
+                        .sum(),
+                    diagnostics,
+                }
+            }
+            Err(error) => Self {
+                diagnostics: vec![Diagnostic::from_error(&body, &error)],
+                ..Self::default()
+            },
+        }
+    }
+}
+
+impl fmt::Display for Outcome {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "families {}", self.families)?;
+        writeln!(f, "samples {}", self.samples)?;
+        for diagnostic in &self.diagnostics {
+            write!(f, "{} ", diagnostic.rule)?;
+            match &diagnostic.span {
+                Some(span) => write!(f, "{}..{}", span.start, span.end)?,
+                None => write!(f, "-")?,
+            }
+            writeln!(f, " {}", diagnostic.message)?;
+        }
+        Ok(())
+    }
+}
+
+// Captures scrape bodies into `dir` as `<n>.om`, each next to the `<n>.outcome` it produced.
+#[derive(Debug)]
+pub struct Recorder {
+    dir: PathBuf,
+    next: usize,
+}
+
+impl Recorder {
+    // Appends to the recordings already in `dir`.
+    pub fn new<P>(dir: P) -> io::Result<Self>
+    where
+        P: Into<PathBuf>,
+    {
+        let dir = dir.into();
+        fs::create_dir_all(&dir)?;
+        let next = recordings(&dir)?.len();
+        Ok(Self { dir, next })
+    }
+
+    pub fn record(&mut self, body: &str) -> io::Result<Outcome> {
+        let outcome = Outcome::new(body);
+        let path = self.dir.join(format!("{:06}", self.next));
+        fs::write(path.with_extension(BODY), body)?;
+        fs::write(path.with_extension(OUTCOME), outcome.to_string())?;
+        self.next += 1;
+        Ok(outcome)
+    }
+}
+
+// A recording whose outcome changed.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Regression {
+    pub path: PathBuf,
+    pub recorded: String,
+    pub replayed: String,
+}
+
+// Parses every body recorded in `dir` again and reports the ones whose outcome differs.
+pub fn replay(dir: &Path) -> io::Result<Vec<Regression>> {
+    let mut regressions = Vec::new();
+    for path in recordings(dir)? {
+        let body = fs::read_to_string(&path)?;
+        let recorded = fs::read_to_string(path.with_extension(OUTCOME))?;
+        let replayed = Outcome::new(&body).to_string();
+        if replayed != recorded {
+            regressions.push(Regression {
+                path,
+                recorded,
+                replayed,
+            });
+        }
+    }
+    Ok(regressions)
+}
+
+fn recordings(dir: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut paths = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.extension().is_some_and(|extension| extension == BODY) {
+            paths.push(path);
+        }
+    }
+    paths.sort();
+    Ok(paths)
+}
+
+#[cfg(test)]
+mod tests;
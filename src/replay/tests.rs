@@ -0,0 +1,42 @@
+use super::{Outcome, Recorder};
+use std::fs;
+
+#[test]
+fn test_outcome() {
+    let outcome = Outcome::new("# TYPE a_total gauge\na_total 1\n# TYPE b gauge\nb 2\n# EOF\n");
+    assert_eq!(outcome.families, 2);
+    assert_eq!(outcome.samples, 2);
+    assert_eq!(outcome.diagnostics.len(), 1);
+
+    let outcome = Outcome::new("a 1\n");
+    assert!(
+        outcome
+            .to_string()
+            .starts_with("families 0\nsamples 0\nsyntax 4..4 invalid exposition")
+    );
+}
+
+#[test]
+fn test_record_replay() {
+    let dir = std::env::temp_dir().join(format!("openmetrics-nom-replay-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+
+    let mut recorder = Recorder::new(&dir).unwrap();
+    recorder.record("a 1\n# EOF\n").unwrap();
+    recorder.record("a 1\n").unwrap();
+    assert_eq!(super::replay(&dir).unwrap(), []);
+
+    // a recording made by an older version that accepted the body
+    fs::write(dir.join("000001.outcome"), "families 1\nsamples 1\n").unwrap();
+    let regressions = super::replay(&dir).unwrap();
+    assert_eq!(regressions.len(), 1);
+    assert_eq!(regressions[0].path, dir.join("000001.om"));
+    assert_eq!(regressions[0].recorded, "families 1\nsamples 1\n");
+
+    // new recordings are appended
+    let mut recorder = Recorder::new(&dir).unwrap();
+    recorder.record("b 2\n# EOF\n").unwrap();
+    assert!(dir.join("000002.om").exists());
+
+    fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,40 @@
+use crate::diagnostic::{Diagnostic, Severity};
+
+#[rstest::rstest]
+#[case(0, (1, 1))]
+#[case(3, (1, 4))]
+#[case(4, (2, 1))]
+#[case(11, (2, 7))]
+fn test_line_column(#[case] offset: usize, #[case] expected: (usize, usize)) {
+    assert_eq!(super::line_column("a 1\nb{c=\"é\"} 2\n", offset), expected);
+}
+
+#[test]
+fn test_to_sarif() {
+    let source = "a 1\nb 2\n";
+    let diagnostics = [Diagnostic {
+        rule: "rule",
+        severity: Severity::Warning,
+        message: "message".to_owned(),
+        span: Some(4..7),
+        family: Some("b".to_owned()),
+        series: Some("b".to_owned()),
+    }];
+    let log = super::to_sarif(&diagnostics, "metrics.om", source).unwrap();
+    let log = serde_json::from_str::<serde_json::Value>(&log).unwrap();
+
+    assert_eq!(log["version"], "2.1.0");
+    let run = &log["runs"][0];
+    assert_eq!(run["tool"]["driver"]["rules"][0]["id"], "rule");
+    let result = &run["results"][0];
+    assert_eq!(result["ruleId"], "rule");
+    assert_eq!(result["level"], "warning");
+    assert_eq!(result["message"]["text"], "message");
+    let location = &result["locations"][0]["physicalLocation"];
+    assert_eq!(location["artifactLocation"]["uri"], "metrics.om");
+    assert_eq!(location["region"]["startLine"], 2);
+    assert_eq!(location["region"]["startColumn"], 1);
+    assert_eq!(location["region"]["endColumn"], 4);
+    assert_eq!(location["region"]["byteOffset"], 4);
+    assert_eq!(location["region"]["byteLength"], 3);
+}
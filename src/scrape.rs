@@ -0,0 +1,345 @@
+use crate::clock::Clock;
+use crate::reader::{self, Error};
+use crate::transform;
+use crate::{
+    Exposition, Metric, MetricDescriptor, MetricType, Metricfamily, Metricset, Sample, Spanned,
+};
+use nom::error::{ContextError, ParseError};
+use nom::{IResult, Parser};
+use std::io::{self, BufRead, Read, Write};
+use std::time::{Duration, SystemTime};
+
+pub const SCRAPE_TIMEOUT: &str = "scrape-timeout";
+
+// The outcome of a single scrape attempt.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Scrape {
+    pub up: bool,
+    pub duration: Duration,
+    // size of the (uncompressed) body in bytes
+    pub body_size: usize,
+    pub samples: usize,
+    // diagnostics of parsing the body, e.g. of `validate::validate`
+    pub diagnostics: usize,
+}
+
+impl Scrape {
+    // https://prometheus.io/docs/concepts/jobs_instances/
+    //
+    // The synthetic series of the section "Automatically generated labels and time series" as a
+    // metricset, e.g. to merge into the families of the scrape. Spans refer to the text written
+    // by `write_metricset`.
+    pub fn metricset(&self) -> Metricset<String> {
+        let mut text = String::new();
+        let mut metricfamily = Vec::new();
+        for (metricname, unit, value) in self.families() {
+            let start = text.len();
+            let mut metric_descriptor = Vec::new();
+            let at = start + format!("# TYPE {metricname} ").len();
+            metric_descriptor.push(span(
+                &mut text,
+                format!("# TYPE {metricname} gauge\n"),
+                MetricDescriptor::Type {
+                    metricname: metricname.to_owned(),
+                    metric_type: Spanned {
+                        range: at..at + "gauge".len(),
+                        raw: "gauge".to_owned(),
+                        value: MetricType::Gauge,
+                    },
+                },
+            ));
+            if let Some(unit) = unit {
+                metric_descriptor.push(span(
+                    &mut text,
+                    format!("# UNIT {metricname} {unit}\n"),
+                    MetricDescriptor::Unit {
+                        metricname: metricname.to_owned(),
+                        metricname_char: unit.to_owned(),
+                    },
+                ));
+            }
+            let sample = span(
+                &mut text,
+                format!("{metricname} {value}\n"),
+                Sample {
+                    metricname: metricname.to_owned(),
+                    labels: None,
+                    number: value,
+                    timestamp: None,
+                    exemplar: None,
+                    normalized_number: None,
+                },
+            );
+            let metric = Spanned {
+                range: sample.range.clone(),
+                raw: sample.raw.clone(),
+                value: Metric {
+                    sample: vec![sample],
+                },
+            };
+            metricfamily.push(Spanned {
+                range: start..text.len(),
+                raw: text[start..].to_owned(),
+                value: Metricfamily {
+                    metric_descriptor,
+                    metric: vec![metric],
+                },
+            });
+        }
+        Metricset {
+            metricfamily,
+            extension: Vec::new(),
+            comment: Vec::new(),
+            merged: Vec::new(),
+        }
+    }
+
+    // Writes the synthetic series as metric families without the trailing `# EOF`,
+    // so that they can be appended to other families before terminating the exposition.
+    pub fn write_metricset<W>(&self, mut writer: W) -> io::Result<()>
+    where
+        W: Write,
+    {
+        write!(writer, "{}", self.metricset())
+    }
+
+    // `(metricname, unit, value)` of the series Prometheus adds to every scrape, and of
+    // `scrape_diagnostics`, which it does not
+    fn families(&self) -> [(&'static str, Option<&'static str>, String); 5] {
+        [
+            ("up", None, u8::from(self.up).to_string()),
+            (
+                "scrape_duration_seconds",
+                Some("seconds"),
+                self.duration.as_secs_f64().to_string(),
+            ),
+            ("scrape_samples_scraped", None, self.samples.to_string()),
+            (
+                "scrape_body_size_bytes",
+                Some("bytes"),
+                self.body_size.to_string(),
+            ),
+            ("scrape_diagnostics", None, self.diagnostics.to_string()),
+        ]
+    }
+}
+
+// Spans the line `raw` appended to `text`.
+fn span<T>(text: &mut String, raw: String, value: T) -> Spanned<String, T> {
+    let start = text.len();
+    text.push_str(&raw);
+    Spanned {
+        range: start..text.len(),
+        raw,
+        value,
+    }
+}
+
+// What `read_within` read before it ended.
+#[derive(Debug)]
+pub struct Partial {
+    pub families: Vec<Spanned<String, Metricfamily<String>>>,
+    // `up` only if the exposition was read up to `# EOF`; `body_size` and `samples` count the
+    // families read, `diagnostics` the error if it has a diagnostic
+    pub scrape: Scrape,
+    // what ended the read early, e.g. `reader::Error::Timeout`
+    pub error: Option<Error>,
+}
+
+// https://prometheus.io/docs/prometheus/latest/configuration/configuration/#scrape_config
+//
+// Reads the families of the exposition from `reader` like `scrape_timeout`: `timeout` is the
+// budget of the whole read, measured by `clock` from this call. Unlike Prometheus, the families
+// read before the deadline or an error are kept rather than discarded.
+pub fn read_within<R, C>(reader: R, timeout: Duration, clock: C) -> Partial
+where
+    R: BufRead,
+    C: Clock + Clone + Send + 'static,
+{
+    let start = clock.instant();
+    let mut families = Vec::new();
+    let mut error = None;
+    for family in reader::parse_reader(reader).timeout_with(timeout, clock.clone()) {
+        match family {
+            Ok(family) => families.push(family),
+            Err(e) => error = Some(e),
+        }
+    }
+    let scrape = Scrape {
+        up: error.is_none(),
+        duration: clock.instant().saturating_duration_since(start),
+        body_size: families.iter().map(|family| family.raw.len()).sum(),
+        samples: families.iter().map(|family| family.samples().count()).sum(),
+        diagnostics: usize::from(matches!(error, Some(Error::Invalid(_) | Error::Timeout(_)))),
+    };
+    Partial {
+        families,
+        scrape,
+        error,
+    }
+}
+
+// `read_within`, along with where and when the exposition was scraped and the hash of the body
+// read, e.g. to trace the families fed to `sink::feed_scraped` back to the scrape.
+pub fn scrape_within<R, C>(
+    target: impl Into<String>,
+    content_type: Option<String>,
+    reader: R,
+    timeout: Duration,
+    clock: C,
+) -> Scraped<Partial>
+where
+    R: BufRead,
+    C: Clock + Clone + Send + 'static,
+{
+    let scraped_at = clock.now();
+    let mut reader = Hashing {
+        inner: reader,
+        hash: FNV_OFFSET_BASIS,
+    };
+    let partial = read_within(&mut reader, timeout, clock);
+    Scraped {
+        target: target.into(),
+        scraped_at,
+        content_type,
+        body_hash: reader.hash,
+        inner: partial,
+    }
+}
+
+const FNV_OFFSET_BASIS: u64 = 0xcbf29ce484222325;
+
+fn fnv1a(hash: u64, bytes: &[u8]) -> u64 {
+    bytes.iter().fold(hash, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(0x100000001b3)
+    })
+}
+
+// Hashes the bytes read from `inner`.
+struct Hashing<R> {
+    inner: R,
+    hash: u64,
+}
+
+impl<R> Read for Hashing<R>
+where
+    R: BufRead,
+{
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let len = self.inner.read(buf)?;
+        self.hash = fnv1a(self.hash, &buf[..len]);
+        Ok(len)
+    }
+}
+
+impl<R> BufRead for Hashing<R>
+where
+    R: BufRead,
+{
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        self.inner.fill_buf()
+    }
+
+    fn consume(&mut self, amount: usize) {
+        // the buffer is still filled, so this does not read
+        if let Ok(buffer) = self.inner.fill_buf() {
+            self.hash = fnv1a(self.hash, &buffer[..amount.min(buffer.len())]);
+        }
+        self.inner.consume(amount);
+    }
+}
+
+// Where and when `inner` was scraped, kept along while the body is parsed and transformed.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Scraped<T> {
+    // e.g. the scraped URL
+    pub target: String,
+    pub scraped_at: SystemTime,
+    pub content_type: Option<String>,
+    // FNV-1a of the body, stable across runs and platforms
+    pub body_hash: u64,
+    pub inner: T,
+}
+
+impl<T> Scraped<T>
+where
+    T: AsRef<str>,
+{
+    pub fn new(
+        target: impl Into<String>,
+        scraped_at: SystemTime,
+        content_type: Option<String>,
+        body: T,
+    ) -> Self {
+        Self {
+            target: target.into(),
+            scraped_at,
+            content_type,
+            body_hash: fnv1a(FNV_OFFSET_BASIS, body.as_ref().as_bytes()),
+            inner: body,
+        }
+    }
+
+    // Parses the body like `crate::exposition`.
+    pub fn exposition<'a, E>(&'a self) -> IResult<&'a str, Scraped<Exposition<&'a str>>, E>
+    where
+        E: ContextError<&'a str> + ParseError<&'a str>,
+    {
+        let (input, exposition) = crate::exposition.parse(self.inner.as_ref())?;
+        Ok((input, self.as_ref().map(|_| exposition)))
+    }
+}
+
+impl<I> Scraped<Exposition<I>> {
+    // Applies a transform, e.g. `|exposition| transform::prefix(exposition, "node_")`. The
+    // transformed exposition keeps the provenance, including the hash of the scraped body.
+    pub fn transform<F>(&self, f: F) -> Result<Scraped<String>, transform::Error>
+    where
+        F: FnOnce(&Exposition<I>) -> Result<String, transform::Error>,
+    {
+        self.as_ref().try_map(f)
+    }
+}
+
+impl<T> Scraped<T> {
+    pub fn as_ref(&self) -> Scraped<&T> {
+        Scraped {
+            target: self.target.clone(),
+            scraped_at: self.scraped_at,
+            content_type: self.content_type.clone(),
+            body_hash: self.body_hash,
+            inner: &self.inner,
+        }
+    }
+
+    // e.g. replacing a parsed exposition by its diagnostics
+    pub fn map<U, F>(self, f: F) -> Scraped<U>
+    where
+        F: FnOnce(T) -> U,
+    {
+        Scraped {
+            target: self.target,
+            scraped_at: self.scraped_at,
+            content_type: self.content_type,
+            body_hash: self.body_hash,
+            inner: f(self.inner),
+        }
+    }
+
+    // e.g. parsing the body or applying a transform
+    pub fn try_map<U, E, F>(self, f: F) -> Result<Scraped<U>, E>
+    where
+        F: FnOnce(T) -> Result<U, E>,
+    {
+        Ok(Scraped {
+            target: self.target,
+            scraped_at: self.scraped_at,
+            content_type: self.content_type,
+            body_hash: self.body_hash,
+            inner: f(self.inner)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests;
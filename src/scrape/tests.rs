@@ -0,0 +1,161 @@
+use super::Scrape;
+use crate::clock::MockClock;
+use nom::error::Error;
+use nom::{Finish, Parser};
+use std::collections::VecDeque;
+use std::io::{BufReader, Read};
+use std::time::Duration;
+
+// Returns a line per read, a second after the previous one.
+struct Slow {
+    lines: VecDeque<&'static str>,
+    clock: MockClock,
+}
+
+impl Read for Slow {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let Some(line) = self.lines.pop_front() else {
+            return Ok(0);
+        };
+        self.clock.advance(Duration::from_secs(1));
+        buf[..line.len()].copy_from_slice(line.as_bytes());
+        Ok(line.len())
+    }
+}
+
+#[test]
+fn test_write_metricset() {
+    let scrape = Scrape {
+        up: true,
+        duration: Duration::from_millis(1500),
+        body_size: 1024,
+        samples: 42,
+        diagnostics: 2,
+    };
+    let mut output = Vec::new();
+    scrape.write_metricset(&mut output).unwrap();
+    let output = String::from_utf8(output).unwrap();
+    assert_eq!(
+        output,
+        r#"# TYPE up gauge
+up 1
+# TYPE scrape_duration_seconds gauge
+# UNIT scrape_duration_seconds seconds
+scrape_duration_seconds 1.5
+# TYPE scrape_samples_scraped gauge
+scrape_samples_scraped 42
+# TYPE scrape_body_size_bytes gauge
+# UNIT scrape_body_size_bytes bytes
+scrape_body_size_bytes 1024
+# TYPE scrape_diagnostics gauge
+scrape_diagnostics 2
+"#,
+    );
+
+    let input = output + "# EOF\n";
+    let (_, exposition) = crate::exposition::<_, Error<_>>
+        .parse(input.as_str())
+        .finish()
+        .unwrap();
+    assert_eq!(exposition.metricset.value.into_owned(), scrape.metricset());
+}
+
+#[test]
+fn test_scraped() {
+    let scraped_at = std::time::UNIX_EPOCH + Duration::from_secs(1);
+    let scraped = super::Scraped::new(
+        "http://localhost:9100/metrics",
+        scraped_at,
+        Some("application/openmetrics-text; version=1.0.0".to_owned()),
+        "a 1\n# EOF\n".to_owned(),
+    );
+    assert_eq!(scraped.body_hash, 0x32b43168b549622c);
+    let empty = super::Scraped::new("", scraped_at, None, "");
+    assert_eq!(empty.body_hash, 0xcbf29ce484222325);
+
+    let (_, exposition) = scraped.exposition::<Error<_>>().finish().unwrap();
+    let prefixed = exposition
+        .transform(|exposition| crate::transform::prefix(exposition, "node_"))
+        .unwrap();
+    assert_eq!(prefixed.inner, "node_a 1\n# EOF\n");
+    assert_eq!(prefixed.map(|_| ()), scraped.map(|_| ()));
+}
+
+#[test]
+fn test_scrape_within() {
+    let body = "a 1\n# TYPE b gauge\nb 2\n# EOF\n";
+    let clock = MockClock::new(std::time::UNIX_EPOCH);
+    let scraped = super::scrape_within(
+        "http://localhost:9100/metrics",
+        None,
+        body.as_bytes(),
+        Duration::from_secs(10),
+        clock,
+    );
+    let expected = super::Scraped::new(
+        "http://localhost:9100/metrics",
+        std::time::UNIX_EPOCH,
+        None,
+        body,
+    );
+    assert_eq!(scraped.body_hash, expected.body_hash);
+    assert_eq!(scraped.scraped_at, std::time::UNIX_EPOCH);
+    assert_eq!(scraped.inner.families.len(), 2);
+    assert!(scraped.inner.scrape.up);
+}
+
+#[rstest::rstest]
+#[case(Duration::from_secs(10), 3, None)]
+#[case(Duration::from_millis(2500), 1, Some(23..23))]
+fn test_read_within(
+    #[case] timeout: Duration,
+    #[case] families: usize,
+    #[case] span: Option<std::ops::Range<usize>>,
+) {
+    let clock = MockClock::new(std::time::UNIX_EPOCH);
+    let reader = Slow {
+        lines: VecDeque::from([
+            "a 1\n",
+            "# TYPE b gauge\n",
+            "b 2\n",
+            "# TYPE c gauge\n",
+            "c 3\n",
+            "# EOF\n",
+        ]),
+        clock: clock.clone(),
+    };
+    let partial = super::read_within(BufReader::new(reader), timeout, clock.clone());
+    assert_eq!(partial.families.len(), families);
+    match partial.error {
+        Some(crate::reader::Error::Timeout(diagnostic)) => {
+            assert_eq!(diagnostic.rule, super::SCRAPE_TIMEOUT);
+            assert_eq!(diagnostic.code(), Some("OM-SCR-001"));
+            assert_eq!(diagnostic.span, span);
+        }
+        None => assert_eq!(span, None),
+        Some(e) => panic!("{e}"),
+    }
+    let samples = partial.families.len();
+    assert_eq!(
+        partial.scrape,
+        Scrape {
+            up: span.is_none(),
+            duration: clock.elapsed(),
+            body_size: partial.families.iter().map(|family| family.raw.len()).sum(),
+            samples,
+            diagnostics: usize::from(span.is_some()),
+        },
+    );
+}
+
+#[test]
+fn test_read_within_invalid() {
+    let clock = MockClock::new(std::time::UNIX_EPOCH);
+    let partial = super::read_within("a 1\nb\n# EOF\n".as_bytes(), Duration::from_secs(10), clock);
+    assert!(matches!(
+        partial.error,
+        Some(crate::reader::Error::Invalid(_))
+    ));
+    assert!(!partial.scrape.up);
+    assert_eq!(partial.scrape.diagnostics, 1);
+}
@@ -0,0 +1,52 @@
+use crate::{Metricset, Sample, Spanned};
+
+// Samples whose `label` value, once unescaped, contains `needle`.
+// The needle is escaped instead, and searched for in the values as written.
+pub fn find_samples_with_value_containing<'a, I>(
+    metricset: &'a Metricset<I>,
+    label: &str,
+    needle: &str,
+) -> Vec<&'a Spanned<I, Sample<I>>>
+where
+    I: AsRef<str>,
+{
+    let needle = crate::escape(needle);
+    metricset
+        .metricfamily
+        .iter()
+        .flat_map(|metricfamily| metricfamily.samples())
+        .filter(|sample| {
+            sample
+                .labels
+                .iter()
+                .flat_map(|labels| &labels.label)
+                .any(|l| {
+                    l.label_name.as_ref() == label
+                        && contains_escaped(l.escaped_string.raw.as_ref(), &needle)
+                })
+        })
+        .collect()
+}
+
+// A match only counts if it starts at an escape sequence boundary, otherwise the `n` of `\n`
+// would match `n`. Every boundary is tried, as matches may overlap: `nn` is in `\nnn`.
+fn contains_escaped(haystack: &str, needle: &str) -> bool {
+    let mut rest = haystack;
+    loop {
+        if rest.starts_with(needle) {
+            return true;
+        }
+        let mut chars = rest.chars();
+        match chars.next() {
+            Some('\\') => {
+                chars.next();
+            }
+            Some(_) => {}
+            None => return false,
+        }
+        rest = chars.as_str();
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,31 @@
+use nom::error::Error;
+use nom::{Finish, Parser};
+
+#[rstest::rstest]
+#[case("GET", &["a{path=\"/GET\"} 1\n"])]
+#[case("n", &["a{path=\"n\"} 3\n", "a{path=\"\\\\n\"} 4\n", "a{path=\"\\nnn\"} 6\n"])]
+#[case("nn", &["a{path=\"\\nnn\"} 6\n"])]
+#[case("\n", &["a{path=\"\\n\"} 2\n", "a{path=\"\\nnn\"} 6\n"])]
+#[case("\\", &["a{path=\"\\\\n\"} 4\n"])]
+#[case("\\n", &["a{path=\"\\\\n\"} 4\n"])]
+#[case("\"", &[])]
+fn test_find_samples_with_value_containing(#[case] needle: &str, #[case] expected: &[&str]) {
+    let input = r#"a{path="/GET"} 1
+a{path="\n"} 2
+a{path="n"} 3
+a{path="\\n"} 4
+a{method="GET"} 5
+a{path="\nnn"} 6
+# EOF
+"#;
+    let (_, exposition) = crate::exposition::<_, Error<_>>
+        .parse(input)
+        .finish()
+        .unwrap();
+    let samples =
+        super::find_samples_with_value_containing(&exposition.metricset.value, "path", needle)
+            .into_iter()
+            .map(|sample| sample.raw)
+            .collect::<Vec<_>>();
+    assert_eq!(samples, expected);
+}
@@ -0,0 +1,100 @@
+use ::bytes::Bytes;
+use ::bytes::buf::IntoIter;
+use nom::{Compare, CompareResult, Input, Needed, Offset};
+use std::fmt::{self, Display};
+use std::iter::Enumerate;
+use std::str::Utf8Error;
+
+// A UTF-8 `bytes::Bytes` input: every span captured by the parsers is a refcounted slice of the
+// buffer, e.g. of the body of a response, so the exposition can be kept without copying it. Parse
+// `Shared::new(body)?` like a `&str`. The parsers only split the buffer around ASCII characters,
+// so the spans are UTF-8 too; `as_ref` checks the span it is called on.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Shared(Bytes);
+
+impl Shared {
+    pub fn new(bytes: Bytes) -> Result<Self, Utf8Error> {
+        std::str::from_utf8(&bytes)?;
+        Ok(Self(bytes))
+    }
+
+    pub fn bytes(&self) -> &Bytes {
+        &self.0
+    }
+
+    pub fn into_bytes(self) -> Bytes {
+        self.0
+    }
+}
+
+impl AsRef<str> for Shared {
+    fn as_ref(&self) -> &str {
+        std::str::from_utf8(&self.0).unwrap_or_default()
+    }
+}
+
+impl Display for Shared {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_ref())
+    }
+}
+
+impl Input for Shared {
+    type Item = u8;
+    type Iter = IntoIter<Bytes>;
+    type IterIndices = Enumerate<IntoIter<Bytes>>;
+
+    fn input_len(&self) -> usize {
+        self.0.len()
+    }
+
+    fn take(&self, index: usize) -> Self {
+        Self(self.0.slice(..index))
+    }
+
+    fn take_from(&self, index: usize) -> Self {
+        Self(self.0.slice(index..))
+    }
+
+    fn take_split(&self, index: usize) -> (Self, Self) {
+        (self.take_from(index), self.take(index))
+    }
+
+    fn position<P>(&self, predicate: P) -> Option<usize>
+    where
+        P: Fn(Self::Item) -> bool,
+    {
+        self.0.iter().position(|b| predicate(*b))
+    }
+
+    fn iter_elements(&self) -> Self::Iter {
+        self.0.clone().into_iter()
+    }
+
+    fn iter_indices(&self) -> Self::IterIndices {
+        self.iter_elements().enumerate()
+    }
+
+    fn slice_index(&self, count: usize) -> Result<usize, Needed> {
+        (&self.0[..]).slice_index(count)
+    }
+}
+
+impl<'b> Compare<&'b str> for Shared {
+    fn compare(&self, t: &'b str) -> CompareResult {
+        (&self.0[..]).compare(t)
+    }
+
+    fn compare_no_case(&self, t: &'b str) -> CompareResult {
+        (&self.0[..]).compare_no_case(t)
+    }
+}
+
+impl Offset for Shared {
+    fn offset(&self, second: &Self) -> usize {
+        self.0[..].offset(&second.0[..])
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,34 @@
+use super::Shared;
+use bytes::Bytes;
+use nom::error::Error;
+use nom::{Finish, Parser};
+
+#[test]
+fn test_shared() {
+    let input = "# TYPE a counter\na_total{b=\"€\",c=\"d\"} 1\n# EOF\n";
+    let body = Bytes::from(input);
+    let (_, exposition) = crate::exposition::<_, Error<_>>
+        .parse(Shared::new(body.clone()).unwrap())
+        .finish()
+        .unwrap();
+    let (_, expected) = crate::exposition::<_, Error<_>>
+        .parse(input)
+        .finish()
+        .unwrap();
+    assert_eq!(
+        exposition.clone().map_input(|shared| shared.to_string()),
+        expected.map_input(str::to_owned)
+    );
+
+    let sample = &exposition.metricset.value.metricfamily[0].metric[0].sample[0];
+    let number = sample.number.bytes();
+    assert_eq!(
+        number.as_ptr(),
+        body[input.find(" 1\n").unwrap() + 1..].as_ptr()
+    );
+}
+
+#[test]
+fn test_invalid_utf8() {
+    assert!(Shared::new(Bytes::from(&b"a \xff\n# EOF\n"[..])).is_err());
+}
@@ -0,0 +1,268 @@
+pub use crate::CONTENT_TYPE;
+use std::fmt::Write as _;
+use std::io::{self, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+#[derive(Clone, Debug)]
+pub struct Config {
+    // delay before the response headers are sent
+    pub latency: Duration,
+    // send the body with `Transfer-Encoding: chunked` using chunks of this size
+    pub chunk_size: Option<usize>,
+    // send the body with `Content-Encoding: gzip`
+    pub gzip: bool,
+    pub content_type: String,
+    // close the connection after this many (encoded) body bytes
+    pub truncate: Option<usize>,
+    // drop a connection whose request has not arrived by then
+    pub read_timeout: Duration,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            latency: Duration::ZERO,
+            chunk_size: None,
+            gzip: false,
+            content_type: CONTENT_TYPE.to_owned(),
+            truncate: None,
+            read_timeout: Duration::from_secs(5),
+        }
+    }
+}
+
+// Deterministic expositions of `families` families of `series` series each, cycling through
+// counters, gauges and histograms, e.g. to load-test a scraper. The `scrape`-th exposition
+// continues the previous ones: counters and histograms grow, gauges move.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Synthetic {
+    pub families: usize,
+    pub series: usize,
+    pub seed: u64,
+}
+
+impl Synthetic {
+    pub fn generate(&self, scrape: u64) -> String {
+        const BOUNDS: [&str; 3] = ["0.1", "1", "10"];
+        let mut output = String::new();
+        for family in 0..self.families {
+            let name = format!("synthetic_{family}");
+            let kind = family % 3;
+            let metric_type = ["counter", "gauge", "histogram"][kind];
+            let _ = writeln!(output, "# TYPE {name} {metric_type}");
+            for series in 0..self.series {
+                let random = mix(self.seed ^ mix(family as u64) ^ mix(series as u64 + 1));
+                let labels = format!("series=\"{series}\"");
+                // observations so far, at least one per scrape
+                let count = (scrape + 1) * (random % 10 + 1);
+                match kind {
+                    0 => {
+                        let _ = writeln!(output, "{name}_total{{{labels}}} {count}");
+                    }
+                    1 => {
+                        let value = mix(random ^ scrape) % 1000;
+                        let _ = writeln!(output, "{name}{{{labels}}} {value}");
+                    }
+                    _ => {
+                        for (index, bound) in BOUNDS.iter().enumerate() {
+                            let bucket = count * (index as u64 + 1) / (BOUNDS.len() as u64 + 1);
+                            let _ = writeln!(
+                                output,
+                                "{name}_bucket{{{labels},le=\"{bound}\"}} {bucket}"
+                            );
+                        }
+                        let _ = writeln!(output, "{name}_bucket{{{labels},le=\"+Inf\"}} {count}");
+                        let _ = writeln!(output, "{name}_count{{{labels}}} {count}");
+                        let _ = writeln!(output, "{name}_sum{{{labels}}} {}", count * 2);
+                    }
+                }
+            }
+        }
+        output.push_str("# EOF\n");
+        output
+    }
+}
+
+// SplitMix64
+fn mix(x: u64) -> u64 {
+    let x = x.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    let x = (x ^ (x >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    let x = (x ^ (x >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    x ^ (x >> 31)
+}
+
+#[derive(Debug)]
+enum Body {
+    Static(Vec<u8>),
+    // the number of the next scrape
+    Synthetic(Synthetic, AtomicU64),
+}
+
+impl Body {
+    fn next(&self) -> Vec<u8> {
+        match self {
+            Self::Static(body) => body.clone(),
+            Self::Synthetic(synthetic, scrape) => synthetic
+                .generate(scrape.fetch_add(1, Ordering::SeqCst))
+                .into_bytes(),
+        }
+    }
+}
+
+// A minimal HTTP/1.1 server answering every request with the same exposition, or with the
+// next one of a `Synthetic`. Every connection is served on a thread of its own. It is stopped
+// when dropped.
+#[derive(Debug)]
+pub struct Simulator {
+    addr: SocketAddr,
+    shutdown: Arc<AtomicBool>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl Simulator {
+    pub fn start<B>(body: B, config: Config) -> io::Result<Self>
+    where
+        B: Into<Vec<u8>>,
+    {
+        Self::serve(Body::Static(body.into()), config)
+    }
+
+    pub fn start_synthetic(synthetic: Synthetic, config: Config) -> io::Result<Self> {
+        Self::serve(Body::Synthetic(synthetic, AtomicU64::new(0)), config)
+    }
+
+    fn serve(body: Body, config: Config) -> io::Result<Self> {
+        let listener = TcpListener::bind(("127.0.0.1", 0))?;
+        let addr = listener.local_addr()?;
+        let shutdown = Arc::new(AtomicBool::new(false));
+        let body = Arc::new(body);
+        let config = Arc::new(config);
+        let handle = thread::spawn({
+            let shutdown = shutdown.clone();
+            move || {
+                for stream in listener.incoming() {
+                    if shutdown.load(Ordering::SeqCst) {
+                        break;
+                    }
+                    if let Ok(stream) = stream {
+                        let (body, config) = (body.clone(), config.clone());
+                        // a misbehaving client must not stop the server
+                        thread::spawn(move || respond(stream, &body, &config));
+                    }
+                }
+            }
+        });
+        Ok(Self {
+            addr,
+            shutdown,
+            handle: Some(handle),
+        })
+    }
+
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    pub fn url(&self) -> String {
+        format!("http://{}/metrics", self.addr)
+    }
+}
+
+impl Drop for Simulator {
+    fn drop(&mut self) {
+        self.shutdown.store(true, Ordering::SeqCst);
+        // wake up the blocking `accept`
+        let _ = TcpStream::connect(self.addr);
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+fn respond(mut stream: TcpStream, body: &Body, config: &Config) -> io::Result<()> {
+    stream.set_read_timeout(Some(config.read_timeout))?;
+    read_request(&mut stream)?;
+    thread::sleep(config.latency);
+
+    let body = body.next();
+    let body = if config.gzip { gzip(&body) } else { body };
+    let truncated = &body[..config.truncate.unwrap_or(body.len()).min(body.len())];
+
+    let mut head = format!(
+        "HTTP/1.1 200 OK\r\nContent-Type: {}\r\nConnection: close\r\n",
+        config.content_type,
+    );
+    if config.gzip {
+        head.push_str("Content-Encoding: gzip\r\n");
+    }
+    match config.chunk_size {
+        Some(_) => head.push_str("Transfer-Encoding: chunked\r\n"),
+        None => head.push_str(&format!("Content-Length: {}\r\n", body.len())),
+    }
+    head.push_str("\r\n");
+    stream.write_all(head.as_bytes())?;
+
+    match config.chunk_size {
+        Some(chunk_size) => {
+            for chunk in truncated.chunks(chunk_size.max(1)) {
+                stream.write_all(format!("{:x}\r\n", chunk.len()).as_bytes())?;
+                stream.write_all(chunk)?;
+                stream.write_all(b"\r\n")?;
+                stream.flush()?;
+            }
+            if truncated.len() == body.len() {
+                stream.write_all(b"0\r\n\r\n")?;
+            }
+        }
+        None => stream.write_all(truncated)?,
+    }
+    stream.flush()
+}
+
+fn read_request(stream: &mut TcpStream) -> io::Result<()> {
+    let mut request = Vec::new();
+    let mut buf = [0; 1024];
+    while !request.ends_with(b"\r\n\r\n") {
+        let n = stream.read(&mut buf)?;
+        if n == 0 {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+        request.extend_from_slice(&buf[..n]);
+    }
+    Ok(())
+}
+
+// RFC 1952 member whose deflate stream (RFC 1951) only uses stored blocks.
+// The output is not smaller than the input, but any gzip decoder accepts it.
+fn gzip(data: &[u8]) -> Vec<u8> {
+    let mut out = vec![0x1f, 0x8b, 0x08, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xff];
+    let mut chunks = data.chunks(u16::MAX as usize).peekable();
+    if chunks.peek().is_none() {
+        out.extend_from_slice(&[0x01, 0x00, 0x00, 0xff, 0xff]);
+    }
+    while let Some(chunk) = chunks.next() {
+        let len = chunk.len() as u16;
+        out.push(if chunks.peek().is_none() { 0x01 } else { 0x00 });
+        out.extend_from_slice(&len.to_le_bytes());
+        out.extend_from_slice(&(!len).to_le_bytes());
+        out.extend_from_slice(chunk);
+    }
+    out.extend_from_slice(&crc32(data).to_le_bytes());
+    out.extend_from_slice(&(data.len() as u32).to_le_bytes());
+    out
+}
+
+fn crc32(data: &[u8]) -> u32 {
+    !data.iter().fold(!0, |crc, byte| {
+        (0..8).fold(crc ^ u32::from(*byte), |crc, _| {
+            (crc >> 1) ^ (0xedb8_8320 & (crc & 1).wrapping_neg())
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,162 @@
+use super::{Config, Simulator, Synthetic};
+use nom::error::Error;
+use nom::{Finish, Parser};
+use std::io::{Read, Write};
+use std::net::{SocketAddr, TcpStream};
+use std::time::Duration;
+
+const INPUT: &str = r#"# TYPE a counter
+a_total{b="c"} 1
+# EOF
+"#;
+
+fn fetch(addr: SocketAddr) -> (String, Vec<u8>) {
+    let mut stream = TcpStream::connect(addr).unwrap();
+    stream
+        .write_all(b"GET /metrics HTTP/1.1\r\nHost: localhost\r\n\r\n")
+        .unwrap();
+    let mut response = Vec::new();
+    stream.read_to_end(&mut response).unwrap();
+    let position = response
+        .windows(4)
+        .position(|window| window == b"\r\n\r\n")
+        .unwrap();
+    let body = response.split_off(position + 4);
+    (String::from_utf8(response).unwrap(), body)
+}
+
+fn dechunk(mut body: &[u8]) -> Vec<u8> {
+    let mut out = Vec::new();
+    loop {
+        let position = body
+            .windows(2)
+            .position(|window| window == b"\r\n")
+            .unwrap();
+        let len = std::str::from_utf8(&body[..position]).unwrap();
+        let len = usize::from_str_radix(len, 16).unwrap();
+        if len == 0 {
+            return out;
+        }
+        out.extend_from_slice(&body[position + 2..position + 2 + len]);
+        body = &body[position + 2 + len + 2..];
+    }
+}
+
+#[test]
+fn test_crc32() {
+    assert_eq!(super::crc32(b"123456789"), 0xcbf4_3926);
+}
+
+#[test]
+fn test_chunked() {
+    let simulator = Simulator::start(
+        INPUT,
+        Config {
+            latency: Duration::from_millis(10),
+            chunk_size: Some(7),
+            ..Config::default()
+        },
+    )
+    .unwrap();
+
+    let (head, body) = fetch(simulator.addr());
+    assert!(head.starts_with("HTTP/1.1 200 OK\r\n"));
+    assert!(head.contains("Transfer-Encoding: chunked\r\n"));
+    assert!(head.contains(super::CONTENT_TYPE));
+
+    let body = String::from_utf8(dechunk(&body)).unwrap();
+    crate::exposition::<_, Error<_>>
+        .parse(body.as_str())
+        .finish()
+        .unwrap();
+}
+
+#[test]
+fn test_faults() {
+    let simulator = Simulator::start(
+        INPUT,
+        Config {
+            content_type: "text/html".to_owned(),
+            truncate: Some(20),
+            ..Config::default()
+        },
+    )
+    .unwrap();
+
+    let (head, body) = fetch(simulator.addr());
+    assert!(head.contains("Content-Type: text/html\r\n"));
+    assert!(head.contains(&format!("Content-Length: {}\r\n", INPUT.len())));
+    assert_eq!(body, &INPUT.as_bytes()[..20]);
+    assert!(
+        crate::exposition::<_, Error<_>>
+            .parse(std::str::from_utf8(&body).unwrap())
+            .finish()
+            .is_err()
+    );
+}
+
+#[test]
+fn test_gzip() {
+    let simulator = Simulator::start(
+        INPUT,
+        Config {
+            gzip: true,
+            ..Config::default()
+        },
+    )
+    .unwrap();
+
+    let (head, body) = fetch(simulator.addr());
+    assert!(head.contains("Content-Encoding: gzip\r\n"));
+    assert_eq!(body[..3], [0x1f, 0x8b, 0x08]);
+    // a single final stored block follows the 10-byte header
+    assert_eq!(body[10], 0x01);
+    assert_eq!(&body[15..15 + INPUT.len()], INPUT.as_bytes());
+    assert_eq!(
+        body[body.len() - 8..body.len() - 4],
+        super::crc32(INPUT.as_bytes()).to_le_bytes(),
+    );
+}
+
+#[test]
+fn test_idle_client() {
+    let simulator = Simulator::start(
+        INPUT,
+        Config {
+            read_timeout: Duration::from_secs(60),
+            ..Config::default()
+        },
+    )
+    .unwrap();
+
+    // a client that never sends its request blocks neither other clients nor the drop
+    let _idle = TcpStream::connect(simulator.addr()).unwrap();
+    let (_, body) = fetch(simulator.addr());
+    assert_eq!(body, INPUT.as_bytes());
+    drop(simulator);
+}
+
+#[test]
+fn test_synthetic() {
+    let synthetic = Synthetic {
+        families: 4,
+        series: 3,
+        seed: 42,
+    };
+    assert_eq!(synthetic.generate(1), synthetic.generate(1));
+    assert_ne!(synthetic.generate(0), synthetic.generate(1));
+
+    let simulator = Simulator::start_synthetic(synthetic, Config::default()).unwrap();
+    for scrape in 0..2 {
+        let (_, body) = fetch(simulator.addr());
+        let body = String::from_utf8(body).unwrap();
+        assert_eq!(body, synthetic.generate(scrape));
+        let (_, exposition) = crate::exposition::<_, Error<_>>
+            .parse(body.as_str())
+            .finish()
+            .unwrap();
+        assert_eq!(crate::validate::validate(&exposition), []);
+        let families = crate::model::metric_families(exposition.metricset.value).unwrap();
+        assert_eq!(families.len(), 4);
+    }
+}
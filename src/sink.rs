@@ -0,0 +1,219 @@
+use crate::cancel::{CancellationToken, Cancelled};
+use crate::diagnostic::Diagnostic;
+use crate::options::ParseOptions;
+use crate::scrape::Scraped;
+use crate::{Metricfamily, Metricset, Sample, Spanned};
+use nom::Finish;
+use nom::error::Error;
+use std::io::{self, Write};
+use std::thread;
+
+// An output format fed by `feed`: `start_family` for every family, then `sample` for each of its
+// samples, and `finish` once after the last family. A new format is added by implementing it,
+// and one parsed metricset can be fed to any number of sinks.
+pub trait Sink<I> {
+    // Called by `feed_scraped` before the first family, e.g. to record where the families come
+    // from. Ignored by default.
+    fn provenance(&mut self, _: &Scraped<()>) -> io::Result<()> {
+        Ok(())
+    }
+    fn start_family(&mut self, metricfamily: &Spanned<I, Metricfamily<I>>) -> io::Result<()>;
+    fn sample(
+        &mut self,
+        metricfamily: &Spanned<I, Metricfamily<I>>,
+        sample: &Spanned<I, Sample<I>>,
+    ) -> io::Result<()>;
+    fn finish(&mut self) -> io::Result<()>;
+}
+
+impl<I, S> Sink<I> for &mut S
+where
+    S: Sink<I> + ?Sized,
+{
+    fn provenance(&mut self, scraped: &Scraped<()>) -> io::Result<()> {
+        (**self).provenance(scraped)
+    }
+
+    fn start_family(&mut self, metricfamily: &Spanned<I, Metricfamily<I>>) -> io::Result<()> {
+        (**self).start_family(metricfamily)
+    }
+
+    fn sample(
+        &mut self,
+        metricfamily: &Spanned<I, Metricfamily<I>>,
+        sample: &Spanned<I, Sample<I>>,
+    ) -> io::Result<()> {
+        (**self).sample(metricfamily, sample)
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        (**self).finish()
+    }
+}
+
+// Stops at the first error of `sink`, without calling `finish`.
+pub fn feed<I, S>(metricset: &Metricset<I>, sink: &mut S) -> io::Result<()>
+where
+    S: Sink<I> + ?Sized,
+{
+    for metricfamily in &metricset.metricfamily {
+        sink.start_family(metricfamily)?;
+        for sample in metricfamily.samples() {
+            sink.sample(metricfamily, sample)?;
+        }
+    }
+    sink.finish()
+}
+
+// `feed`, passing the provenance of `scraped` to `sink` first.
+pub fn feed_scraped<I, S>(scraped: &Scraped<&Metricset<I>>, sink: &mut S) -> io::Result<()>
+where
+    S: Sink<I> + ?Sized,
+{
+    sink.provenance(&scraped.as_ref().map(|_| ()))?;
+    feed(scraped.inner, sink)
+}
+
+// Parses `input` once and feeds it to every sink on a thread of its own, so that a slow or
+// failing sink does not hold up the others. The result of every sink, in order, a panic being
+// an error of that sink alone; no sink is fed if `input` does not parse.
+pub fn fan_out<'a>(
+    input: &'a str,
+    sinks: &mut [&mut (dyn Sink<&'a str> + Send)],
+) -> Result<Vec<io::Result<()>>, Diagnostic> {
+    fan_out_with(input, sinks, &CancellationToken::new())
+}
+
+// `fan_out`, where every sink not done once `token` is cancelled fails before its next family or
+// sample with an `io::ErrorKind::Interrupted` error wrapping `Cancelled`, without `finish`. The
+// parse stops at the next line once `token` is cancelled, and then no sink is fed.
+pub fn fan_out_with<'a>(
+    input: &'a str,
+    sinks: &mut [&mut (dyn Sink<&'a str> + Send)],
+    token: &CancellationToken,
+) -> Result<Vec<io::Result<()>>, Diagnostic> {
+    let (_, exposition) = ParseOptions::default()
+        .exposition_until::<_, Error<_>, _>(input, |_| token.is_cancelled())
+        .finish()
+        .map_err(|e| Diagnostic::from_error(&input, &e))?;
+    if token.is_cancelled() {
+        return Ok(sinks
+            .iter()
+            .map(|_| Err(io::Error::new(io::ErrorKind::Interrupted, Cancelled)))
+            .collect());
+    }
+    let metricset = &exposition.metricset.value;
+    Ok(thread::scope(|scope| {
+        let handles = sinks
+            .iter_mut()
+            .map(|sink| {
+                scope.spawn(move || {
+                    feed(
+                        metricset,
+                        &mut Cancellable {
+                            sink,
+                            token: token.clone(),
+                        },
+                    )
+                })
+            })
+            .collect::<Vec<_>>();
+        handles
+            .into_iter()
+            .map(|handle| {
+                handle
+                    .join()
+                    .unwrap_or_else(|_| Err(io::Error::other("the sink panicked")))
+            })
+            .collect()
+    }))
+}
+
+struct Cancellable<S> {
+    sink: S,
+    token: CancellationToken,
+}
+
+impl<S> Cancellable<S> {
+    fn check(&self) -> io::Result<()> {
+        if self.token.is_cancelled() {
+            return Err(io::Error::new(io::ErrorKind::Interrupted, Cancelled));
+        }
+        Ok(())
+    }
+}
+
+impl<I, S> Sink<I> for Cancellable<S>
+where
+    S: Sink<I>,
+{
+    fn provenance(&mut self, scraped: &Scraped<()>) -> io::Result<()> {
+        self.check()?;
+        self.sink.provenance(scraped)
+    }
+
+    fn start_family(&mut self, metricfamily: &Spanned<I, Metricfamily<I>>) -> io::Result<()> {
+        self.check()?;
+        self.sink.start_family(metricfamily)
+    }
+
+    fn sample(
+        &mut self,
+        metricfamily: &Spanned<I, Metricfamily<I>>,
+        sample: &Spanned<I, Sample<I>>,
+    ) -> io::Result<()> {
+        self.check()?;
+        self.sink.sample(metricfamily, sample)
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        self.check()?;
+        self.sink.finish()
+    }
+}
+
+// OpenMetrics text as written by `crate::encode::encode`, without the extension lines, which are
+// not fed to sinks.
+#[derive(Debug)]
+pub struct Text<W> {
+    writer: W,
+}
+
+impl<W> Text<W> {
+    pub fn new(writer: W) -> Self {
+        Self { writer }
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+impl<I, W> Sink<I> for Text<W>
+where
+    I: AsRef<str>,
+    W: Write,
+{
+    fn start_family(&mut self, metricfamily: &Spanned<I, Metricfamily<I>>) -> io::Result<()> {
+        for metric_descriptor in &metricfamily.metric_descriptor {
+            write!(self.writer, "{metric_descriptor}")?;
+        }
+        Ok(())
+    }
+
+    fn sample(
+        &mut self,
+        _: &Spanned<I, Metricfamily<I>>,
+        sample: &Spanned<I, Sample<I>>,
+    ) -> io::Result<()> {
+        write!(self.writer, "{sample}")
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        self.writer.write_all(b"# EOF\n")?;
+        self.writer.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,173 @@
+use super::{Sink, Text};
+use crate::scrape::Scraped;
+use crate::{Metricfamily, Sample, Spanned};
+use nom::error::Error;
+use nom::{Finish, Parser};
+use std::io;
+
+const INPUT: &str = r#"# TYPE a counter
+# HELP a Help.
+a_total{b="c"} 1 0 # {trace_id="t"} 1
+a_created 0
+# TYPE b gauge
+b 2
+# EOF
+"#;
+
+#[derive(Default)]
+struct Recorder {
+    events: Vec<String>,
+    fail_at: Option<usize>,
+}
+
+impl Sink<&str> for Recorder {
+    fn provenance(&mut self, scraped: &Scraped<()>) -> io::Result<()> {
+        self.events.push(format!("provenance {}", scraped.target));
+        Ok(())
+    }
+
+    fn start_family(&mut self, metricfamily: &Spanned<&str, Metricfamily<&str>>) -> io::Result<()> {
+        self.events
+            .push(format!("family {}", metricfamily.metric_descriptor.len()));
+        Ok(())
+    }
+
+    fn sample(
+        &mut self,
+        _: &Spanned<&str, Metricfamily<&str>>,
+        sample: &Spanned<&str, Sample<&str>>,
+    ) -> io::Result<()> {
+        if self.fail_at == Some(self.events.len()) {
+            return Err(io::ErrorKind::Other.into());
+        }
+        self.events.push(format!("sample {}", sample.metricname));
+        Ok(())
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        self.events.push("finish".to_owned());
+        Ok(())
+    }
+}
+
+#[test]
+fn test_feed() {
+    let (_, exposition) = crate::exposition::<_, Error<_>>
+        .parse(INPUT)
+        .finish()
+        .unwrap();
+
+    let mut text = Text::new(Vec::new());
+    let mut recorder = Recorder::default();
+    for sink in [&mut text as &mut dyn Sink<_>, &mut recorder] {
+        super::feed(&exposition.metricset, sink).unwrap();
+    }
+    assert_eq!(String::from_utf8(text.into_inner()).unwrap(), INPUT);
+    assert_eq!(
+        recorder.events,
+        [
+            "family 2",
+            "sample a_total",
+            "sample a_created",
+            "family 1",
+            "sample b",
+            "finish",
+        ]
+    );
+
+    let mut recorder = Recorder {
+        fail_at: Some(2),
+        ..Recorder::default()
+    };
+    assert!(super::feed(&exposition.metricset, &mut recorder).is_err());
+    assert_eq!(recorder.events, ["family 2", "sample a_total"]);
+}
+
+#[test]
+fn test_feed_scraped() {
+    let scraped = Scraped::new(
+        "http://localhost/metrics",
+        std::time::UNIX_EPOCH,
+        None,
+        INPUT,
+    );
+    let (_, exposition) = scraped.exposition::<Error<_>>().finish().unwrap();
+    let mut text = Text::new(Vec::new());
+    let mut recorder = Recorder::default();
+    for sink in [&mut text as &mut dyn Sink<_>, &mut recorder] {
+        super::feed_scraped(&exposition.as_ref().map(|e| &e.metricset.value), sink).unwrap();
+    }
+    assert_eq!(String::from_utf8(text.into_inner()).unwrap(), INPUT);
+    assert_eq!(
+        recorder.events[..2],
+        ["provenance http://localhost/metrics", "family 2"]
+    );
+}
+
+#[test]
+fn test_fan_out() {
+    let mut text = Text::new(Vec::new());
+    let mut failing = Recorder {
+        fail_at: Some(1),
+        ..Recorder::default()
+    };
+    let mut recorder = Recorder::default();
+    let results = super::fan_out(INPUT, &mut [&mut text, &mut failing, &mut recorder]).unwrap();
+    assert!(matches!(&results[..], [Ok(()), Err(_), Ok(())]));
+    assert_eq!(String::from_utf8(text.into_inner()).unwrap(), INPUT);
+    assert_eq!(failing.events, ["family 2"]);
+    assert_eq!(recorder.events.last().unwrap(), "finish");
+
+    let mut recorder = Recorder::default();
+    let diagnostic = super::fan_out("a x\n# EOF\n", &mut [&mut recorder]).unwrap_err();
+    assert_eq!(diagnostic.span, Some(0..1));
+    assert!(recorder.events.is_empty());
+}
+
+#[test]
+fn test_fan_out_with() {
+    struct Cancelling(crate::cancel::CancellationToken, Recorder);
+
+    impl Sink<&str> for Cancelling {
+        fn start_family(
+            &mut self,
+            metricfamily: &Spanned<&str, Metricfamily<&str>>,
+        ) -> io::Result<()> {
+            self.1.start_family(metricfamily)
+        }
+
+        fn sample(
+            &mut self,
+            metricfamily: &Spanned<&str, Metricfamily<&str>>,
+            sample: &Spanned<&str, Sample<&str>>,
+        ) -> io::Result<()> {
+            self.0.cancel();
+            self.1.sample(metricfamily, sample)
+        }
+
+        fn finish(&mut self) -> io::Result<()> {
+            self.1.finish()
+        }
+    }
+
+    let token = crate::cancel::CancellationToken::new();
+    let mut cancelling = Cancelling(token.clone(), Recorder::default());
+    let results = super::fan_out_with(INPUT, &mut [&mut cancelling], &token).unwrap();
+    let [Err(e)] = &results[..] else {
+        panic!("{results:?}");
+    };
+    assert_eq!(e.kind(), io::ErrorKind::Interrupted);
+    assert!(
+        e.get_ref()
+            .is_some_and(|e| e.is::<crate::cancel::Cancelled>())
+    );
+    assert_eq!(cancelling.1.events, ["family 2", "sample a_total"]);
+
+    let mut recorder = Recorder::default();
+    let results = super::fan_out_with(INPUT, &mut [&mut recorder], &token).unwrap();
+    let [Err(e)] = &results[..] else {
+        panic!("{results:?}");
+    };
+    assert_eq!(e.kind(), io::ErrorKind::Interrupted);
+    assert!(recorder.events.is_empty());
+}
@@ -0,0 +1,82 @@
+use crate::Exposition;
+use crate::cancel::CancellationToken;
+use nom::error::{ContextError, ErrorKind, ParseError};
+use nom::{AsChar, Compare, CompareResult, IResult, Input, Needed, Offset};
+
+const EOF: &str = "# EOF";
+
+// `crate::exposition` for a body that is still being received: fails with `Incomplete` instead
+// of `Error` as long as every complete line is valid and `# EOF` has not arrived yet, so that it
+// can be retried once the buffer grew. A line is only judged once its line feed arrived, and
+// `# EOF` is accepted without one.
+//
+// This checks the whole buffer on every call, see `Streaming` to feed a body in many chunks.
+pub fn exposition<I, E>(input: I) -> IResult<I, Exposition<I>, E>
+where
+    I: Compare<&'static str> + Input + Offset,
+    I::Item: AsChar,
+    E: ContextError<I> + ParseError<I>,
+{
+    Streaming::default().exposition(input)
+}
+
+// `exposition` retried on a growing buffer, each call starting with the input of the previous
+// one. The lines already checked are skipped up to the start of their last family, the only one
+// that more lines may continue, so that feeding a body in many chunks stays linear. The whole
+// buffer is parsed once, when `# EOF` arrived or a line fails.
+#[derive(Clone, Debug, Default)]
+pub struct Streaming {
+    // the offset of the last family checked, or of the end of the lines checked without family
+    checked: usize,
+    cancellation: Option<CancellationToken>,
+}
+
+impl Streaming {
+    // Fails every call with the context `cancelled` once `token` is cancelled, before checking
+    // the buffer.
+    pub fn cancel_on(mut self, token: CancellationToken) -> Self {
+        self.cancellation = Some(token);
+        self
+    }
+
+    pub fn exposition<I, E>(&mut self, input: I) -> IResult<I, Exposition<I>, E>
+    where
+        I: Compare<&'static str> + Input + Offset,
+        I::Item: AsChar,
+        E: ContextError<I> + ParseError<I>,
+    {
+        if self
+            .cancellation
+            .as_ref()
+            .is_some_and(CancellationToken::is_cancelled)
+        {
+            return Err(nom::Err::Failure(E::add_context(
+                input.clone(),
+                "cancelled",
+                E::from_error_kind(input, ErrorKind::Fail),
+            )));
+        }
+        if self.checked > input.input_len() {
+            self.checked = 0;
+        }
+        let unchecked = input.take_from(self.checked);
+        let lines = unchecked
+            .iter_indices()
+            .filter(|(_, c)| c.as_char() == crate::LF)
+            .last()
+            .map_or(0, |(index, _)| index + 1);
+        let (rest, metricset) = crate::metricset::<_, E>(unchecked.take(lines))?;
+        let eof = unchecked.take_from(lines).compare(EOF) == CompareResult::Ok;
+        if rest.input_len() == 0 && !eof {
+            self.checked += metricset
+                .metricfamily
+                .last()
+                .map_or(lines, |metricfamily| metricfamily.range.start);
+            return Err(nom::Err::Incomplete(Needed::Unknown));
+        }
+        crate::exposition(input)
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,70 @@
+use crate::error::{Kind, OpenMetricsError};
+use nom::Parser;
+use nom::error::Error;
+
+#[rstest::rstest]
+#[case("# TYPE a counter\na_total 1\n# EOF\n")]
+#[case("a 1\n# EOF")]
+fn test_exposition(#[case] input: &str) {
+    let expected = crate::exposition::<_, Error<_>>.parse(input);
+    // up to `# EOF`, which is complete even without its line feed
+    let eof = input.rfind("# EOF").unwrap() + 5;
+    for end in 0..eof {
+        assert!(
+            matches!(
+                super::exposition::<_, Error<_>>(&input[..end]),
+                Err(nom::Err::Incomplete(_)),
+            ),
+            "{:?}",
+            &input[..end],
+        );
+    }
+    assert_eq!(super::exposition::<_, Error<_>>(input), expected);
+}
+
+#[rstest::rstest]
+#[case("a 1\nb\n# E")]
+#[case("# TYPE a counter\n# TYPE a foo\n")]
+fn test_exposition_error(#[case] input: &str) {
+    assert!(matches!(
+        super::exposition::<_, Error<_>>(input),
+        Err(nom::Err::Error(_)),
+    ));
+}
+
+#[rstest::rstest]
+#[case("# TYPE a counter\na_total 1\n# TYPE b gauge\nb 1\nb{c=\"d\"} 2\n# EOF\n")]
+#[case("a 1\n# TYPE a gauge\n# EOF")]
+#[case("# TYPE a gauge\na 1\nb\n# EOF\n")]
+fn test_streaming(#[case] input: &str) {
+    let mut streaming = super::Streaming::default();
+    for end in 0..=input.len() {
+        let result = streaming.exposition::<_, Error<_>>(&input[..end]);
+        assert_eq!(
+            result,
+            super::exposition::<_, Error<_>>(&input[..end]),
+            "{:?}",
+            &input[..end],
+        );
+        if !matches!(result, Err(nom::Err::Incomplete(_))) {
+            break;
+        }
+    }
+}
+
+#[test]
+fn test_streaming_cancel_on() {
+    let token = crate::cancel::CancellationToken::new();
+    let mut streaming = super::Streaming::default().cancel_on(token.clone());
+    let input = "# TYPE a gauge\na 1\n";
+    assert!(matches!(
+        streaming.exposition::<_, Error<_>>(input),
+        Err(nom::Err::Incomplete(_)),
+    ));
+    token.cancel();
+    let Err(nom::Err::Failure(e)) = streaming.exposition::<_, OpenMetricsError<_>>(input) else {
+        panic!();
+    };
+    assert_eq!(e.kind, Kind::Cancelled);
+    assert_eq!(e.context, ["cancelled"]);
+}
@@ -0,0 +1,69 @@
+use crate::{Exposition, Sample};
+
+// The labels identifying the scraped target, which aggregation treats apart from the others.
+pub const JOB: &str = "job";
+pub const INSTANCE: &str = "instance";
+
+// The `job` and `instance` label values of a sample, unescaped.
+#[derive(Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Target {
+    pub job: Option<String>,
+    pub instance: Option<String>,
+}
+
+impl Target {
+    pub fn of<I>(sample: &Sample<I>) -> Self
+    where
+        I: AsRef<str>,
+    {
+        let mut target = Self::default();
+        for label in sample.labels.iter().flat_map(|labels| &labels.label) {
+            let value = || Some(label.escaped_string.unescape().into_owned());
+            match label.label_name.as_ref() {
+                JOB => target.job = value(),
+                INSTANCE => target.instance = value(),
+                _ => {}
+            }
+        }
+        target
+    }
+}
+
+// Every target exposed by the samples, once, in the order of appearance. A single target without
+// `job` and `instance` for a plain scrape, usually several for federation.
+pub fn targets<I>(exposition: &Exposition<I>) -> Vec<Target>
+where
+    I: AsRef<str>,
+{
+    let mut targets = Vec::new();
+    for metricfamily in &exposition.metricset.metricfamily {
+        for sample in metricfamily.samples() {
+            let target = Target::of(sample);
+            if !targets.contains(&target) {
+                targets.push(target);
+            }
+        }
+    }
+    targets
+}
+
+// The label name an exposed label is moved to when the target sets it, `exported_<name>`
+// prefixed again as long as it is taken, as Prometheus does without `honor_labels`.
+pub fn exported_name<I>(sample: &Sample<I>, name: &str) -> String
+where
+    I: AsRef<str>,
+{
+    let mut exported = format!("exported_{name}");
+    while sample
+        .labels
+        .iter()
+        .flat_map(|labels| &labels.label)
+        .any(|label| label.label_name.as_ref() == exported)
+    {
+        exported.insert_str(0, "exported_");
+    }
+    exported
+}
+
+#[cfg(test)]
+mod tests;
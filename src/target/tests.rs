@@ -0,0 +1,47 @@
+use super::{Target, exported_name, targets};
+use nom::error::Error;
+use nom::{Finish, Parser};
+
+#[test]
+fn test_targets() {
+    let (_, exposition) = crate::exposition::<_, Error<_>>
+        .parse(concat!(
+            "a{job=\"x\",instance=\"i\\\"\"} 1\n",
+            "a{instance=\"j\",job=\"x\"} 2\n",
+            "b{job=\"x\",instance=\"i\\\"\"} 3\n",
+            "b 4\n",
+            "# EOF\n",
+        ))
+        .finish()
+        .unwrap();
+    assert_eq!(
+        targets(&exposition),
+        [
+            Target {
+                job: Some("x".to_owned()),
+                instance: Some("i\"".to_owned()),
+            },
+            Target {
+                job: Some("x".to_owned()),
+                instance: Some("j".to_owned()),
+            },
+            Target::default(),
+        ],
+    );
+}
+
+#[test]
+fn test_exported_name() {
+    let (_, exposition) = crate::exposition::<_, Error<_>>
+        .parse("a{job=\"x\",exported_job=\"y\"} 1\nb{instance=\"i\"} 2\n# EOF\n")
+        .finish()
+        .unwrap();
+    let samples = exposition
+        .metricset
+        .metricfamily
+        .iter()
+        .flat_map(|metricfamily| metricfamily.samples())
+        .collect::<Vec<_>>();
+    assert_eq!(exported_name(samples[0], "job"), "exported_exported_job");
+    assert_eq!(exported_name(samples[1], "instance"), "exported_instance");
+}
@@ -37,6 +37,84 @@ fn test_number(#[case] input: &str) {
     check(crate::number, input, input);
 }
 
+#[rstest::rstest]
+#[case("23", 23.)]
+#[case("0042", 42.)]
+#[case("1341298465647914", 1341298465647914.)]
+#[case("03.123421", 3.123421)]
+#[case("1.89e-7", 1.89e-7)]
+#[case("-1.5", -1.5)]
+#[case("+Inf", f64::INFINITY)]
+#[case("-inf", f64::NEG_INFINITY)]
+#[case("Infinity", f64::INFINITY)]
+#[case("-INFINITY", f64::NEG_INFINITY)]
+fn test_number_value(#[case] input: &str, #[case] expected: f64) {
+    check(crate::number_value, input, expected);
+    assert_eq!(crate::parse_number(input), Some(expected));
+}
+
+#[rstest::rstest]
+#[case("NaN")]
+#[case("nan")]
+fn test_number_value_nan(#[case] input: &str) {
+    assert!(crate::parse_number(input).unwrap().is_nan());
+}
+
+#[rstest::rstest]
+#[case("0", 0, 0)]
+#[case("1520879607.789", 1520879607, 789_000_000)]
+#[case("1520879607.123456789", 1520879607, 123_456_789)]
+#[case("1520879607.1234567899", 1520879607, 123_456_789)]
+#[case("1.5e9", 1_500_000_000, 0)]
+#[case("15e-1", 1, 500_000_000)]
+#[case(".5", 0, 500_000_000)]
+#[case("-1.5", -2, 500_000_000)]
+#[case("-2", -2, 0)]
+#[case("+3.", 3, 0)]
+#[case("0e999999999", 0, 0)]
+fn test_timestamp_value(#[case] input: &str, #[case] seconds: i64, #[case] nanos: u32) {
+    let expected = crate::Timestamp { seconds, nanos };
+    check(crate::timestamp_value, input, expected);
+    assert_eq!(crate::Timestamp::parse(input), Some(expected));
+}
+
+#[test]
+fn test_timestamp_order() {
+    let timestamps = [
+        "-1.5",
+        "-1",
+        "0",
+        "1520879607.123456789",
+        "1520879607.12345679",
+    ]
+    .map(|s| crate::Timestamp::parse(s).unwrap());
+    assert!(timestamps.is_sorted());
+    assert_eq!(crate::Timestamp::parse("1e19"), None);
+    assert_eq!(crate::Timestamp::parse("1 "), None);
+}
+
+#[rstest::rstest]
+#[case("")]
+#[case("1 ")]
+#[case("Infinite")]
+fn test_parse_number_error(#[case] input: &str) {
+    assert_eq!(crate::parse_number(input), None);
+}
+
+#[rstest::rstest]
+#[case("0042", "42")]
+#[case("1.890E-07", "1.89e-7")]
+#[case("0.0001", "0.0001")]
+#[case("1e21", "1e21")]
+#[case("1e20", "100000000000000000000")]
+#[case("-0.50", "-0.5")]
+#[case("+inf", "+Inf")]
+#[case("-Infinity", "-Inf")]
+#[case("nan", "NaN")]
+fn test_normalize_number(#[case] input: &str, #[case] expected: &str) {
+    assert_eq!(crate::normalize_number(input).as_deref(), Some(expected));
+}
+
 #[rstest::rstest]
 fn test_testdata(
     #[base_dir = "./OpenMetrics/tests/testdata/parsers"]
@@ -65,6 +143,327 @@ fn test_testdata(
         .finish();
 
     if test.should_parse {
-        exposition.unwrap();
+        exposition.clone().unwrap();
+    }
+
+    let bytes = fs::read(path.join(&test.file)).unwrap();
+    let exposition_bytes = crate::exposition_bytes::<Error<_>>(&bytes).finish();
+    assert_eq!(
+        exposition_bytes.ok(),
+        exposition
+            .ok()
+            .map(|(rest, exposition)| (rest.as_bytes(), exposition.map_input(str::as_bytes))),
+    );
+}
+
+#[rstest::rstest]
+#[case("# TYPE a histogram\na_bucket{le=\"+Inf\"} 1 # {b=\"c\"} 1 1.5\na_count 1\n# EOF\n")]
+#[case("# HELP a \\n\n# UNIT a_seconds seconds\na_seconds{b=\"\\\"\"} NaN -1.5e3\n# EOF\n")]
+#[case("# TYPE a counter\na_total 1\n# TYPE b gauge\nb 2\na_total 3\n# EOF\n")]
+fn test_no_panic(#[case] input: &str) {
+    no_panic(input.as_bytes());
+}
+
+#[rstest::rstest]
+fn test_no_panic_testdata(
+    #[base_dir = "./OpenMetrics/tests/testdata/parsers"]
+    #[files("*")]
+    path: PathBuf,
+) {
+    let input = fs::read_dir(&path)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .find(|path| path.extension().is_none_or(|extension| extension != "json"))
+        .map_or_else(Vec::new, |path| fs::read(path).unwrap());
+    no_panic(&input);
+}
+
+// Mutations of `input`, parsed by the entry points taking untrusted input. Nothing is checked
+// but that no parser panics; the mutations are seeded, so a failure reproduces.
+fn no_panic(input: &[u8]) {
+    const BYTES: &[u8] = b"# \n\"{}=,.eE+-_019aIfN\t\\\xff";
+
+    // xorshift64
+    let mut state = input.len() as u64 + 0x9e37_79b9_7f4a_7c15;
+    let mut next = |n: usize| {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        state as usize % n.max(1)
+    };
+    for _ in 0..200 {
+        let mut bytes = input.to_vec();
+        for _ in 0..=next(4) {
+            let at = next(bytes.len() + 1);
+            match next(4) {
+                0 => bytes.insert(at, BYTES[next(BYTES.len())]),
+                1 if at < bytes.len() => {
+                    bytes.remove(at);
+                }
+                2 => {
+                    let end = (at + next(16)).min(bytes.len());
+                    let duplicate = bytes[at..end].to_vec();
+                    bytes.splice(at..at, duplicate);
+                }
+                _ => bytes.truncate(at),
+            }
+        }
+
+        let _ = crate::exposition_bytes::<Error<_>>(&bytes);
+        let Ok(input) = std::str::from_utf8(&bytes) else {
+            continue;
+        };
+        if let Ok((_, exposition)) = crate::exposition::<_, Error<_>>(input) {
+            for metricfamily in &exposition.metricset.metricfamily {
+                let _ = crate::model::group_metric_points(metricfamily, metricfamily.metric_type());
+                for timestamp in metricfamily.samples().filter_map(|sample| sample.timestamp) {
+                    let _ = crate::Timestamp::parse(timestamp).map(|t| t.to_string());
+                }
+            }
+            let _ = crate::model::metric_families(exposition.metricset.value);
+        }
+        if let Err(nom::Err::Error(e) | nom::Err::Failure(e)) =
+            crate::exposition::<_, crate::error::OpenMetricsError<_>>(input)
+        {
+            let _ = crate::error::diagnose(&input, &e).to_string();
+        }
+        let _ = crate::lenient::Lenient::default()
+            .merge_interleaved_families(true)
+            .parse(input);
+        let _ = crate::options::ParseOptions::federation()
+            .normalize_numbers(true)
+            .comments(crate::options::Comments::Keep)
+            .exposition::<_, Error<_>>(input);
+        let _ = crate::prometheus::exposition::<_, Error<_>>(input);
+    }
+}
+
+#[test]
+fn test_exposition_bytes() {
+    let input = b"# HELP a \\\xff\\n\na{b=\"\xfe\\\"\xe2\x82\xac\"} 1\n# EOF\n";
+    let (_, exposition) = crate::exposition_bytes::<Error<_>>(input).finish().unwrap();
+    let metricfamily = &exposition.metricset.value.metricfamily[0];
+    let metric_descriptor = &metricfamily.metric_descriptor[0];
+    let crate::MetricDescriptor::Help { escaped_string, .. } = &metric_descriptor.value else {
+        panic!("{metric_descriptor:?}");
+    };
+    assert_eq!(&*escaped_string.unescape_bytes(), b"\\\xff\n");
+    let sample = metricfamily.samples().next().unwrap();
+    let labels = sample.labels.as_ref().unwrap();
+    assert_eq!(
+        &*labels.label[0].escaped_string.unescape_bytes(),
+        b"\xfe\"\xe2\x82\xac",
+    );
+}
+
+#[test]
+fn test_into_owned() {
+    let input = String::from(
+        r#"# TYPE a counter
+# HELP a Help.
+a_total{job="x\n"} 1 0 # {trace_id="t"} 1
+# EOF
+"#,
+    );
+    let (_, exposition) = crate::exposition::<_, Error<_>>
+        .parse(input.as_str())
+        .finish()
+        .unwrap();
+    let owned = exposition.into_owned();
+    drop(input);
+
+    let metricfamily = &owned.metricset.value.metricfamily[0];
+    assert_eq!(
+        metricfamily.raw,
+        "# TYPE a counter\n# HELP a Help.\na_total{job=\"x\\n\"} 1 0 # {trace_id=\"t\"} 1\n"
+    );
+    assert_eq!(metricfamily.metric_type(), crate::MetricType::Counter);
+    let sample = metricfamily.samples().next().unwrap();
+    assert_eq!(sample.metricname, "a_total");
+    let labels = sample.labels.as_ref().unwrap();
+    assert_eq!(labels.label[0].escaped_string.unescape(), "x\n");
+    assert_eq!(sample.timestamp.as_deref(), Some("0"));
+}
+
+#[test]
+fn test_map_input() {
+    let input = r#"# TYPE a counter
+a_total{job="x"} 1
+# EOF
+"#;
+    let (_, exposition) = crate::exposition::<_, Error<_>>
+        .parse(input)
+        .finish()
+        .unwrap();
+    let mut count = 0;
+    let exposition = exposition.map_input(|s: &str| {
+        count += 1;
+        std::sync::Arc::<str>::from(s)
+    });
+    // metricset, family, descriptor (raw, metricname, type), metric, sample (raw, metricname,
+    // labels, label (raw, name, value, fragment (raw, normal)), number), eof
+    assert_eq!(count, 16);
+    let metricfamily = &exposition.metricset.value.metricfamily[0];
+    let sample = metricfamily.samples().next().unwrap();
+    assert_eq!(&*sample.metricname, "a_total");
+}
+
+#[rstest::rstest]
+#[case("", "", true)]
+#[case("abc", "abc", true)]
+#[case(r#"a\nb"#, "a\nb", false)]
+#[case(r#"\"\\"#, "\"\\", false)]
+fn test_unescape(#[case] input: &str, #[case] expected: &str, #[case] borrowed: bool) {
+    let (_, escaped_string) = crate::escaped_string::<_, Error<_>>
+        .parse(input)
+        .finish()
+        .unwrap();
+    let unescaped = escaped_string.unescape();
+    assert_eq!(unescaped, expected);
+    assert_eq!(matches!(unescaped, std::borrow::Cow::Borrowed(_)), borrowed);
+}
+
+#[test]
+fn test_help() {
+    let (_, metric_descriptor) = crate::metric_descriptor::<_, Error<_>>
+        .parse("# HELP a A\\B.\n")
+        .finish()
+        .unwrap();
+    assert_eq!(metric_descriptor.help().as_deref(), Some("A\\B."));
+    let (_, metric_descriptor) = crate::metric_descriptor::<_, Error<_>>
+        .parse("# TYPE a gauge\n")
+        .finish()
+        .unwrap();
+    assert_eq!(metric_descriptor.help(), None);
+}
+
+#[rstest::rstest]
+#[case(r#"a "quoted" b"#, r#"a "quoted" b"#)]
+#[case(r#"a \"b\""#, r#"a \"b\""#)]
+#[case(r#"a\\b\nc"#, "a\\b\nc")]
+fn test_help_escaped_string(#[case] input: &str, #[case] expected: &str) {
+    let (_, escaped_string) = crate::help_escaped_string::<_, Error<_>>
+        .parse(input)
+        .finish()
+        .unwrap();
+    assert_eq!(escaped_string.unescape(), expected);
+}
+
+#[test]
+fn test_escape_help() {
+    assert_eq!(crate::escape_help("a\\b\n\"c\""), r#"a\\b\n"c""#);
+    assert_eq!(crate::escape("a\\b\n\"c\""), r#"a\\b\n\"c\""#);
+}
+
+#[rstest::rstest]
+#[case("# HELP a Say \"hi\".\n", true)]
+#[case("a{b=\"say \"hi\"\"} 1\n", false)]
+fn test_bare_dquote(#[case] input: &str, #[case] accepted: bool) {
+    let parsed = crate::metricfamily::<_, Error<_>>.parse(input).finish();
+    assert_eq!(parsed.is_ok_and(|(rest, _)| rest.is_empty()), accepted);
+}
+
+#[rstest::rstest]
+#[case("# EOF")]
+#[case("# TYPE a gauge\na{} 1e3 01\n# EOF\n")]
+#[case("a{b=\"c\\n\"} +Inf # {} 1.0\n# EOF\n")]
+fn test_reconstruct(#[case] input: &str) {
+    let (_, exposition) = crate::exposition::<_, Error<_>>
+        .parse(input)
+        .finish()
+        .unwrap();
+    assert_eq!(exposition.reconstruct(), input);
+    assert_eq!(&input[exposition.eof_span()], exposition.eof);
+    assert_eq!(exposition.had_trailing_newline(), input.ends_with('\n'));
+}
+
+#[test]
+fn test_raw() {
+    use crate::Raw;
+
+    let input = "# TYPE a gauge\na 1\n# TYPE b gauge\nb 2\n# EOF\n";
+    let (_, exposition) = crate::exposition::<_, Error<_>>
+        .parse(input)
+        .finish()
+        .unwrap();
+    let spliced = exposition
+        .metricset
+        .metricfamily
+        .iter()
+        .map(|metricfamily| match metricfamily.raw() {
+            "# TYPE a gauge\na 1\n" => "# TYPE a counter\na_total 1\n",
+            raw => raw,
+        })
+        .chain([exposition.eof])
+        .collect::<String>();
+    assert_eq!(
+        spliced,
+        "# TYPE a counter\na_total 1\n# TYPE b gauge\nb 2\n# EOF\n",
+    );
+}
+
+#[test]
+fn test_range() {
+    fn check(input: &str, metricfamily: &crate::Metricfamily<&str>) {
+        let sample = &metricfamily.metric[0].sample[0];
+        assert_eq!(&input[sample.range.clone()], sample.raw);
+        let label = &sample.labels.as_ref().unwrap().label[0];
+        assert_eq!(&input[label.range.clone()], label.raw);
+        let exemplar = sample.exemplar.as_ref().unwrap();
+        assert_eq!(&input[exemplar.range.clone()], exemplar.raw);
     }
+
+    let input = "# TYPE a gauge\na{b=\"c\"} 1 # {d=\"e\"} 2\n# EOF\n";
+    let (_, exposition) = crate::exposition::<_, Error<_>>
+        .parse(input)
+        .finish()
+        .unwrap();
+    let metricfamily = &exposition.metricset.metricfamily[0];
+    assert_eq!(&input[metricfamily.range.clone()], metricfamily.raw);
+    check(input, metricfamily);
+
+    let input = &input[..input.find("# EOF").unwrap()];
+    let (_, metricfamily) = crate::metricfamily::<_, Error<_>>
+        .parse(input)
+        .finish()
+        .unwrap();
+    check(input, &metricfamily);
+}
+
+#[rstest::rstest]
+#[case("0")]
+#[case("1.5")]
+#[case("-1.5")]
+#[case("-2")]
+#[case("1700000000.000000001")]
+fn test_timestamp_display(#[case] input: &str) {
+    let timestamp = crate::Timestamp::parse(input).unwrap();
+    assert_eq!(timestamp.to_string(), input);
+}
+
+#[rstest::rstest]
+#[case(i64::MIN, 0, "-9223372036854775808")]
+#[case(i64::MAX, 999_999_999, "9223372036854775807.999999999")]
+#[case(0, 1_500_000_000, "1.5")]
+#[case(-1, u32::MAX, "3.294967295")]
+fn test_timestamp_display_out_of_range(
+    #[case] seconds: i64,
+    #[case] nanos: u32,
+    #[case] expected: &str,
+) {
+    let timestamp = crate::Timestamp { seconds, nanos };
+    assert_eq!(timestamp.to_string(), expected);
+}
+
+#[test]
+fn test_timestamp_from_system_time() {
+    let time = std::time::UNIX_EPOCH
+        .checked_add(std::time::Duration::new(1_700_000_000, 1))
+        .unwrap();
+    assert_eq!(
+        crate::Timestamp::from_system_time(time).to_string(),
+        "1700000000.000000001",
+    );
+    let time = std::time::UNIX_EPOCH - std::time::Duration::from_millis(1500);
+    assert_eq!(crate::Timestamp::from_system_time(time).to_string(), "-1.5");
 }
@@ -0,0 +1,792 @@
+use crate::diagnostic::Diagnostic;
+use crate::target;
+use crate::{Exposition, MetricDescriptor, MetricType, Metricfamily, Sample, Spanned};
+use nom::{Finish, Input, Offset, Parser};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::ops::Range;
+
+// Transforms rewrite the exposition as text: every change is an edit of the original input,
+// everything else is copied verbatim. The result is parsed again before it is returned.
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Error {
+    // the transformed exposition does not parse; the span refers to the output
+    Invalid(Diagnostic),
+    // the transform cannot be applied to the input
+    Unsupported(String),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Invalid(diagnostic) => write!(f, "{}", diagnostic.message),
+            Self::Unsupported(message) => write!(f, "{message}"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+// Renames every family by prepending `prefix` to all metricnames, in descriptors and samples.
+pub fn prefix<I>(exposition: &Exposition<I>, prefix: &str) -> Result<String, Error>
+where
+    I: AsRef<str> + Input + Offset,
+{
+    let origin = &exposition.metricset.raw;
+    let mut edits = Edits::default();
+    for metricfamily in &exposition.metricset.metricfamily {
+        for metric_descriptor in &metricfamily.metric_descriptor {
+            edits.insert(origin, metric_descriptor.metricname(), prefix);
+        }
+        for metric in &metricfamily.metric {
+            for sample in &metric.sample {
+                edits.insert(origin, &sample.metricname, prefix);
+            }
+        }
+    }
+    edits.apply(exposition)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Conversion {
+    pub from: &'static str,
+    pub to: &'static str,
+    // a value in `to` is the value in `from` times `multiplier` divided by `divisor`, so that
+    // e.g. 9 milliseconds are 0.009 seconds rather than `9. * 1e-3`
+    pub multiplier: f64,
+    pub divisor: f64,
+}
+
+pub const CONVERSIONS: &[Conversion] = &[
+    Conversion {
+        from: "nanoseconds",
+        to: "seconds",
+        multiplier: 1.,
+        divisor: 1e9,
+    },
+    Conversion {
+        from: "microseconds",
+        to: "seconds",
+        multiplier: 1.,
+        divisor: 1e6,
+    },
+    Conversion {
+        from: "milliseconds",
+        to: "seconds",
+        multiplier: 1.,
+        divisor: 1e3,
+    },
+    Conversion {
+        from: "minutes",
+        to: "seconds",
+        multiplier: 60.,
+        divisor: 1.,
+    },
+    Conversion {
+        from: "hours",
+        to: "seconds",
+        multiplier: 3600.,
+        divisor: 1.,
+    },
+    Conversion {
+        from: "kilobytes",
+        to: "bytes",
+        multiplier: 1e3,
+        divisor: 1.,
+    },
+    Conversion {
+        from: "megabytes",
+        to: "bytes",
+        multiplier: 1e6,
+        divisor: 1.,
+    },
+    Conversion {
+        from: "gigabytes",
+        to: "bytes",
+        multiplier: 1e9,
+        divisor: 1.,
+    },
+    Conversion {
+        from: "percent",
+        to: "ratio",
+        multiplier: 1.,
+        divisor: 100.,
+    },
+];
+
+// Converts families whose name ends with the `from` unit of a conversion, e.g. `foo_milliseconds`
+// becomes `foo_seconds` and its values are scaled by `multiplier / divisor`. Counts (`_count`,
+// `_gcount`, bucket values) and `_created` timestamps are left untouched, bucket bounds and
+// exemplar values are converted as well.
+pub fn convert_units<I>(
+    exposition: &Exposition<I>,
+    conversions: &[Conversion],
+) -> Result<String, Error>
+where
+    I: AsRef<str> + Input + Offset,
+{
+    let origin = &exposition.metricset.raw;
+    let convert = |name: &str| {
+        conversions.iter().find_map(|conversion| {
+            let base = name.strip_suffix(conversion.from)?.strip_suffix('_')?;
+            Some((format!("{base}_{}", conversion.to), conversion))
+        })
+    };
+    let mut edits = Edits::default();
+    for metricfamily in &exposition.metricset.metricfamily {
+        for metric_descriptor in &metricfamily.metric_descriptor {
+            let metricname = metric_descriptor.metricname();
+            if let Some((name, conversion)) = convert(metricname.as_ref()) {
+                edits.replace(origin, metricname, name);
+                if let MetricDescriptor::Unit {
+                    metricname_char, ..
+                } = &metric_descriptor.value
+                {
+                    edits.replace(origin, metricname_char, conversion.to.to_owned());
+                }
+            }
+        }
+        for sample in metricfamily.samples() {
+            let family = metricfamily.family_name(sample);
+            let Some((name, conversion)) = convert(family) else {
+                continue;
+            };
+            let suffix = &sample.metricname.as_ref()[family.len()..];
+            edits.replace(origin, &sample.metricname, format!("{name}{suffix}"));
+            if ["", "_total", "_sum", "_gsum"].contains(&suffix) {
+                edits.scale(origin, &sample.number, conversion)?;
+            }
+            if let (Some(labels), "_bucket") = (&sample.labels, suffix) {
+                for label in &labels.label {
+                    if label.label_name.as_ref() == "le" {
+                        edits.scale(origin, &label.escaped_string.raw, conversion)?;
+                    }
+                }
+            }
+            if let Some(exemplar) = &sample.exemplar {
+                edits.scale(origin, &exemplar.number, conversion)?;
+            }
+        }
+    }
+    edits.apply(exposition)
+}
+
+// `foo` counter -> `foo` gauge: `foo_total` becomes `foo` without its exemplar, `foo_created` is
+// dropped.
+pub fn counter_to_gauge<I>(exposition: &Exposition<I>, name: &str) -> Result<String, Error>
+where
+    I: AsRef<str> + Input + Offset,
+{
+    retype(exposition, name, MetricType::Counter, |metricfamily| {
+        let mut text = descriptors(metricfamily, name, "gauge", true);
+        let mut rest = String::new();
+        for sample in metricfamily.samples() {
+            match suffix(metricfamily, name, sample) {
+                Some("_total") => text.push_str(&without_exemplar(sample, name)),
+                Some(_) => {}
+                None => rest.push_str(sample.raw.as_ref()),
+            }
+        }
+        text + &rest
+    })
+}
+
+// `foo` histogram -> `foo_count` and `foo_sum` counters, buckets are dropped.
+pub fn histogram_to_counters<I>(exposition: &Exposition<I>, name: &str) -> Result<String, Error>
+where
+    I: AsRef<str> + Input + Offset,
+{
+    retype(exposition, name, MetricType::Histogram, |metricfamily| {
+        let [mut count, mut sum] = ["_count", "_sum"]
+            .map(|suffix| descriptors(metricfamily, &format!("{name}{suffix}"), "counter", false));
+        let mut rest = String::new();
+        for sample in metricfamily.samples() {
+            match suffix(metricfamily, name, sample) {
+                Some("_count") => count.push_str(&rename(
+                    &sample.raw,
+                    &sample.metricname,
+                    &format!("{name}_count_total"),
+                )),
+                Some("_sum") => sum.push_str(&rename(
+                    &sample.raw,
+                    &sample.metricname,
+                    &format!("{name}_sum_total"),
+                )),
+                Some("_created") => {
+                    count.push_str(&rename(
+                        &sample.raw,
+                        &sample.metricname,
+                        &format!("{name}_count_created"),
+                    ));
+                    sum.push_str(&rename(
+                        &sample.raw,
+                        &sample.metricname,
+                        &format!("{name}_sum_created"),
+                    ));
+                }
+                Some(_) => {}
+                None => rest.push_str(sample.raw.as_ref()),
+            }
+        }
+        count + &sum + &rest
+    })
+}
+
+// `foo` summary -> `foo` gauge of the given quantile, without the `quantile` label.
+pub fn summary_to_gauge<I>(
+    exposition: &Exposition<I>,
+    name: &str,
+    quantile: f64,
+) -> Result<String, Error>
+where
+    I: AsRef<str> + Input + Offset,
+{
+    retype(exposition, name, MetricType::Summary, |metricfamily| {
+        let mut text = descriptors(metricfamily, name, "gauge", true);
+        let mut rest = String::new();
+        for sample in metricfamily.samples() {
+            match suffix(metricfamily, name, sample) {
+                Some("") => {
+                    let Some(sample_labels) = &sample.labels else {
+                        continue;
+                    };
+                    let (quantiles, labels) = sample_labels
+                        .label
+                        .iter()
+                        .partition::<Vec<_>, _>(|label| label.label_name.as_ref() == "quantile");
+                    if !quantiles
+                        .iter()
+                        .any(|label| label.escaped_string.raw.as_ref().parse() == Ok(quantile))
+                    {
+                        continue;
+                    }
+                    let labels = labels
+                        .iter()
+                        .map(|label| label.raw.as_ref())
+                        .collect::<Vec<_>>()
+                        .join(",");
+                    let labels = if labels.is_empty() {
+                        labels
+                    } else {
+                        format!("{{{labels}}}")
+                    };
+                    let line = sample.raw.as_ref();
+                    let start = sample_labels.range.start - sample.range.start;
+                    let end = sample_labels.range.end - sample.range.start;
+                    text.push_str(&line[..start]);
+                    text.push_str(&labels);
+                    text.push_str(&line[end..]);
+                }
+                Some(_) => {}
+                None => rest.push_str(sample.raw.as_ref()),
+            }
+        }
+        text + &rest
+    })
+}
+
+// Inserts the bounds of `schedule` missing from any series of the histogram `name`. A new bucket
+// gets the count of the next lower bucket (or 0), which keeps the buckets cumulative.
+pub fn fill_buckets<I>(
+    exposition: &Exposition<I>,
+    name: &str,
+    schedule: &[f64],
+) -> Result<String, Error>
+where
+    I: AsRef<str> + Input + Offset,
+{
+    let origin = &exposition.metricset.raw;
+    let metricfamily = family(
+        exposition,
+        name,
+        &[MetricType::Histogram, MetricType::Gaugehistogram],
+    )?;
+    let mut schedule = schedule
+        .iter()
+        .copied()
+        .filter(|bound| bound.is_finite())
+        .collect::<Vec<_>>();
+    schedule.sort_by(f64::total_cmp);
+
+    // labels other than `le` -> buckets
+    let mut series = BTreeMap::<_, Vec<_>>::new();
+    for sample in metricfamily.samples() {
+        if suffix(metricfamily, name, sample) != Some("_bucket") {
+            continue;
+        }
+        let (le, labels) = bucket(sample)?;
+        series.entry(labels).or_default().push((le, sample));
+    }
+
+    let mut edits = Edits::default();
+    for (labels, buckets) in series {
+        let labels = labels
+            .iter()
+            .map(|label| format!("{label},"))
+            .collect::<String>();
+        for bound in &schedule {
+            if buckets.iter().any(|(le, _)| le == bound) {
+                continue;
+            }
+            let index = buckets.partition_point(|(le, _)| le < bound);
+            let value = index
+                .checked_sub(1)
+                .map_or("0", |index| buckets[index].1.number.as_ref());
+            let (_, sample) = buckets[index.min(buckets.len() - 1)];
+            let timestamp = sample
+                .timestamp
+                .as_ref()
+                .map(|timestamp| format!(" {}", timestamp.as_ref()))
+                .unwrap_or_default();
+            let line = format!("{name}_bucket{{{labels}le=\"{bound}\"}} {value}{timestamp}\n");
+            if index < buckets.len() {
+                edits.insert(origin, &sample.raw, &line);
+            } else {
+                edits.insert_after(origin, &sample.raw, &line);
+            }
+        }
+    }
+    edits.apply(exposition)
+}
+
+// Merges adjacent buckets of the histogram `name` into the coarser `bounds` by dropping the
+// buckets in between. Buckets are never split: every series must already have all `bounds`.
+pub fn rebucket<I>(exposition: &Exposition<I>, name: &str, bounds: &[f64]) -> Result<String, Error>
+where
+    I: AsRef<str> + Input + Offset,
+{
+    let origin = &exposition.metricset.raw;
+    let metricfamily = family(
+        exposition,
+        name,
+        &[MetricType::Histogram, MetricType::Gaugehistogram],
+    )?;
+
+    let mut series = BTreeMap::<_, Vec<_>>::new();
+    for sample in metricfamily.samples() {
+        if suffix(metricfamily, name, sample) != Some("_bucket") {
+            continue;
+        }
+        let (le, labels) = bucket(sample)?;
+        series.entry(labels).or_default().push((le, &sample.raw));
+    }
+
+    let mut edits = Edits::default();
+    for (labels, buckets) in series {
+        if let Some(bound) = bounds
+            .iter()
+            .find(|bound| bound.is_finite() && !buckets.iter().any(|(le, _)| le == *bound))
+        {
+            return Err(Error::Unsupported(format!(
+                "`{name}_bucket{{{}}}` has no bucket to merge into `le=\"{bound}\"`",
+                labels.join(","),
+            )));
+        }
+        for (le, raw) in buckets {
+            if le.is_finite() && !bounds.contains(&le) {
+                edits.replace(origin, raw, String::new());
+            }
+        }
+    }
+    edits.apply(exposition)
+}
+
+// Shortens sample label values longer than `max` characters to exactly `max`: a prefix, `…` and
+// the FNV-1a hash of the whole value in 8 hex digits. The hash keeps series distinct that only
+// differ after the prefix, and a value truncates alike in every scrape. Fails if two distinct
+// series become the same, e.g. by a hash collision or a value already in the truncated form.
+pub fn truncate_label_values<I>(exposition: &Exposition<I>, max: usize) -> Result<String, Error>
+where
+    I: AsRef<str> + Input + Offset,
+{
+    const MARKER: char = '…';
+    const HASH: usize = 8;
+    if max <= HASH {
+        return Err(Error::Unsupported(format!(
+            "cannot truncate label values to {max} characters"
+        )));
+    }
+    let origin = &exposition.metricset.raw;
+    let mut edits = Edits::default();
+    // truncated series -> series as written
+    let mut series = BTreeMap::new();
+    for metricfamily in &exposition.metricset.metricfamily {
+        for sample in metricfamily.samples() {
+            let mut labels = Vec::new();
+            for label in sample.labels.iter().flat_map(|labels| &labels.label) {
+                let escaped_string = &label.escaped_string;
+                let value = escaped_string.unescape();
+                let name = label.label_name.as_ref();
+                if value.chars().count() <= max {
+                    labels.push((name, value.into_owned()));
+                    continue;
+                }
+                let hash = value.bytes().fold(0x811c9dc5_u32, |hash, byte| {
+                    (hash ^ u32::from(byte)).wrapping_mul(0x01000193)
+                });
+                let prefix = value.chars().take(max - 1 - HASH).collect::<String>();
+                let truncated = format!("{prefix}{MARKER}{hash:08x}");
+                edits.replace(origin, &escaped_string.raw, crate::escape(&truncated));
+                labels.push((name, truncated));
+            }
+            let written = sample.labels.as_ref().map(|labels| labels.raw.as_ref());
+            let key = (sample.metricname.as_ref(), labels);
+            match series.insert(key, written) {
+                Some(other) if other != written => {
+                    return Err(Error::Unsupported(format!(
+                        "`{}{}` and `{}{}` collide once truncated",
+                        sample.metricname.as_ref(),
+                        other.unwrap_or_default(),
+                        sample.metricname.as_ref(),
+                        written.unwrap_or_default(),
+                    )));
+                }
+                _ => {}
+            }
+        }
+    }
+    edits.apply(exposition)
+}
+
+// How `set_target_labels` resolves a target label that a sample exposes as well.
+// https://prometheus.io/docs/prometheus/latest/configuration/configuration/#scrape_config
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum LabelConflict {
+    // `honor_labels: false`: the exposed label is moved to `exported_<name>`, see
+    // `target::exported_name`
+    #[default]
+    Export,
+    // `honor_labels: true`: the exposed label is kept and the target label is not set
+    Honor,
+    // the exposed label is dropped
+    Overwrite,
+}
+
+// Sets the target `labels` on every sample, as a scraper attaches its target, after the exposed
+// labels. As in Prometheus, an empty target label is not set, and `LabelConflict::Export` drops
+// an empty exposed label rather than moving it. Exemplars are left as they are.
+pub fn set_target_labels<I>(
+    exposition: &Exposition<I>,
+    labels: &[(&str, &str)],
+    conflict: LabelConflict,
+) -> Result<String, Error>
+where
+    I: AsRef<str> + Input + Offset,
+{
+    let origin = &exposition.metricset.raw;
+    let mut edits = Edits::default();
+    for metricfamily in &exposition.metricset.metricfamily {
+        for sample in metricfamily.samples() {
+            let Some(written) = target_labels(sample, labels, conflict) else {
+                continue;
+            };
+            let written = format!("{{{}}}", written.join(","));
+            match &sample.labels {
+                Some(labels) => edits.replace(origin, &labels.raw, written),
+                None => edits.insert_after(origin, &sample.metricname, &written),
+            }
+        }
+    }
+    edits.apply(exposition)
+}
+
+// The labels of `sample` as written once the target `labels` are set, see `set_target_labels`,
+// or `None` if they are left as they are.
+pub(crate) fn target_labels<I>(
+    sample: &Sample<I>,
+    labels: &[(&str, &str)],
+    conflict: LabelConflict,
+) -> Option<Vec<String>>
+where
+    I: AsRef<str> + Input + Offset,
+{
+    let exposed = sample
+        .labels
+        .iter()
+        .flat_map(|labels| &labels.label)
+        .collect::<Vec<_>>();
+    let mut written = Vec::new();
+    let mut changed = false;
+    for label in &exposed {
+        let name = label.label_name.as_ref();
+        let targeted = labels.iter().any(|(target, _)| *target == name);
+        match conflict {
+            _ if !targeted => written.push(label.raw.as_ref().to_owned()),
+            LabelConflict::Honor => written.push(label.raw.as_ref().to_owned()),
+            LabelConflict::Export if !label.escaped_string.raw.as_ref().is_empty() => {
+                let exported = target::exported_name(sample, name);
+                written.push(rename(&label.raw, &label.label_name, &exported));
+                changed = true;
+            }
+            LabelConflict::Export | LabelConflict::Overwrite => changed = true,
+        }
+    }
+    for (name, value) in labels {
+        let honored = conflict == LabelConflict::Honor
+            && exposed
+                .iter()
+                .any(|label| label.label_name.as_ref() == *name);
+        if value.is_empty() || honored {
+            continue;
+        }
+        written.push(format!("{name}=\"{}\"", crate::escape(value)));
+        changed = true;
+    }
+    changed.then_some(written)
+}
+
+// Sets `job` and `instance` on every sample, see `set_target_labels`.
+pub fn set_target<I>(
+    exposition: &Exposition<I>,
+    job: &str,
+    instance: &str,
+    honor_labels: bool,
+) -> Result<String, Error>
+where
+    I: AsRef<str> + Input + Offset,
+{
+    let conflict = if honor_labels {
+        LabelConflict::Honor
+    } else {
+        LabelConflict::Export
+    };
+    set_target_labels(
+        exposition,
+        &[(target::JOB, job), (target::INSTANCE, instance)],
+        conflict,
+    )
+}
+
+// The `le` bound of a bucket and its other labels as written.
+fn bucket<I>(sample: &Sample<I>) -> Result<(f64, Vec<&str>), Error>
+where
+    I: AsRef<str>,
+{
+    let mut le = None;
+    let mut labels = Vec::new();
+    for label in sample.labels.iter().flat_map(|labels| &labels.label) {
+        if label.label_name.as_ref() == "le" {
+            le = Some(label.escaped_string.raw.as_ref());
+        } else {
+            labels.push(label.raw.as_ref());
+        }
+    }
+    let le = le.ok_or_else(|| {
+        Error::Unsupported(format!(
+            "`{}` has no `le` label",
+            sample.metricname.as_ref()
+        ))
+    })?;
+    let le = le
+        .parse()
+        .map_err(|_| Error::Unsupported(format!("invalid bucket bound `{le}`")))?;
+    Ok((le, labels))
+}
+
+// Replaces the family `name` of type `metric_type` with the output of `f`.
+fn retype<I, F>(
+    exposition: &Exposition<I>,
+    name: &str,
+    metric_type: MetricType,
+    f: F,
+) -> Result<String, Error>
+where
+    I: AsRef<str> + Input + Offset,
+    F: FnOnce(&Metricfamily<I>) -> String,
+{
+    let origin = &exposition.metricset.raw;
+    let metricfamily = family(exposition, name, &[metric_type])?;
+    let mut edits = Edits::default();
+    edits.replace(origin, &metricfamily.raw, f(metricfamily));
+    // a family merged by `options::ParseOptions::merge_repeated_families` continues in the later
+    // blocks it came from, which the output of `f` covers as well
+    for merged in &exposition.metricset.merged {
+        if merged.metricname.as_ref() == name {
+            edits.replace(origin, &merged.raw, String::new());
+        }
+    }
+    edits.apply(exposition)
+}
+
+// The family described as `name`, which must have one of `metric_types`.
+fn family<'a, I>(
+    exposition: &'a Exposition<I>,
+    name: &str,
+    metric_types: &[MetricType],
+) -> Result<&'a Spanned<I, Metricfamily<I>>, Error>
+where
+    I: AsRef<str>,
+{
+    let family = exposition
+        .metricset
+        .metricfamily
+        .iter()
+        .find(|metricfamily| {
+            metricfamily
+                .metric_descriptor
+                .first()
+                .is_some_and(|metric_descriptor| metric_descriptor.metricname().as_ref() == name)
+        })
+        .ok_or_else(|| Error::Unsupported(format!("`{name}` is not described")))?;
+    if !metric_types.contains(&family.metric_type()) {
+        let metric_types = metric_types
+            .iter()
+            .map(|metric_type| format!("{metric_type:?}").to_lowercase())
+            .collect::<Vec<_>>()
+            .join("/");
+        return Err(Error::Unsupported(format!(
+            "`{name}` is not a {metric_types}"
+        )));
+    }
+    Ok(family)
+}
+
+// The descriptors of `metricfamily` renamed to `name`, with a new TYPE.
+fn descriptors<I>(
+    metricfamily: &Metricfamily<I>,
+    name: &str,
+    metric_type: &str,
+    unit: bool,
+) -> String
+where
+    I: AsRef<str> + Input + Offset,
+{
+    let mut text = format!("# TYPE {name} {metric_type}\n");
+    for metric_descriptor in &metricfamily.metric_descriptor {
+        match &metric_descriptor.value {
+            MetricDescriptor::Type { .. } => {}
+            MetricDescriptor::Unit { .. } if !unit => {}
+            _ => text.push_str(&rename(
+                &metric_descriptor.raw,
+                metric_descriptor.metricname(),
+                name,
+            )),
+        }
+    }
+    text
+}
+
+// The sample's suffix if it belongs to the family `name`.
+fn suffix<'a, I>(
+    metricfamily: &'a Metricfamily<I>,
+    name: &str,
+    sample: &'a Sample<I>,
+) -> Option<&'a str>
+where
+    I: AsRef<str>,
+{
+    if metricfamily.family_name(sample) == name {
+        Some(&sample.metricname.as_ref()[name.len()..])
+    } else {
+        None
+    }
+}
+
+// `raw` with `metricname` (a part of it) replaced by `name`.
+fn rename<I>(raw: &I, metricname: &I, name: &str) -> String
+where
+    I: AsRef<str> + Input + Offset,
+{
+    let start = raw.offset(metricname);
+    let raw = raw.as_ref();
+    format!(
+        "{}{name}{}",
+        &raw[..start],
+        &raw[start + metricname.input_len()..],
+    )
+}
+
+// The line of `sample` renamed to `name`, ending at its value or timestamp: a gauge cannot have
+// an exemplar.
+fn without_exemplar<I>(sample: &Spanned<I, Sample<I>>, name: &str) -> String
+where
+    I: AsRef<str> + Input + Offset,
+{
+    let line = rename(&sample.raw, &sample.metricname, name);
+    if sample.exemplar.is_none() {
+        return line;
+    }
+    let last = sample.timestamp.as_ref().unwrap_or(&sample.number);
+    let end =
+        sample.raw.offset(last) + last.input_len() - sample.metricname.input_len() + name.len();
+    format!("{}\n", &line[..end])
+}
+
+#[derive(Default)]
+struct Edits(Vec<(Range<usize>, String)>);
+
+impl Edits {
+    // inserts `text` before `raw`
+    fn insert<I>(&mut self, origin: &I, raw: &I, text: &str)
+    where
+        I: Input + Offset,
+    {
+        let start = origin.offset(raw);
+        self.0.push((start..start, text.to_owned()));
+    }
+
+    // inserts `text` after `raw`
+    fn insert_after<I>(&mut self, origin: &I, raw: &I, text: &str)
+    where
+        I: Input + Offset,
+    {
+        let end = origin.offset(raw) + raw.input_len();
+        self.0.push((end..end, text.to_owned()));
+    }
+
+    fn replace<I>(&mut self, origin: &I, raw: &I, text: String)
+    where
+        I: Input + Offset,
+    {
+        let start = origin.offset(raw);
+        self.0.push((start..start + raw.input_len(), text));
+    }
+
+    // converts the number `raw` by `conversion`; infinities and NaN are kept as written
+    fn scale<I>(&mut self, origin: &I, raw: &I, conversion: &Conversion) -> Result<(), Error>
+    where
+        I: AsRef<str> + Input + Offset,
+    {
+        let value = raw
+            .as_ref()
+            .parse::<f64>()
+            .map_err(|_| Error::Unsupported(format!("invalid number `{}`", raw.as_ref())))?;
+        if value.is_finite() {
+            let value = value * conversion.multiplier / conversion.divisor;
+            self.replace(origin, raw, value.to_string());
+        }
+        Ok(())
+    }
+
+    fn apply<I>(mut self, exposition: &Exposition<I>) -> Result<String, Error>
+    where
+        I: AsRef<str>,
+    {
+        let source = exposition.metricset.raw.as_ref();
+        self.0.sort_by_key(|(range, _)| (range.start, range.end));
+        let mut output = String::with_capacity(source.len());
+        let mut position = 0;
+        for (range, text) in self.0 {
+            output.push_str(&source[position..range.start]);
+            output.push_str(&text);
+            position = range.end;
+        }
+        output.push_str(&source[position..]);
+        output.push_str("# EOF\n");
+
+        crate::exposition::<_, nom::error::Error<_>>
+            .parse(output.as_str())
+            .finish()
+            .map_err(|e| Error::Invalid(Diagnostic::from_error(&output.as_str(), &e)))?;
+        Ok(output)
+    }
+}
+
+#[cfg(test)]
+mod tests;
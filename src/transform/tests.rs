@@ -0,0 +1,436 @@
+use super::{Error, LabelConflict};
+use nom::error::Error as NomError;
+use nom::{Finish, Parser};
+
+fn check<'a, F>(f: F, input: &'a str) -> Result<String, Error>
+where
+    F: FnOnce(&crate::Exposition<&'a str>) -> Result<String, Error>,
+{
+    let (_, exposition) = crate::exposition::<_, NomError<_>>
+        .parse(input)
+        .finish()
+        .unwrap();
+    f(&exposition)
+}
+
+#[test]
+fn test_prefix() {
+    let input = r#"# TYPE a counter
+# HELP a Help.
+a_total{job="x"} 1
+a_created 0
+b 2
+# EOF
+"#;
+    assert_eq!(
+        check(|exposition| super::prefix(exposition, "myapp_"), input),
+        Ok(r#"# TYPE myapp_a counter
+# HELP myapp_a Help.
+myapp_a_total{job="x"} 1
+myapp_a_created 0
+myapp_b 2
+# EOF
+"#
+        .to_owned()),
+    );
+}
+
+#[test]
+fn test_prefix_invalid() {
+    let input = r#"a 1
+# EOF
+"#;
+    assert!(matches!(
+        check(|exposition| super::prefix(exposition, "0"), input),
+        Err(Error::Invalid(_)),
+    ));
+}
+
+#[test]
+fn test_convert_units() {
+    let input = r#"# TYPE a_milliseconds histogram
+# UNIT a_milliseconds milliseconds
+a_milliseconds_bucket{le="250"} 1 # {trace_id="t"} 125
+a_milliseconds_bucket{le="+Inf"} 2
+a_milliseconds_count 2
+a_milliseconds_sum 1500
+a_milliseconds_created 1700000000
+# TYPE b_kilobytes gauge
+b_kilobytes 2
+c_percent 50
+d 1
+e_milliseconds 9
+f_percent 7
+# EOF
+"#;
+    assert_eq!(
+        check(
+            |exposition| super::convert_units(exposition, super::CONVERSIONS),
+            input
+        ),
+        Ok(r#"# TYPE a_seconds histogram
+# UNIT a_seconds seconds
+a_seconds_bucket{le="0.25"} 1 # {trace_id="t"} 0.125
+a_seconds_bucket{le="+Inf"} 2
+a_seconds_count 2
+a_seconds_sum 1.5
+a_seconds_created 1700000000
+# TYPE b_bytes gauge
+b_bytes 2000
+c_ratio 0.5
+d 1
+e_seconds 0.009
+f_ratio 0.07
+# EOF
+"#
+        .to_owned()),
+    );
+}
+
+#[test]
+fn test_counter_to_gauge() {
+    let input = r#"# TYPE a counter
+# HELP a Help.
+a_total{job="x"} 1
+a_created{job="x"} 0
+b 2
+# EOF
+"#;
+    assert_eq!(
+        check(|exposition| super::counter_to_gauge(exposition, "a"), input),
+        Ok(r#"# TYPE a gauge
+# HELP a Help.
+a{job="x"} 1
+b 2
+# EOF
+"#
+        .to_owned()),
+    );
+}
+
+#[test]
+fn test_counter_to_gauge_exemplar() {
+    let input = r#"# TYPE a counter
+a_total 1 # {t="x"} 1
+a_total{x="y"} 2 3 # {t="x"} 1 3
+# EOF
+"#;
+    let output = check(|exposition| super::counter_to_gauge(exposition, "a"), input).unwrap();
+    assert_eq!(
+        output,
+        r#"# TYPE a gauge
+a 1
+a{x="y"} 2 3
+# EOF
+"#,
+    );
+    let (_, exposition) = crate::exposition::<_, NomError<_>>
+        .parse(output.as_str())
+        .finish()
+        .unwrap();
+    assert_eq!(crate::validate::validate(&exposition), []);
+}
+
+#[test]
+fn test_counter_to_gauge_merged() {
+    let input = r#"# TYPE a counter
+a_total 1
+# TYPE b gauge
+b 2
+# TYPE a counter
+a_total{x="y"} 3
+# EOF
+"#;
+    let (_, exposition) = crate::options::ParseOptions::default()
+        .merge_repeated_families(true)
+        .exposition::<_, NomError<_>>(input)
+        .finish()
+        .unwrap();
+    assert_eq!(
+        super::counter_to_gauge(&exposition, "a"),
+        Ok(r#"# TYPE a gauge
+a 1
+a{x="y"} 3
+# TYPE b gauge
+b 2
+# EOF
+"#
+        .to_owned()),
+    );
+}
+
+#[test]
+fn test_histogram_to_counters() {
+    let input = r#"# TYPE a histogram
+# UNIT a seconds
+# HELP a Help.
+a_bucket{le="1"} 1
+a_bucket{le="+Inf"} 2
+a_count 2
+a_sum 1.5
+a_created 0
+# EOF
+"#;
+    assert_eq!(
+        check(
+            |exposition| super::histogram_to_counters(exposition, "a"),
+            input
+        ),
+        Ok(r#"# TYPE a_count counter
+# HELP a_count Help.
+a_count_total 2
+a_count_created 0
+# TYPE a_sum counter
+# HELP a_sum Help.
+a_sum_total 1.5
+a_sum_created 0
+# EOF
+"#
+        .to_owned()),
+    );
+}
+
+#[test]
+fn test_summary_to_gauge() {
+    let input = r#"# TYPE a summary
+a{job="x",quantile="0.5"} 1
+a{job="x",quantile="0.99"} 2
+a{quantile="0.99"} 3
+a_count 2
+a_sum 3
+# EOF
+"#;
+    assert_eq!(
+        check(
+            |exposition| super::summary_to_gauge(exposition, "a", 0.99),
+            input
+        ),
+        Ok(r#"# TYPE a gauge
+a{job="x"} 2
+a 3
+# EOF
+"#
+        .to_owned()),
+    );
+}
+
+#[test]
+fn test_retype_unsupported() {
+    let input = r#"# TYPE a gauge
+a 1
+# EOF
+"#;
+    assert!(matches!(
+        check(|exposition| super::counter_to_gauge(exposition, "a"), input),
+        Err(Error::Unsupported(_)),
+    ));
+}
+
+#[test]
+fn test_fill_buckets() {
+    let input = r#"# TYPE a histogram
+a_bucket{job="x",le="1"} 1
+a_bucket{job="x",le="+Inf"} 3
+a_count{job="x"} 3
+a_sum{job="x"} 7
+a_bucket{job="y",le="0.5"} 2
+a_bucket{job="y",le="5"} 4
+a_bucket{job="y",le="+Inf"} 4
+a_count{job="y"} 4
+a_sum{job="y"} 6
+# EOF
+"#;
+    assert_eq!(
+        check(
+            |exposition| super::fill_buckets(exposition, "a", &[0.5, 1., 5.]),
+            input
+        ),
+        Ok(r#"# TYPE a histogram
+a_bucket{job="x",le="0.5"} 0
+a_bucket{job="x",le="1"} 1
+a_bucket{job="x",le="5"} 1
+a_bucket{job="x",le="+Inf"} 3
+a_count{job="x"} 3
+a_sum{job="x"} 7
+a_bucket{job="y",le="0.5"} 2
+a_bucket{job="y",le="1"} 2
+a_bucket{job="y",le="5"} 4
+a_bucket{job="y",le="+Inf"} 4
+a_count{job="y"} 4
+a_sum{job="y"} 6
+# EOF
+"#
+        .to_owned()),
+    );
+}
+
+#[test]
+fn test_rebucket() {
+    let input = r#"# TYPE a histogram
+a_bucket{le="0.1"} 1
+a_bucket{le="0.5"} 2
+a_bucket{le="1"} 3
+a_bucket{le="5"} 4
+a_bucket{le="+Inf"} 5
+a_count 5
+a_sum 10
+# EOF
+"#;
+    assert_eq!(
+        check(
+            |exposition| super::rebucket(exposition, "a", &[0.5, 5.]),
+            input
+        ),
+        Ok(r#"# TYPE a histogram
+a_bucket{le="0.5"} 2
+a_bucket{le="5"} 4
+a_bucket{le="+Inf"} 5
+a_count 5
+a_sum 10
+# EOF
+"#
+        .to_owned()),
+    );
+    assert!(matches!(
+        check(|exposition| super::rebucket(exposition, "a", &[2.]), input),
+        Err(Error::Unsupported(_)),
+    ));
+}
+
+#[test]
+fn test_truncate_label_values() {
+    let input = r#"a{b="0123456789a",c="short"} 1
+a{b="0123456789b",c="short"} 2
+a{b="0123\"56789"} 3
+# EOF
+"#;
+    assert_eq!(
+        check(
+            |exposition| super::truncate_label_values(exposition, 10),
+            input
+        ),
+        Ok(r#"a{b="0…58620469",c="short"} 1
+a{b="0…5561ffb0",c="short"} 2
+a{b="0123\"56789"} 3
+# EOF
+"#
+        .to_owned()),
+    );
+    assert_eq!(
+        check(
+            |exposition| super::truncate_label_values(exposition, 11),
+            input
+        ),
+        Ok(input.to_owned()),
+    );
+    assert!(matches!(
+        check(
+            |exposition| super::truncate_label_values(exposition, 8),
+            input
+        ),
+        Err(Error::Unsupported(_)),
+    ));
+
+    // distinct series becoming the same
+    let input = r#"a{b="0123456789a"} 1
+a{b="0…58620469"} 2
+a{b="0123456789a"} 3
+# EOF
+"#;
+    assert!(matches!(
+        check(
+            |exposition| super::truncate_label_values(exposition, 10),
+            input
+        ),
+        Err(Error::Unsupported(_)),
+    ));
+    let input = r#"a{b="0123456789a"} 1
+a{b="0123456789a"} 2
+b{b="0…58620469"} 3
+# EOF
+"#;
+    assert!(
+        check(
+            |exposition| super::truncate_label_values(exposition, 10),
+            input
+        )
+        .is_ok()
+    );
+}
+
+#[rstest::rstest]
+#[case(
+    false,
+    r#"a{job="j",instance="i\\"} 1
+b{c="d",job="j",instance="i\\"} 2
+c{exported_job="e",exported_exported_job="x",job="j",instance="i\\"} 3
+d{exported_instance="y",job="j",instance="i\\"} 4
+# EOF
+"#
+)]
+#[case(
+    true,
+    r#"a{job="j",instance="i\\"} 1
+b{c="d",job="j",instance="i\\"} 2
+c{exported_job="e",job="x",instance="i\\"} 3
+d{instance="y",job="j"} 4
+# EOF
+"#
+)]
+fn test_set_target(#[case] honor_labels: bool, #[case] expected: &str) {
+    let input = r#"a 1
+b{c="d"} 2
+c{exported_job="e",job="x"} 3
+d{instance="y"} 4
+# EOF
+"#;
+    let output = check(
+        |exposition| super::set_target(exposition, "j", "i\\", honor_labels),
+        input,
+    );
+    assert_eq!(output.as_deref(), Ok(expected));
+}
+
+#[rstest::rstest]
+#[case(
+    LabelConflict::Export,
+    r#"a{env="p",job="j"} 1
+b{c="d",exported_env="q",exported_region="r",env="p",job="j"} 2
+c{env="p",job="j"} 3
+# EOF
+"#
+)]
+#[case(
+    LabelConflict::Honor,
+    r#"a{env="p",job="j"} 1
+b{c="d",env="q",region="r",job="j"} 2
+c{env="",job="j"} 3
+# EOF
+"#
+)]
+#[case(
+    LabelConflict::Overwrite,
+    r#"a{env="p",job="j"} 1
+b{c="d",env="p",job="j"} 2
+c{env="p",job="j"} 3
+# EOF
+"#
+)]
+fn test_set_target_labels(#[case] conflict: LabelConflict, #[case] expected: &str) {
+    let input = r#"a 1
+b{c="d",env="q",region="r"} 2
+c{env=""} 3
+# EOF
+"#;
+    let output = check(
+        |exposition| {
+            super::set_target_labels(
+                exposition,
+                &[("env", "p"), ("job", "j"), ("region", "")],
+                conflict,
+            )
+        },
+        input,
+    );
+    assert_eq!(output.as_deref(), Ok(expected));
+}
@@ -0,0 +1,279 @@
+use crate::diagnostic::{Diagnostic, Severity};
+use crate::lint::{self, FamilyLine};
+use crate::{Exposition, MetricType, Timestamp};
+use nom::{Input, Offset};
+use std::collections::BTreeMap;
+
+pub const METADATA_ORDER: &str = "metadata-order";
+pub const FAMILY_PREFIX: &str = "family-prefix";
+pub const NAME_CLASH: &str = "name-clash";
+pub const EXEMPLAR_LENGTH: &str = "exemplar-length";
+pub const COUNTER_VALUE: &str = "counter-value";
+pub const EXEMPLAR_PLACEMENT: &str = "exemplar-placement";
+
+// https://github.com/prometheus/OpenMetrics/blob/main/specification/OpenMetrics.md
+//
+// The MUSTs of the specification the grammar does not enforce, ordered by span:
+// - family names are unique, see `lint::interleaved_families`;
+// - `metadata_order`, `family_prefix`, `name_clash`, `exemplar_length`, `counter_value` and
+//   `exemplar_placement`.
+// An exposition without any is valid.
+pub fn validate<I>(exposition: &Exposition<I>) -> Vec<Diagnostic>
+where
+    I: AsRef<str> + Input + Offset,
+{
+    let mut diagnostics = lint::interleaved_families(exposition);
+    diagnostics.extend(metadata_order(exposition));
+    diagnostics.extend(family_prefix(exposition));
+    diagnostics.extend(name_clash(exposition));
+    diagnostics.extend(exemplar_length(exposition));
+    diagnostics.extend(counter_value(exposition));
+    diagnostics.extend(exemplar_placement(exposition));
+    diagnostics.sort_by_key(|diagnostic| diagnostic.span.as_ref().map(|span| span.start));
+    diagnostics
+}
+
+// Flags descriptors following a sample of their family, e.g. `a 1` then `# TYPE a gauge`.
+pub fn metadata_order<I>(exposition: &Exposition<I>) -> Vec<Diagnostic>
+where
+    I: AsRef<str>,
+{
+    let mut current = None;
+    let mut samples = false;
+    let mut diagnostics = Vec::new();
+    for FamilyLine {
+        name,
+        range,
+        sample,
+        ..
+    } in lint::family_lines(&exposition.metricset)
+    {
+        if current != Some(name) {
+            current = Some(name);
+            samples = false;
+        }
+        if sample.is_some() {
+            samples = true;
+        } else if samples {
+            diagnostics.push(Diagnostic {
+                rule: METADATA_ORDER,
+                severity: Severity::Error,
+                message: format!("descriptor of `{name}` after its samples"),
+                span: Some(range.clone()),
+                family: Some(name.to_owned()),
+                series: None,
+            });
+        }
+    }
+    diagnostics
+}
+
+// Flags samples of a described family named after it with a suffix its type does not have, e.g.
+// `a` or `a_count` in a counter `a`. Families of a type without suffixes are not checked, a
+// suffixed sample being a family of its own, e.g. `a_total` after a gauge `a`.
+pub fn family_prefix<I>(exposition: &Exposition<I>) -> Vec<Diagnostic>
+where
+    I: AsRef<str>,
+{
+    let mut diagnostics = Vec::new();
+    for metricfamily in &exposition.metricset.metricfamily {
+        let Some(metric_descriptor) = metricfamily.metric_descriptor.first() else {
+            continue;
+        };
+        let name = metric_descriptor.metricname().as_ref();
+        let suffixes = metricfamily.metric_type().sample_suffixes();
+        if suffixes == [""] {
+            continue;
+        }
+        for sample in metricfamily.samples() {
+            let Some(suffix) = sample.metricname.as_ref().strip_prefix(name) else {
+                continue;
+            };
+            if (suffix.is_empty() || SUFFIXES.contains(&suffix)) && !suffixes.contains(&suffix) {
+                diagnostics.push(Diagnostic {
+                    rule: FAMILY_PREFIX,
+                    severity: Severity::Error,
+                    message: format!(
+                        "`{}` is not a sample name of the {:?} family `{name}`",
+                        sample.metricname.as_ref(),
+                        metricfamily.metric_type(),
+                    ),
+                    span: Some(sample.range.clone()),
+                    family: Some(name.to_owned()),
+                    series: Some(lint::series(sample)),
+                });
+            }
+        }
+    }
+    diagnostics
+}
+
+// Flags the first line of a family named like a sample of another one, e.g. a gauge `a_total`
+// and a counter `a`.
+pub fn name_clash<I>(exposition: &Exposition<I>) -> Vec<Diagnostic>
+where
+    I: AsRef<str>,
+{
+    let mut types = BTreeMap::new();
+    for metricfamily in &exposition.metricset.metricfamily {
+        for metric_descriptor in &metricfamily.metric_descriptor {
+            types.insert(
+                metric_descriptor.metricname().as_ref(),
+                metricfamily.metric_type(),
+            );
+        }
+    }
+    let mut firsts = BTreeMap::new();
+    for line in lint::family_lines(&exposition.metricset) {
+        firsts.entry(line.name).or_insert(line.range);
+    }
+    let mut diagnostics = Vec::new();
+    for (family, range) in &firsts {
+        let clash = firsts.keys().find(|other| {
+            let metric_type = types.get(*other).copied().unwrap_or(MetricType::Unknown);
+            family.strip_prefix(**other).is_some_and(|suffix| {
+                !suffix.is_empty() && metric_type.sample_suffixes().contains(&suffix)
+            })
+        });
+        if let Some(other) = clash {
+            diagnostics.push(Diagnostic {
+                rule: NAME_CLASH,
+                severity: Severity::Error,
+                message: format!("family `{family}` clashes with the samples of `{other}`"),
+                span: Some((*range).clone()),
+                family: Some((*family).to_owned()),
+                series: None,
+            });
+        }
+    }
+    diagnostics.sort_by_key(|diagnostic| diagnostic.span.as_ref().map(|span| span.start));
+    diagnostics
+}
+
+// Flags exemplars whose label names and values are longer than 128 characters in total.
+pub fn exemplar_length<I>(exposition: &Exposition<I>) -> Vec<Diagnostic>
+where
+    I: AsRef<str>,
+{
+    let mut diagnostics = Vec::new();
+    for metricfamily in &exposition.metricset.metricfamily {
+        for sample in metricfamily.samples() {
+            let Some(exemplar) = &sample.exemplar else {
+                continue;
+            };
+            let length = exemplar
+                .labels
+                .label
+                .iter()
+                .map(|label| {
+                    label.label_name.as_ref().chars().count()
+                        + label.escaped_string.unescape().chars().count()
+                })
+                .sum::<usize>();
+            if length > MAX_EXEMPLAR_LENGTH {
+                diagnostics.push(Diagnostic {
+                    rule: EXEMPLAR_LENGTH,
+                    severity: Severity::Error,
+                    message: format!(
+                        "exemplar labels of {length} characters, more than {MAX_EXEMPLAR_LENGTH}"
+                    ),
+                    span: Some(exemplar.labels.range.clone()),
+                    family: Some(metricfamily.family_name(sample).to_owned()),
+                    series: Some(lint::series(sample)),
+                });
+            }
+        }
+    }
+    diagnostics
+}
+
+// Flags the `_total` samples of counters that are not finite non-negative numbers, and the
+// `_created` samples that are not timestamps, e.g. `a_total NaN` or `a_created 1e99`.
+pub fn counter_value<I>(exposition: &Exposition<I>) -> Vec<Diagnostic>
+where
+    I: AsRef<str>,
+{
+    let mut diagnostics = Vec::new();
+    for metricfamily in &exposition.metricset.metricfamily {
+        if metricfamily.metric_type() != MetricType::Counter {
+            continue;
+        }
+        for sample in metricfamily.samples() {
+            let name = metricfamily.family_name(sample);
+            let number = sample.number.as_ref();
+            let message = match sample.metricname.as_ref().strip_prefix(name) {
+                Some("_total")
+                    if !crate::parse_number(number)
+                        .is_some_and(|value| value.is_finite() && value >= 0.) =>
+                {
+                    format!("counter value `{number}` is not a finite non-negative number")
+                }
+                Some("_created") if Timestamp::parse(number).is_none() => {
+                    format!("created value `{number}` is not a timestamp")
+                }
+                _ => continue,
+            };
+            diagnostics.push(Diagnostic {
+                rule: COUNTER_VALUE,
+                severity: Severity::Error,
+                message,
+                span: Some(sample.range.clone()),
+                family: Some(name.to_owned()),
+                series: Some(lint::series(sample)),
+            });
+        }
+    }
+    diagnostics
+}
+
+// Flags exemplars of samples other than the `_total` of a counter and the `_bucket` of a
+// histogram or gaugehistogram, e.g. of a gauge or of a histogram's `_count`.
+pub fn exemplar_placement<I>(exposition: &Exposition<I>) -> Vec<Diagnostic>
+where
+    I: AsRef<str>,
+{
+    let mut diagnostics = Vec::new();
+    for metricfamily in &exposition.metricset.metricfamily {
+        for sample in metricfamily.samples() {
+            let Some(exemplar) = &sample.exemplar else {
+                continue;
+            };
+            let name = metricfamily.family_name(sample);
+            let metricname = sample.metricname.as_ref();
+            let metric_type = metricfamily
+                .metric_descriptor
+                .first()
+                .filter(|metric_descriptor| metric_descriptor.metricname().as_ref() == name)
+                .map_or(MetricType::Unknown, |_| metricfamily.metric_type());
+            let allowed = matches!(
+                (metric_type, metricname.strip_prefix(name)),
+                (MetricType::Counter, Some("_total"))
+                    | (
+                        MetricType::Histogram | MetricType::Gaugehistogram,
+                        Some("_bucket")
+                    )
+            );
+            if !allowed {
+                diagnostics.push(Diagnostic {
+                    rule: EXEMPLAR_PLACEMENT,
+                    severity: Severity::Error,
+                    message: format!("exemplar on `{metricname}` of the {metric_type:?} `{name}`"),
+                    span: Some(exemplar.range.clone()),
+                    family: Some(name.to_owned()),
+                    series: Some(lint::series(sample)),
+                });
+            }
+        }
+    }
+    diagnostics
+}
+
+// of every type
+const SUFFIXES: [&str; 8] = [
+    "_total", "_created", "_bucket", "_count", "_sum", "_gcount", "_gsum", "_info",
+];
+
+const MAX_EXEMPLAR_LENGTH: usize = 128;
+
+#[cfg(test)]
+mod tests;
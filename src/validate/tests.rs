@@ -0,0 +1,121 @@
+use crate::diagnostic::Diagnostic;
+use nom::error::Error;
+use nom::{Finish, Parser};
+
+fn check<'a, F>(f: F, input: &'a str) -> Vec<(&'static str, String)>
+where
+    F: FnOnce(&crate::Exposition<&'a str>) -> Vec<Diagnostic>,
+{
+    let (_, exposition) = crate::exposition::<_, Error<_>>
+        .parse(input)
+        .finish()
+        .unwrap();
+    f(&exposition)
+        .into_iter()
+        .map(|diagnostic| (diagnostic.rule, input[diagnostic.span.unwrap()].to_owned()))
+        .collect()
+}
+
+#[test]
+fn test_validate() {
+    let input = r#"# TYPE a counter
+a_total 1
+# TYPE a_created gauge
+a_created 2
+b 3
+# TYPE b gauge
+b 4
+# TYPE c histogram
+c 4
+c_count{x="y"} 5 # {a="b"} 1
+a_total 6
+# EOF
+"#;
+    assert_eq!(
+        check(super::validate, input),
+        [
+            (super::NAME_CLASH, "# TYPE a_created gauge\n".to_owned()),
+            (super::METADATA_ORDER, "# TYPE b gauge\n".to_owned()),
+            (super::FAMILY_PREFIX, "c 4\n".to_owned()),
+            (super::EXEMPLAR_PLACEMENT, " # {a=\"b\"} 1".to_owned()),
+            (crate::lint::INTERLEAVED_FAMILIES, "a_total 6\n".to_owned(),),
+        ],
+    );
+}
+
+#[rstest::rstest]
+#[case("# TYPE a gauge\na 1\n# EOF\n")]
+#[case("# TYPE a counter\n# HELP a Help.\na_total 1\na_created 0\n# TYPE b gauge\nb 2\n# EOF\n")]
+#[case("# TYPE a gauge\na 1\na_total 2\n# EOF\n")]
+#[case("# TYPE a histogram\na_bucket{le=\"+Inf\"} 1 # {b=\"c\"} 1\na_count 1\n# EOF\n")]
+fn test_validate_valid(#[case] input: &str) {
+    assert_eq!(check(super::validate, input), []);
+}
+
+#[test]
+fn test_metadata_order() {
+    let input = "a 1\n# HELP a Help.\n# TYPE b gauge\nb 2\n# HELP b Help.\n# EOF\n";
+    assert_eq!(
+        check(super::metadata_order, input),
+        [
+            (super::METADATA_ORDER, "# HELP a Help.\n".to_owned()),
+            (super::METADATA_ORDER, "# HELP b Help.\n".to_owned()),
+        ],
+    );
+}
+
+#[rstest::rstest]
+#[case(128, false)]
+#[case(129, true)]
+fn test_exemplar_length(#[case] length: usize, #[case] flagged: bool) {
+    // `a` and a value of `length - 1` characters, one of which escaped
+    let value = format!("\\\"{}", "ü".repeat(length - 2));
+    let input = format!("# TYPE a counter\na_total 1 # {{a=\"{value}\"}} 1\n# EOF\n");
+    let diagnostics = check(super::exemplar_length, &input);
+    assert_eq!(diagnostics.len(), usize::from(flagged));
+}
+
+#[rstest::rstest]
+#[case("a_total 0", false)]
+#[case("a_total 1.5e3", false)]
+#[case("a_total -1", true)]
+#[case("a_total NaN", true)]
+#[case("a_total +Inf", true)]
+#[case("a_total -Inf", true)]
+#[case("a_created 1520879607.789", false)]
+#[case("a_created -1", false)]
+#[case("a_created NaN", true)]
+#[case("a_created 1e99", true)]
+fn test_counter_value(#[case] sample: &str, #[case] flagged: bool) {
+    let input = format!("# TYPE a counter\n{sample}\n# TYPE b gauge\nb -Inf\n# EOF\n");
+    let expected = if flagged {
+        vec![(super::COUNTER_VALUE, format!("{sample}\n"))]
+    } else {
+        Vec::new()
+    };
+    assert_eq!(check(super::counter_value, &input), expected);
+    assert_eq!(check(super::validate, &input), expected);
+}
+
+#[rstest::rstest]
+#[case("# TYPE a counter\na_total 1 # {} 1\n", None)]
+#[case("# TYPE a histogram\na_bucket{le=\"+Inf\"} 1 # {} 1\n", None)]
+#[case("# TYPE a gaugehistogram\na_bucket{le=\"+Inf\"} 1 # {} 1\n", None)]
+#[case("# TYPE a counter\na_created 1 # {} 1\n", Some(" # {} 1"))]
+#[case(
+    "# TYPE a histogram\na_count 1 # {x=\"y\"} 1 2\n",
+    Some(" # {x=\"y\"} 1 2")
+)]
+#[case("# TYPE a gauge\na 1 # {} 1\n", Some(" # {} 1"))]
+#[case("# TYPE a info\na_info 1 # {} 1\n", Some(" # {} 1"))]
+#[case("# TYPE a summary\na_count 1 # {} 1\n", Some(" # {} 1"))]
+#[case("# TYPE a stateset\na{a=\"b\"} 1 # {} 1\n", Some(" # {} 1"))]
+#[case("a_total 1 # {} 1\n", Some(" # {} 1"))]
+fn test_exemplar_placement(#[case] input: &str, #[case] exemplar: Option<&str>) {
+    let input = format!("{input}# EOF\n");
+    let expected = exemplar
+        .map(|exemplar| (super::EXEMPLAR_PLACEMENT, exemplar.to_owned()))
+        .into_iter()
+        .collect::<Vec<_>>();
+    assert_eq!(check(super::exemplar_placement, &input), expected);
+}